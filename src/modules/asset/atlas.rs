@@ -0,0 +1,307 @@
+use std::cmp::Reverse;
+
+use indexmap::IndexMap;
+
+use crate::modules::{
+  context::render::{Region, Texture},
+  enity::view::EnityView,
+  scene::NormalScene,
+};
+
+use super::{image::Image, AssetError, AssetHandle, AssetRegistry};
+
+///=========================================================================================
+/// Atlas
+///=========================================================================================
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRegion {
+  pub page: AssetHandle,
+  pub region: Region,
+}
+
+/// # 圖集
+/// 原圖 handle 對應到圖集頁面中的區域
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Atlas {
+  pages: Vec<AssetHandle>,
+  regions: IndexMap<AssetHandle, AtlasRegion>,
+}
+
+impl Atlas {
+  pub fn pages(&self) -> &Vec<AssetHandle> {
+    &self.pages
+  }
+  pub fn region(&self, handle: AssetHandle) -> Option<AtlasRegion> {
+    self.regions.get(&handle).copied()
+  }
+  pub fn regions(&self) -> &IndexMap<AssetHandle, AtlasRegion> {
+    &self.regions
+  }
+
+  /// texture drawn from atlas page instead, `None` if image is not packed
+  /// 將材質改為圖集區域
+  pub fn rewrite(&self, registry: &AssetRegistry, texture: &Texture) -> Option<Texture> {
+    let packed = |handle: Option<AssetHandle>, region: Option<Region>| {
+      let packed = self.region(handle?)?;
+      let region = match region {
+        Some(region) => Region::new(
+          packed.region.x + region.x,
+          packed.region.y + region.y,
+          region.width,
+          region.height,
+        ),
+        None => packed.region,
+      };
+      Some((packed.page, region))
+    };
+    let (page, region) = match texture {
+      Texture::Bitmap(path) => packed(registry.handle(path), None)?,
+      Texture::Region { source, region } => packed(registry.handle(source), Some(*region))?,
+      Texture::Asset(asset) => packed(Some(*asset), None)?,
+      Texture::AssetRegion { asset, region } => packed(Some(*asset), Some(*region))?,
+      Texture::NineSlice {
+        source,
+        region,
+        slice,
+      } => {
+        let (page, region) = packed(registry.handle(source), *region)?;
+        return Some(Texture::NineSlice {
+          source: registry.path(page)?.to_string(),
+          region: Some(region),
+          slice: *slice,
+        });
+      }
+      Texture::Styled { texture, style } => {
+        return Some(self.rewrite(registry, texture)?.with_style(style.clone()))
+      }
+      _ => return None,
+    };
+    Some(Texture::AssetRegion {
+      asset: page,
+      region,
+    })
+  }
+
+  pub fn apply_view(&self, registry: &AssetRegistry, view: &mut EnityView) {
+    for texture in view.textures_mut() {
+      if let Some(packed) = self.rewrite(registry, texture) {
+        *texture = packed;
+      }
+    }
+  }
+
+  /// rewrite textures of entities, background and tiles
+  pub fn apply_scene(&self, registry: &AssetRegistry, scene: &mut NormalScene) {
+    for track in scene.entities().values() {
+      self.apply_view(registry, &mut track.view_mut());
+    }
+    if let Some(background) = self.rewrite(registry, &scene.background()) {
+      scene.set_background(background);
+    }
+    if let Some(tilemap) = scene.tilemap_mut() {
+      let tileset = tilemap.tileset_mut();
+      for id in 0..tileset.len() as u32 {
+        let Some(tile) = tileset.get_mut(id) else {
+          continue;
+        };
+        if let Some(packed) = self.rewrite(registry, &tile.texture) {
+          tile.texture = packed;
+        }
+      }
+    }
+  }
+}
+
+///=========================================================================================
+/// AtlasPacker
+///=========================================================================================
+/// # 圖集打包
+/// 以列 (shelf) 排列, 由高到矮放入頁面, 圖片間和邊緣保留 padding
+/// 超過頁面大小的圖片獨佔一頁
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasPacker {
+  pub page_size: (u32, u32),
+  pub padding: u32,
+}
+
+impl Default for AtlasPacker {
+  fn default() -> Self {
+    AtlasPacker {
+      page_size: (1024, 1024),
+      padding: 2,
+    }
+  }
+}
+
+impl AtlasPacker {
+  pub fn new(width: u32, height: u32) -> Self {
+    AtlasPacker {
+      page_size: (width, height),
+      ..Default::default()
+    }
+  }
+  pub fn with_padding(mut self, padding: u32) -> Self {
+    self.padding = padding;
+    self
+  }
+
+  /// pack every png in registry
+  pub fn pack_all(&self, registry: &mut AssetRegistry) -> Result<Atlas, AssetError> {
+    let handles: Vec<AssetHandle> = registry
+      .iter()
+      .filter(|(_, asset)| asset.bytes().starts_with(b"\x89PNG"))
+      .map(|(handle, _)| *handle)
+      .collect();
+    self.pack(registry, &handles)
+  }
+
+  /// pack images into pages, pages are registered as `atlas/<n>.png`
+  pub fn pack(
+    &self,
+    registry: &mut AssetRegistry,
+    handles: &[AssetHandle],
+  ) -> Result<Atlas, AssetError> {
+    let mut images = Vec::with_capacity(handles.len());
+    for handle in handles {
+      if let Some(asset) = registry.get(*handle) {
+        images.push((*handle, Image::decode_png(asset.bytes())?));
+      }
+    }
+    images.sort_by_key(|(_, image)| Reverse(image.height()));
+
+    let padding = self.padding;
+    let (page_width, page_height) = self.page_size;
+    // placed images of each page
+    let mut pages: Vec<Vec<(usize, u32, u32)>> = vec![];
+    let (mut x, mut y, mut shelf) = (padding, padding, 0);
+    let mut current: Option<usize> = None;
+    for (index, (_, image)) in images.iter().enumerate() {
+      let (width, height) = (image.width(), image.height());
+      if width + padding * 2 > page_width || height + padding * 2 > page_height {
+        pages.push(vec![(index, padding, padding)]);
+        continue;
+      }
+      if x + width + padding > page_width {
+        (x, y, shelf) = (padding, y + shelf + padding, 0);
+      }
+      if current.is_none() || y + height + padding > page_height {
+        pages.push(vec![]);
+        current = Some(pages.len() - 1);
+        (x, y, shelf) = (padding, padding, 0);
+      }
+      let page = current.unwrap_or_default();
+      pages[page].push((index, x, y));
+      x += width + padding;
+      shelf = shelf.max(height);
+    }
+
+    let mut atlas = Atlas::default();
+    for (number, placed) in pages.iter().enumerate() {
+      let (width, height) = placed
+        .iter()
+        .fold((0, 0), |(width, height), (index, x, y)| {
+          let image = &images[*index].1;
+          (
+            width.max(x + image.width() + padding),
+            height.max(y + image.height() + padding),
+          )
+        });
+      let mut page = Image::new(width, height);
+      for (index, x, y) in placed {
+        page.blit(&images[*index].1, *x, *y);
+      }
+      let page = registry.load_bytes(&format!("atlas/{number}.png"), page.encode_png()?)?;
+      atlas.pages.push(page);
+      for (index, x, y) in placed {
+        let (handle, image) = &images[*index];
+        let region = Region::new(*x, *y, image.width(), image.height());
+        atlas.regions.insert(*handle, AtlasRegion { page, region });
+      }
+    }
+    Ok(atlas)
+  }
+}
+
+#[test]
+fn test() {
+  use crate::utils::{rect::Rect, vector::Vector};
+
+  let solid = |width: u32, height: u32, color: [u8; 4]| {
+    let mut image = Image::new(width, height);
+    for y in 0..height {
+      for x in 0..width {
+        image.set(x, y, color);
+      }
+    }
+    image
+  };
+  let mut registry = AssetRegistry::new();
+  let red = solid(10, 12, [255, 0, 0, 255]);
+  let decoded = Image::decode_png(&red.encode_png().unwrap()).unwrap();
+  assert_eq!(decoded, red);
+  let red = registry
+    .load_bytes("red.png", red.encode_png().unwrap())
+    .unwrap();
+  let green = registry
+    .load_bytes(
+      "green.png",
+      solid(8, 8, [0, 255, 0, 255]).encode_png().unwrap(),
+    )
+    .unwrap();
+  let big = registry
+    .load_bytes(
+      "big.png",
+      solid(40, 4, [0, 0, 255, 255]).encode_png().unwrap(),
+    )
+    .unwrap();
+
+  let atlas = AtlasPacker::new(32, 32)
+    .with_padding(1)
+    .pack_all(&mut registry)
+    .unwrap();
+  // big image gets its own page
+  assert_eq!(atlas.pages().len(), 2);
+  let red_region = atlas.region(red).unwrap();
+  let green_region = atlas.region(green).unwrap();
+  assert_eq!(red_region.page, green_region.page);
+  assert_eq!(red_region.region, Region::new(1, 1, 10, 12));
+  assert_eq!(green_region.region, Region::new(12, 1, 8, 8));
+  assert_eq!(atlas.region(big).unwrap().region, Region::new(1, 1, 40, 4));
+
+  let page = registry.get(green_region.page).unwrap();
+  assert_eq!(page.size(), (21, 14));
+  let page = Image::decode_png(page.bytes()).unwrap();
+  assert_eq!(page.get(12, 1), Some([0, 255, 0, 255]));
+  assert_eq!(page.get(11, 1), Some([0, 0, 0, 0]));
+
+  let rect = Rect::new(Vector::ORIGIN, Vector::new(10., 10.));
+  let mut view = EnityView::new(
+    vec![
+      (rect, Texture::Bitmap("green.png".to_string())),
+      (
+        rect,
+        Texture::region("red.png", Region::new(2, 2, 4, 4)).with_flip(true, false),
+      ),
+      (rect, Texture::default()),
+    ],
+    vec![],
+  );
+  atlas.apply_view(&registry, &mut view);
+  let part = view.part("base").unwrap();
+  assert_eq!(
+    part[0].1,
+    Texture::AssetRegion {
+      asset: green_region.page,
+      region: green_region.region
+    }
+  );
+  assert_eq!(
+    part[1].1.inner(),
+    &Texture::AssetRegion {
+      asset: red_region.page,
+      region: Region::new(3, 3, 4, 4)
+    }
+  );
+  assert!(part[1].1.style().flip_x);
+  assert_eq!(part[2].1, Texture::default());
+}
@@ -0,0 +1,333 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+  modules::context::render::{Region, RenderFrame, Texture},
+  utils::{
+    rect::Rect,
+    scalar::{scalar, Scalar},
+    vector::Vector,
+  },
+};
+
+use super::AssetError;
+
+/// most pages of a font, larger ids are rejected
+const MAX_PAGES: u32 = 256;
+
+/// glyph of bitmap font, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+  pub region: Region,
+  /// from pen position to glyph left-top
+  pub offset: (i32, i32),
+  pub advance: i32,
+  pub page: usize,
+}
+
+/// # 點陣字型
+/// 讀取 BMFont (AngelCode) 文字格式, 每個字元輸出一個圖集區域矩形
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BitmapFont {
+  face: String,
+  size: i32,
+  line_height: i32,
+  base: i32,
+  pages: Vec<String>,
+  glyphs: HashMap<char, Glyph>,
+  kernings: HashMap<(char, char), i32>,
+}
+
+impl BitmapFont {
+  /// load `.fnt` file, page paths are resolved by font directory
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+    let path = path.as_ref();
+    let mut font = BitmapFont::parse(&fs::read_to_string(path)?)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    for page in font.pages.iter_mut() {
+      *page = base.join(&page).to_string_lossy().into_owned();
+    }
+    Ok(font)
+  }
+
+  pub fn parse(source: &str) -> Result<Self, AssetError> {
+    let mut font = BitmapFont::default();
+    for (number, line) in source.lines().enumerate() {
+      let mut tokens = tokenize(line).into_iter();
+      let Some((tag, _)) = tokens.next() else {
+        continue;
+      };
+      let values: HashMap<String, String> = tokens.collect();
+      let text = |key: &str| values.get(key).cloned().unwrap_or_default();
+      let int = |key: &str| -> Result<i32, AssetError> {
+        match values.get(key) {
+          Some(value) => value
+            .parse()
+            .map_err(|_| AssetError::Font(format!("line {}: `{key}` is not a number", number + 1))),
+          None => Ok(0),
+        }
+      };
+      let unsigned = |key: &str| -> Result<u32, AssetError> {
+        u32::try_from(int(key)?)
+          .map_err(|_| AssetError::Font(format!("line {}: `{key}` is negative", number + 1)))
+      };
+      let page_of = |key: &str| -> Result<usize, AssetError> {
+        match unsigned(key)? {
+          page if page < MAX_PAGES => Ok(page as usize),
+          page => Err(AssetError::Font(format!(
+            "line {}: page {page} is too large",
+            number + 1
+          ))),
+        }
+      };
+      let char_of = |key: &str| {
+        char::from_u32(unsigned(key)?)
+          .ok_or_else(|| AssetError::Font(format!("line {}: `{key}` is not a char", number + 1)))
+      };
+
+      match tag.as_str() {
+        "info" => {
+          font.face = text("face");
+          font.size = int("size")?.abs();
+        }
+        "common" => {
+          font.line_height = int("lineHeight")?;
+          font.base = int("base")?;
+        }
+        "page" => {
+          let id = page_of("id")?;
+          if font.pages.len() <= id {
+            font.pages.resize(id + 1, String::new());
+          }
+          font.pages[id] = text("file");
+        }
+        "char" => {
+          let glyph = Glyph {
+            region: Region::new(
+              unsigned("x")?,
+              unsigned("y")?,
+              unsigned("width")?,
+              unsigned("height")?,
+            ),
+            offset: (int("xoffset")?, int("yoffset")?),
+            advance: int("xadvance")?,
+            page: page_of("page")?,
+          };
+          font.glyphs.insert(char_of("id")?, glyph);
+        }
+        "kerning" => {
+          let pair = (char_of("first")?, char_of("second")?);
+          font.kernings.insert(pair, int("amount")?);
+        }
+        _ => {}
+      }
+    }
+    if font.glyphs.is_empty() {
+      return Err(AssetError::Font("no char in font".to_string()));
+    }
+    Ok(font)
+  }
+
+  //================================================================================
+  // Lookup
+  //================================================================================
+  pub fn face(&self) -> &str {
+    &self.face
+  }
+  pub fn size(&self) -> i32 {
+    self.size
+  }
+  pub fn line_height(&self) -> i32 {
+    self.line_height
+  }
+  /// from line top to baseline
+  pub fn base(&self) -> i32 {
+    self.base
+  }
+  pub fn pages(&self) -> &Vec<String> {
+    &self.pages
+  }
+  /// glyph of char, `?` for missing char
+  pub fn glyph(&self, char: char) -> Option<&Glyph> {
+    self.glyphs.get(&char).or_else(|| self.glyphs.get(&'?'))
+  }
+  pub fn kerning(&self, first: char, second: char) -> i32 {
+    self.kernings.get(&(first, second)).copied().unwrap_or(0)
+  }
+
+  //================================================================================
+  // Layout
+  //================================================================================
+  /// glyph regions in pixels, left-top of text is origin and y is down
+  fn glyphs(&self, text: &str) -> Vec<(i32, i32, &Glyph)> {
+    let mut placed = vec![];
+    let (mut pen, mut line) = (0, 0);
+    let mut previous = None;
+    for char in text.chars() {
+      if char == '\n' {
+        (pen, line, previous) = (0, line + 1, None);
+        continue;
+      }
+      let Some(glyph) = self.glyph(char) else {
+        continue;
+      };
+      if let Some(previous) = previous {
+        pen += self.kerning(previous, char);
+      }
+      placed.push((
+        pen + glyph.offset.0,
+        line * self.line_height + glyph.offset.1,
+        glyph,
+      ));
+      pen += glyph.advance;
+      previous = Some(char);
+    }
+    placed
+  }
+
+  /// size of text block in pixels
+  pub fn measure(&self, text: &str) -> (i32, i32) {
+    let mut width = 0;
+    for line in text.split('\n') {
+      let mut pen = 0;
+      let mut previous = None;
+      for char in line.chars() {
+        let Some(glyph) = self.glyph(char) else {
+          continue;
+        };
+        if let Some(previous) = previous {
+          pen += self.kerning(previous, char);
+        }
+        pen += glyph.advance;
+        previous = Some(char);
+      }
+      width = width.max(pen);
+    }
+    (width, text.split('\n').count() as i32 * self.line_height)
+  }
+
+  /// one rect per glyph, origin is left-top of text, scale is units per pixel
+  /// 排版文字, 每個字元一個矩形, 可作為實體部件或 UI
+  pub fn layout(&self, text: &str, origin: Vector, scale: Scalar) -> Vec<(Rect, Texture)> {
+    let pixel = |value: i32| scalar(value as f32) * scale;
+    self
+      .glyphs(text)
+      .into_iter()
+      .filter(|(_, _, glyph)| glyph.region.width > 0 && glyph.region.height > 0)
+      .map(|(x, y, glyph)| {
+        let (width, height) = (glyph.region.width as i32, glyph.region.height as i32);
+        let center = Vector(
+          pixel(x) + pixel(width) / scalar(2.),
+          -(pixel(y) + pixel(height) / scalar(2.)),
+        );
+        let rect = Rect::new(origin + center, Vector(pixel(width), pixel(height)));
+        let source = self.pages.get(glyph.page).cloned().unwrap_or_default();
+        (
+          rect,
+          Texture::Region {
+            source,
+            region: glyph.region,
+          },
+        )
+      })
+      .collect()
+  }
+
+  pub fn render(&self, frame: &mut RenderFrame, text: &str, origin: Vector, scale: Scalar) {
+    frame.append(&self.layout(text, origin, scale));
+  }
+  pub fn render_ui(&self, frame: &mut RenderFrame, text: &str, origin: Vector, scale: Scalar) {
+    frame.append_ui(&self.layout(text, origin, scale));
+  }
+}
+
+/// `tag key=value key="quoted value"`
+fn tokenize(line: &str) -> Vec<(String, String)> {
+  let mut tokens = vec![];
+  let mut chars = line.trim().chars().peekable();
+  while chars.peek().is_some() {
+    while chars.next_if(|char| char.is_whitespace()).is_some() {}
+    let mut key = String::new();
+    while let Some(char) = chars.next_if(|char| !char.is_whitespace() && *char != '=') {
+      key.push(char);
+    }
+    let mut value = String::new();
+    if chars.next_if_eq(&'=').is_some() {
+      if chars.next_if_eq(&'"').is_some() {
+        for char in chars.by_ref() {
+          if char == '"' {
+            break;
+          }
+          value.push(char);
+        }
+      } else {
+        while let Some(char) = chars.next_if(|char| !char.is_whitespace()) {
+          value.push(char);
+        }
+      }
+    }
+    if !key.is_empty() {
+      tokens.push((key, value));
+    }
+  }
+  tokens
+}
+
+#[test]
+fn test() {
+  let source = r#"info face="Pixel Font" size=-8 bold=0
+common lineHeight=10 base=8 scaleW=64 scaleH=64 pages=1
+page id=0 file="pixel font.png"
+chars count=3
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=4 page=0 chnl=15
+char id=65 x=0 y=0 width=6 height=8 xoffset=0 yoffset=1 xadvance=7 page=0 chnl=15
+char id=86 x=8 y=0 width=6 height=8 xoffset=1 yoffset=1 xadvance=7 page=0 chnl=15
+char id=63 x=16 y=0 width=4 height=8 xoffset=0 yoffset=1 xadvance=5 page=0 chnl=15
+kernings count=1
+kerning first=65 second=86 amount=-2
+"#;
+  let font = BitmapFont::parse(source).unwrap();
+  assert_eq!(font.face(), "Pixel Font");
+  assert_eq!((font.size(), font.line_height(), font.base()), (8, 10, 8));
+  assert_eq!(font.pages(), &vec!["pixel font.png".to_string()]);
+  assert_eq!(font.kerning('A', 'V'), -2);
+  assert_eq!(font.glyph('Z').unwrap().region.x, 16);
+
+  // 7 - 2 + 7 + 4 (space) + 7 / second line
+  assert_eq!(font.measure("AV A\nA"), (23, 20));
+
+  let rects = font.layout("AV\nA", Vector::new(100., 0.), scalar(2.));
+  assert_eq!(rects.len(), 3);
+  let (first, texture) = &rects[0];
+  assert_eq!(
+    texture,
+    &Texture::region("pixel font.png", Region::new(0, 0, 6, 8))
+  );
+  assert_eq!(first.size, Vector::new(12., 16.));
+  assert_eq!(first.position, Vector::new(106., -10.));
+  // kerning moves V left: pen 5 + offset 1
+  assert_eq!(rects[1].0.position, Vector::new(100. + 12. + 6., -10.));
+  // second line is line height lower
+  assert_eq!(rects[2].0.position, Vector::new(106., -30.));
+
+  let mut frame = RenderFrame::new();
+  font.render_ui(&mut frame, "A V", Vector::ORIGIN, scalar(1.));
+  assert_eq!(frame.get_ui().len(), 2);
+  assert!(frame.get().is_empty());
+  assert!(BitmapFont::parse("info face=x").is_err());
+
+  // negative or huge ids and regions
+  let glyph = "char id=65 x=0 y=0 width=6 height=8 page=0\n";
+  for line in [
+    "page id=-1 file=a.png\n",
+    "page id=99999999 file=a.png\n",
+    "char id=66 x=0 y=0 width=6 height=8 page=-1\n",
+    "char id=66 x=0 y=0 width=6 height=8 page=1000\n",
+    "char id=66 x=-4 y=0 width=6 height=8 page=0\n",
+    "char id=66 x=0 y=0 width=6 height=-8 page=0\n",
+    "char id=-66 x=0 y=0 width=6 height=8 page=0\n",
+  ] {
+    let error = BitmapFont::parse(&format!("{glyph}{line}")).unwrap_err();
+    assert!(matches!(error, AssetError::Font(_)), "{line}");
+  }
+  assert!(BitmapFont::parse(glyph).is_ok());
+}
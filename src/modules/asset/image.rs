@@ -0,0 +1,151 @@
+use super::AssetError;
+
+/// # 圖像緩衝
+/// RGBA8, 左上為原點, 逐列儲存
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+  width: u32,
+  height: u32,
+  pixels: Vec<u8>,
+}
+
+impl Image {
+  /// transparent image
+  pub fn new(width: u32, height: u32) -> Self {
+    Image {
+      width,
+      height,
+      pixels: vec![0; width as usize * height as usize * 4],
+    }
+  }
+  pub fn from_pixels(width: u32, height: u32, pixels: Vec<u8>) -> Option<Self> {
+    match pixels.len() == width as usize * height as usize * 4 {
+      true => Some(Image {
+        width,
+        height,
+        pixels,
+      }),
+      false => None,
+    }
+  }
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+  pub fn pixels(&self) -> &[u8] {
+    &self.pixels
+  }
+  pub fn pixels_mut(&mut self) -> &mut [u8] {
+    &mut self.pixels
+  }
+
+  fn index(&self, x: u32, y: u32) -> Option<usize> {
+    match x < self.width && y < self.height {
+      true => Some((y as usize * self.width as usize + x as usize) * 4),
+      false => None,
+    }
+  }
+  pub fn get(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+    let index = self.index(x, y)?;
+    self.pixels[index..index + 4].try_into().ok()
+  }
+  pub fn set(&mut self, x: u32, y: u32, color: [u8; 4]) {
+    if let Some(index) = self.index(x, y) {
+      self.pixels[index..index + 4].copy_from_slice(&color);
+    }
+  }
+
+  /// copy whole image at position, clipped
+  pub fn blit(&mut self, source: &Image, x: u32, y: u32) {
+    let width = source.width.min(self.width.saturating_sub(x)) as usize;
+    for row in 0..source.height.min(self.height.saturating_sub(y)) {
+      let (Some(to), Some(from)) = (self.index(x, y + row), source.index(0, row)) else {
+        continue;
+      };
+      self.pixels[to..to + width * 4].copy_from_slice(&source.pixels[from..from + width * 4]);
+    }
+  }
+
+  /// draw image over this one with alpha, clipped
+  pub fn draw(&mut self, source: &Image, x: i32, y: i32) {
+    for row in 0..source.height {
+      for column in 0..source.width {
+        let (Some(to_x), Some(to_y)) = (x.checked_add(column as i32), y.checked_add(row as i32))
+        else {
+          continue;
+        };
+        if to_x < 0 || to_y < 0 {
+          continue;
+        }
+        if let Some(color) = source.get(column, row) {
+          self.blend(to_x as u32, to_y as u32, color);
+        }
+      }
+    }
+  }
+
+  /// source-over blend of one pixel
+  pub fn blend(&mut self, x: u32, y: u32, color: [u8; 4]) {
+    let Some(index) = self.index(x, y) else {
+      return;
+    };
+    let alpha = color[3] as f32 / 255.;
+    if alpha <= 0. {
+      return;
+    }
+    let under = &mut self.pixels[index..index + 4];
+    let under_alpha = under[3] as f32 / 255.;
+    let out_alpha = alpha + under_alpha * (1. - alpha);
+    for channel in 0..3 {
+      let mixed =
+        color[channel] as f32 * alpha + under[channel] as f32 * under_alpha * (1. - alpha);
+      under[channel] = (mixed / out_alpha).round() as u8;
+    }
+    under[3] = (out_alpha * 255.).round() as u8;
+  }
+
+  //================================================================================
+  // PNG
+  //================================================================================
+  pub fn decode_png(bytes: &[u8]) -> Result<Self, AssetError> {
+    let error = |error: png::DecodingError| AssetError::Image(error.to_string());
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(error)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(error)?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = match info.color_type {
+      png::ColorType::Rgba => buffer,
+      png::ColorType::Rgb => buffer
+        .chunks_exact(3)
+        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+        .collect(),
+      png::ColorType::GrayscaleAlpha => buffer
+        .chunks_exact(2)
+        .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+        .collect(),
+      png::ColorType::Grayscale => buffer.iter().flat_map(|g| [*g, *g, *g, 255]).collect(),
+      png::ColorType::Indexed => {
+        return Err(AssetError::Image("indexed png not expanded".to_string()));
+      }
+    };
+    Image::from_pixels(info.width, info.height, pixels)
+      .ok_or_else(|| AssetError::Image("png size mismatch".to_string()))
+  }
+
+  pub fn encode_png(&self) -> Result<Vec<u8>, AssetError> {
+    let error = |error: png::EncodingError| AssetError::Image(error.to_string());
+    let mut bytes = vec![];
+    let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(error)?;
+    writer.write_image_data(&self.pixels).map_err(error)?;
+    writer.finish().map_err(error)?;
+    Ok(bytes)
+  }
+}
@@ -0,0 +1,408 @@
+use std::{
+  collections::{hash_map::DefaultHasher, HashMap},
+  fmt, fs,
+  hash::{Hash, Hasher},
+  path::Path,
+};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use super::context::render::Texture;
+
+pub mod atlas;
+pub mod font;
+pub mod image;
+pub mod text;
+
+///=========================================================================================
+/// Error
+///=========================================================================================
+#[derive(Debug)]
+pub enum AssetError {
+  Io(std::io::Error),
+  Pack(postcard::Error),
+  /// not a png, gif, bmp or jpeg image
+  Format(String),
+  /// image decode or encode failed
+  Image(String),
+  /// bitmap font file
+  Font(String),
+}
+
+impl fmt::Display for AssetError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      AssetError::Io(error) => write!(f, "asset io error: {error}"),
+      AssetError::Pack(error) => write!(f, "asset pack error: {error}"),
+      AssetError::Format(path) => write!(f, "asset unknown image format: {path}"),
+      AssetError::Image(error) => write!(f, "asset image error: {error}"),
+      AssetError::Font(error) => write!(f, "asset font error: {error}"),
+    }
+  }
+}
+
+impl std::error::Error for AssetError {}
+
+impl From<std::io::Error> for AssetError {
+  fn from(error: std::io::Error) -> Self {
+    AssetError::Io(error)
+  }
+}
+
+impl From<postcard::Error> for AssetError {
+  fn from(error: postcard::Error) -> Self {
+    AssetError::Pack(error)
+  }
+}
+
+///=========================================================================================
+/// Handle
+///=========================================================================================
+/// compact id of loaded image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AssetHandle(pub u32);
+
+/// # 圖片資源
+#[derive(Debug, Clone)]
+pub struct Asset {
+  path: String,
+  /// pixel size
+  size: (u32, u32),
+  bytes: Vec<u8>,
+  refs: u32,
+}
+
+impl Asset {
+  pub fn path(&self) -> &str {
+    &self.path
+  }
+  pub fn size(&self) -> (u32, u32) {
+    self.size
+  }
+  pub fn bytes(&self) -> &[u8] {
+    &self.bytes
+  }
+  pub fn refs(&self) -> u32 {
+    self.refs
+  }
+}
+
+/// handle, path and pixel size for frontends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetEntry {
+  pub handle: AssetHandle,
+  pub path: String,
+  pub size: (u32, u32),
+}
+
+///=========================================================================================
+/// AssetPack
+///=========================================================================================
+/// # 資源包
+/// 記憶體中的檔案, 以路徑為鍵, 可用 postcard 存取
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetPack {
+  files: IndexMap<String, Vec<u8>>,
+}
+
+impl AssetPack {
+  pub fn new() -> Self {
+    Self::default()
+  }
+  pub fn insert(&mut self, path: &str, bytes: Vec<u8>) {
+    self.files.insert(normalize(path), bytes);
+  }
+  pub fn get(&self, path: &str) -> Option<&Vec<u8>> {
+    self.files.get(&normalize(path))
+  }
+  pub fn len(&self) -> usize {
+    self.files.len()
+  }
+  pub fn is_empty(&self) -> bool {
+    self.files.is_empty()
+  }
+  pub fn to_bytes(&self) -> Result<Vec<u8>, AssetError> {
+    Ok(postcard::to_stdvec(self)?)
+  }
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, AssetError> {
+    Ok(postcard::from_bytes(bytes)?)
+  }
+}
+
+///=========================================================================================
+/// AssetRegistry
+///=========================================================================================
+/// # 資源註冊表
+/// 依路徑載入圖片, 同路徑或同內容共用 handle, 以引用計數卸載
+/// 先找已掛載的資源包, 找不到再讀取磁碟
+#[derive(Debug, Clone, Default)]
+pub struct AssetRegistry {
+  next: u32,
+  assets: IndexMap<AssetHandle, Asset>,
+  paths: HashMap<String, AssetHandle>,
+  contents: HashMap<u64, Vec<AssetHandle>>,
+  packs: Vec<AssetPack>,
+}
+
+impl AssetRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// later mounted pack wins
+  pub fn mount(&mut self, pack: AssetPack) {
+    self.packs.push(pack);
+  }
+
+  //================================================================================
+  // Load
+  //================================================================================
+  /// load image, or add reference to loaded one
+  pub fn load(&mut self, path: &str) -> Result<AssetHandle, AssetError> {
+    let path = normalize(path);
+    if let Some(handle) = self.paths.get(&path).copied() {
+      self.retain(handle);
+      return Ok(handle);
+    }
+    let bytes = match self.packs.iter().rev().find_map(|pack| pack.get(&path)) {
+      Some(bytes) => bytes.clone(),
+      None => fs::read(Path::new(&path))?,
+    };
+    self.insert(&path, bytes)
+  }
+
+  /// register image bytes under path
+  pub fn load_bytes(&mut self, path: &str, bytes: Vec<u8>) -> Result<AssetHandle, AssetError> {
+    let path = normalize(path);
+    if let Some(handle) = self.paths.get(&path).copied() {
+      self.retain(handle);
+      return Ok(handle);
+    }
+    self.insert(&path, bytes)
+  }
+
+  fn insert(&mut self, path: &str, bytes: Vec<u8>) -> Result<AssetHandle, AssetError> {
+    let size = image_size(&bytes).ok_or_else(|| AssetError::Format(path.to_string()))?;
+
+    // same content under other path
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    let hash = hasher.finish();
+    let same = self.contents.get(&hash).and_then(|handles| {
+      handles
+        .iter()
+        .find(|handle| self.assets[*handle].bytes == bytes)
+        .copied()
+    });
+    if let Some(handle) = same {
+      self.paths.insert(path.to_string(), handle);
+      self.retain(handle);
+      return Ok(handle);
+    }
+
+    let handle = AssetHandle(self.next);
+    self.next += 1;
+    self.assets.insert(
+      handle,
+      Asset {
+        path: path.to_string(),
+        size,
+        bytes,
+        refs: 1,
+      },
+    );
+    self.paths.insert(path.to_string(), handle);
+    self.contents.entry(hash).or_default().push(handle);
+    Ok(handle)
+  }
+
+  //================================================================================
+  // Reference
+  //================================================================================
+  pub fn retain(&mut self, handle: AssetHandle) {
+    if let Some(asset) = self.assets.get_mut(&handle) {
+      asset.refs += 1;
+    }
+  }
+
+  /// drop one reference, return true if unloaded
+  pub fn release(&mut self, handle: AssetHandle) -> bool {
+    let Some(asset) = self.assets.get_mut(&handle) else {
+      return false;
+    };
+    asset.refs = asset.refs.saturating_sub(1);
+    if asset.refs > 0 {
+      return false;
+    }
+    self.unload(handle);
+    true
+  }
+
+  /// unload regardless of references
+  pub fn unload(&mut self, handle: AssetHandle) -> Option<Asset> {
+    let asset = self.assets.shift_remove(&handle)?;
+    self.paths.retain(|_, other| *other != handle);
+    self.contents.retain(|_, handles| {
+      handles.retain(|other| *other != handle);
+      !handles.is_empty()
+    });
+    Some(asset)
+  }
+
+  //================================================================================
+  // Lookup
+  //================================================================================
+  pub fn get(&self, handle: AssetHandle) -> Option<&Asset> {
+    self.assets.get(&handle)
+  }
+  pub fn handle(&self, path: &str) -> Option<AssetHandle> {
+    self.paths.get(&normalize(path)).copied()
+  }
+  pub fn size(&self, handle: AssetHandle) -> Option<(u32, u32)> {
+    self.assets.get(&handle).map(Asset::size)
+  }
+  pub fn path(&self, handle: AssetHandle) -> Option<&str> {
+    self.assets.get(&handle).map(Asset::path)
+  }
+  pub fn len(&self) -> usize {
+    self.assets.len()
+  }
+  pub fn is_empty(&self) -> bool {
+    self.assets.is_empty()
+  }
+  pub fn iter(&self) -> impl Iterator<Item = (&AssetHandle, &Asset)> {
+    self.assets.iter()
+  }
+  /// loaded assets for frontends to resolve handles
+  pub fn manifest(&self) -> Vec<AssetEntry> {
+    self
+      .assets
+      .iter()
+      .map(|(handle, asset)| AssetEntry {
+        handle: *handle,
+        path: asset.path.clone(),
+        size: asset.size,
+      })
+      .collect()
+  }
+
+  //================================================================================
+  // Texture
+  //================================================================================
+  /// load images by path in texture and reference them by handle
+  /// 將材質中的圖片路徑換成 handle
+  pub fn resolve(&mut self, texture: &Texture) -> Result<Texture, AssetError> {
+    Ok(match texture {
+      Texture::Bitmap(path) => Texture::Asset(self.load(path)?),
+      Texture::Region { source, region } => Texture::AssetRegion {
+        asset: self.load(source)?,
+        region: *region,
+      },
+      Texture::Styled { texture, style } => self.resolve(texture)?.with_style(style.clone()),
+      texture => texture.clone(),
+    })
+  }
+}
+
+/// same file by different spelling, e.g. `./a.png` and `a.png`
+fn normalize(path: &str) -> String {
+  let path = path.replace('\\', "/");
+  let mut parts: Vec<&str> = vec![];
+  for part in path.split('/') {
+    match part {
+      "." => {}
+      "" if !parts.is_empty() => {}
+      ".."
+        if parts
+          .last()
+          .is_some_and(|last| *last != ".." && !last.is_empty()) =>
+      {
+        parts.pop();
+      }
+      part => parts.push(part),
+    }
+  }
+  parts.join("/")
+}
+
+/// pixel size from image header
+fn image_size(bytes: &[u8]) -> Option<(u32, u32)> {
+  let be = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+  let le16 = |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32);
+  let le32 = |at: usize| Some(i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+
+  if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+    return Some((be(16)?, be(20)?));
+  }
+  if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+    return Some((le16(6)?, le16(8)?));
+  }
+  if bytes.starts_with(b"BM") {
+    return Some((le32(18)?.unsigned_abs(), le32(22)?.unsigned_abs()));
+  }
+  if bytes.starts_with(&[0xFF, 0xD8]) {
+    // walk segments to start of frame
+    let mut at = 2;
+    while at + 9 < bytes.len() {
+      if bytes[at] != 0xFF {
+        return None;
+      }
+      let marker = bytes[at + 1];
+      let length = u16::from_be_bytes([bytes[at + 2], bytes[at + 3]]) as usize;
+      if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+        let height = u16::from_be_bytes([bytes[at + 5], bytes[at + 6]]) as u32;
+        let width = u16::from_be_bytes([bytes[at + 7], bytes[at + 8]]) as u32;
+        return Some((width, height));
+      }
+      at += 2 + length;
+    }
+  }
+  None
+}
+
+#[test]
+fn test() {
+  use crate::modules::context::render::Region;
+
+  let png = |width: u32, height: u32| {
+    let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    bytes.extend(width.to_be_bytes());
+    bytes.extend(height.to_be_bytes());
+    bytes
+  };
+  let mut pack = AssetPack::new();
+  pack.insert("sprites/hero.png", png(32, 48));
+  pack.insert("sprites/copy.png", png(32, 48));
+  pack.insert("sprites/tile.png", png(16, 16));
+  let pack = AssetPack::from_bytes(&pack.to_bytes().unwrap()).unwrap();
+
+  let mut registry = AssetRegistry::new();
+  registry.mount(pack);
+  let hero = registry.load("./sprites/hero.png").unwrap();
+  assert_eq!(registry.size(hero), Some((32, 48)));
+  // same path and same content share handle
+  assert_eq!(registry.load("sprites/../sprites/hero.png").unwrap(), hero);
+  assert_eq!(registry.load("sprites/copy.png").unwrap(), hero);
+  assert_eq!(registry.get(hero).unwrap().refs(), 3);
+  assert!(registry.load("missing.png").is_err());
+  assert!(matches!(
+    registry.load_bytes("text.png", b"hello".to_vec()),
+    Err(AssetError::Format(_))
+  ));
+
+  let texture = Texture::region("sprites/tile.png", Region::new(0, 0, 8, 8)).with_opacity(0.5);
+  let resolved = registry.resolve(&texture).unwrap();
+  let tile = registry.handle("sprites/tile.png").unwrap();
+  assert_eq!(resolved.asset(), Some(tile));
+  assert_eq!(resolved.style().opacity, 0.5);
+  assert_eq!(registry.manifest().len(), 2);
+
+  assert!(!registry.release(hero));
+  assert!(!registry.release(hero));
+  assert!(registry.release(hero));
+  assert!(registry.get(hero).is_none());
+  assert_eq!(registry.handle("sprites/copy.png"), None);
+  registry.unload(tile);
+  assert!(registry.is_empty());
+}
@@ -0,0 +1,249 @@
+use std::{
+  collections::{hash_map::DefaultHasher, HashMap},
+  hash::{Hash, Hasher},
+};
+
+use text_to_png::TextRenderer;
+
+use crate::modules::context::render::{Text, TextAlign, Texture};
+
+use super::{image::Image, AssetError, AssetHandle, AssetRegistry};
+
+/// line height by font size
+const LINE_SPACING: f32 = 1.25;
+
+/// rendered line and its baseline from top
+type Line = (Image, u32);
+
+/// # 文字點陣快取
+/// 以 text-to-png 逐行點陣化, 依參數快取並註冊到資源表
+#[derive(Default)]
+pub struct TextCache {
+  renderer: TextRenderer,
+  lines: HashMap<(String, u32, String), Option<Line>>,
+  textures: HashMap<Text, AssetHandle>,
+}
+
+impl TextCache {
+  /// with built-in font
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// with ttf or ttc font data
+  pub fn with_font(font: &[u8]) -> Result<Self, AssetError> {
+    let renderer = TextRenderer::try_new_with_ttf_font_data(font)
+      .map_err(|error| AssetError::Image(error.to_string()))?;
+    Ok(TextCache {
+      renderer,
+      ..Default::default()
+    })
+  }
+  pub fn clear(&mut self) {
+    self.lines.clear();
+    self.textures.clear();
+  }
+
+  /// register text bitmap, reuse cached one if still loaded
+  pub fn load(
+    &mut self,
+    registry: &mut AssetRegistry,
+    text: &Text,
+  ) -> Result<AssetHandle, AssetError> {
+    if let Some(handle) = self.textures.get(text).copied() {
+      if registry.get(handle).is_some() {
+        registry.retain(handle);
+        return Ok(handle);
+      }
+    }
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    let path = format!("text/{:016x}.png", hasher.finish());
+    let handle = registry.load_bytes(&path, self.rasterize(text)?.encode_png()?)?;
+    self.textures.insert(text.clone(), handle);
+    Ok(handle)
+  }
+
+  /// replace text textures by bitmap handle
+  /// 將文字材質換成點陣圖 handle
+  pub fn resolve(
+    &mut self,
+    registry: &mut AssetRegistry,
+    texture: &Texture,
+  ) -> Result<Texture, AssetError> {
+    Ok(match texture {
+      Texture::Text(text) => Texture::Asset(self.load(registry, text)?),
+      Texture::Styled { texture, style } => {
+        self.resolve(registry, texture)?.with_style(style.clone())
+      }
+      texture => texture.clone(),
+    })
+  }
+
+  //================================================================================
+  // Rasterize
+  //================================================================================
+  pub fn rasterize(&mut self, text: &Text) -> Result<Image, AssetError> {
+    let mut lines = vec![];
+    for paragraph in text.text.split('\n') {
+      lines.extend(self.wrap(paragraph, text)?);
+    }
+    for line in lines.iter() {
+      self.line(line, text)?;
+    }
+
+    let pitch = (text.size as f32 * LINE_SPACING).ceil() as u32;
+    let rendered: Vec<&Line> = lines
+      .iter()
+      .filter_map(|line| self.lines[&self.key(line, text)].as_ref())
+      .collect();
+    let ascent = rendered.iter().map(|(_, baseline)| *baseline).max();
+    let ascent = ascent.unwrap_or(text.size);
+    let descent = rendered
+      .iter()
+      .map(|(image, baseline)| image.height().saturating_sub(*baseline))
+      .max()
+      .unwrap_or(0);
+    let widest = rendered.iter().map(|(image, _)| image.width()).max();
+    let width = text.wrap.unwrap_or(0).max(widest.unwrap_or(0)).max(1);
+    let height = ascent + pitch * lines.len().saturating_sub(1) as u32 + descent;
+
+    let mut image = Image::new(width, height.max(1));
+    for (index, line) in lines.iter().enumerate() {
+      let Some((line, baseline)) = self.lines[&self.key(line, text)].as_ref() else {
+        continue;
+      };
+      let x = match text.align {
+        TextAlign::Left => 0,
+        TextAlign::Center => (width - line.width()) / 2,
+        TextAlign::Right => width - line.width(),
+      };
+      let y = ascent + pitch * index as u32 - baseline;
+      image.draw(line, x as i32, y as i32);
+    }
+
+    if let (_, Some(alpha)) = split_alpha(&text.color) {
+      for pixel in image.pixels_mut().chunks_exact_mut(4) {
+        pixel[3] = (pixel[3] as u32 * alpha as u32 / 255) as u8;
+      }
+    }
+    Ok(image)
+  }
+
+  /// split paragraph by wrap width
+  fn wrap(&mut self, paragraph: &str, text: &Text) -> Result<Vec<String>, AssetError> {
+    let Some(wrap) = text.wrap else {
+      return Ok(vec![paragraph.to_string()]);
+    };
+    let mut lines = vec![];
+    let mut current = String::new();
+    for word in paragraph.split_whitespace() {
+      let candidate = match current.is_empty() {
+        true => word.to_string(),
+        false => format!("{current} {word}"),
+      };
+      let width = self
+        .line(&candidate, text)?
+        .map_or(0, |(image, _)| image.width());
+      if width <= wrap || current.is_empty() {
+        current = candidate;
+      } else {
+        lines.push(std::mem::replace(&mut current, word.to_string()));
+      }
+    }
+    lines.push(current);
+    Ok(lines)
+  }
+
+  fn key(&self, line: &str, text: &Text) -> (String, u32, String) {
+    // text-to-png takes no alpha, it is applied after
+    let (color, _) = split_alpha(&text.color);
+    (line.to_string(), text.size, color.to_string())
+  }
+
+  /// rendered line, `None` for blank line
+  fn line(&mut self, line: &str, text: &Text) -> Result<Option<&Line>, AssetError> {
+    let key = self.key(line, text);
+    if !self.lines.contains_key(&key) {
+      let rendered = match line.trim().is_empty() {
+        true => None,
+        false => {
+          let png = self
+            .renderer
+            .render_text_to_png_data(line, text.size, key.2.as_str())
+            .map_err(|error| AssetError::Image(error.to_string()))?;
+          let baseline = png.baseline_down_from_top.round().max(0.) as u32;
+          Some((Image::decode_png(&png.data)?, baseline))
+        }
+      };
+      self.lines.insert(key.clone(), rendered);
+    }
+    Ok(self.lines[&key].as_ref())
+  }
+}
+
+/// split `#rrggbbaa` into `#rrggbb` and alpha, other colors are kept as is
+fn split_alpha(color: &str) -> (&str, Option<u8>) {
+  let hex = match color.strip_prefix('#') {
+    Some(hex) if hex.len() == 8 && hex.chars().all(|c| c.is_ascii_hexdigit()) => hex,
+    _ => return (color, None),
+  };
+  (&color[..7], u8::from_str_radix(&hex[6..], 16).ok())
+}
+
+#[test]
+fn test() {
+  let mut cache = TextCache::new();
+  let single = cache
+    .rasterize(&Text::new("hello world", 16, "#ffffff"))
+    .unwrap();
+  let wrapped = Text::new("hello world", 16, "#ffffff").with_wrap(single.width() - 1);
+  let wrapped_image = cache.rasterize(&wrapped).unwrap();
+  assert_eq!(wrapped_image.width(), single.width() - 1);
+  assert!(wrapped_image.height() >= single.height() + 16);
+  let two_lines = cache
+    .rasterize(&Text::new("hello\nworld", 16, "#ffffff"))
+    .unwrap();
+  assert_eq!(two_lines.height(), wrapped_image.height());
+
+  // first line "a" is on one side, second line fills the width
+  let empty = |image: &Image, columns: std::ops::Range<u32>| {
+    columns
+      .into_iter()
+      .all(|x| (0..16).all(|y| image.get(x, y).unwrap()[3] == 0))
+  };
+  let right = cache
+    .rasterize(&Text::new("a\nwide line", 16, "#ffffff").with_align(TextAlign::Right))
+    .unwrap();
+  let left = cache
+    .rasterize(&Text::new("a\nwide line", 16, "#ffffff"))
+    .unwrap();
+  let width = left.width();
+  assert_eq!(right.width(), width);
+  assert!(empty(&right, 0..width / 2) && !empty(&right, width / 2..width));
+  assert!(empty(&left, width / 2..width) && !empty(&left, 0..width / 2));
+
+  // half transparent color
+  let faded = cache
+    .rasterize(&Text::new("hello world", 16, "#ffffff80"))
+    .unwrap();
+  let alpha = |image: &Image| image.pixels().chunks(4).map(|pixel| pixel[3]).max();
+  assert!(alpha(&faded) < alpha(&single));
+
+  // named colors are not cut like `#rrggbbaa`
+  assert_eq!(split_alpha("DarkTurquoise"), ("DarkTurquoise", None));
+  assert_eq!(split_alpha("#ffffff80"), ("#ffffff", Some(0x80)));
+  assert_eq!(split_alpha("#fff"), ("#fff", None));
+  let named = Text::new("hello world", 16, "DarkTurquoise");
+  assert_eq!(cache.key("hello", &named).2, "DarkTurquoise");
+  let turquoise = cache.rasterize(&named).unwrap();
+  let pixel = turquoise.pixels().chunks(4).find(|pixel| pixel[3] == 255);
+  assert_eq!(pixel.map(|pixel| &pixel[..3]), Some(&[0x00, 0xce, 0xd1][..]));
+
+  let mut registry = AssetRegistry::new();
+  let texture = Texture::Text(Text::new("hp 10", 12, "#ff0000")).with_opacity(0.5);
+  let resolved = cache.resolve(&mut registry, &texture).unwrap();
+  let handle = resolved.asset().unwrap();
+  assert_eq!(resolved.style().opacity, 0.5);
+  assert_eq!(cache.resolve(&mut registry, &texture).unwrap(), resolved);
+  assert_eq!(registry.get(handle).unwrap().refs(), 2);
+}
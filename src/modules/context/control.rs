@@ -1,13 +1,15 @@
+use serde::{Deserialize, Serialize};
+
 use crate::utils::vector::Vector;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Control {
   pub keys: Vec<KeyEvent>,
   pub click: [Option<Vector>; 2],
   pub mouse: Vector,
 }
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct KeyEvent {
   pub code: String,
   pub alt: bool,
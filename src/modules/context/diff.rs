@@ -0,0 +1,438 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::rect::Rect;
+
+use super::render::{DrawKey, RenderFrame, Texture, ViewPort};
+
+/// keyframe every n frames by default
+const KEYFRAME_INTERVAL: u64 = 120;
+
+///=========================================================================================
+/// Message
+///=========================================================================================
+/// identity of draw item across frames
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DrawId {
+  Keyed(DrawKey),
+  /// n-th item without key (or with repeated key) in layer
+  Anonymous(u32),
+}
+
+/// item with compact slot, later deltas refer to slot only
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawItem {
+  pub slot: u32,
+  pub id: DrawId,
+  pub rect: Rect,
+  pub texture: Texture,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayerDelta {
+  pub added: Vec<DrawItem>,
+  pub removed: Vec<u32>,
+  pub moved: Vec<(u32, Rect)>,
+  pub retextured: Vec<(u32, Texture)>,
+  /// slots in draw order, only when order changed
+  pub order: Option<Vec<u32>>,
+}
+
+impl LayerDelta {
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty()
+      && self.removed.is_empty()
+      && self.moved.is_empty()
+      && self.retextured.is_empty()
+      && self.order.is_none()
+  }
+}
+
+/// # 畫面更新
+/// 關鍵幀包含全部項目並重設 slot, 差異幀只包含變化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FrameUpdate {
+  Keyframe {
+    sequence: u64,
+    viewport: ViewPort,
+    world: Vec<DrawItem>,
+    ui: Vec<DrawItem>,
+  },
+  Delta {
+    sequence: u64,
+    viewport: Option<ViewPort>,
+    world: LayerDelta,
+    ui: LayerDelta,
+  },
+}
+
+impl FrameUpdate {
+  pub fn sequence(&self) -> u64 {
+    match self {
+      FrameUpdate::Keyframe { sequence, .. } | FrameUpdate::Delta { sequence, .. } => *sequence,
+    }
+  }
+  pub fn is_keyframe(&self) -> bool {
+    matches!(self, FrameUpdate::Keyframe { .. })
+  }
+}
+
+///=========================================================================================
+/// Layer
+///=========================================================================================
+/// items of one layer by slot, and slot of each id
+#[derive(Debug, Clone, Default)]
+struct Layer {
+  next: u32,
+  slots: HashMap<DrawId, u32>,
+  items: HashMap<u32, (Rect, Texture)>,
+  order: Vec<u32>,
+}
+
+impl Layer {
+  fn reset(&mut self) {
+    *self = Layer::default();
+  }
+
+  /// diff against items of new frame and take them as current
+  fn diff(&mut self, items: &[(Rect, Texture)], keys: &[Option<DrawKey>]) -> LayerDelta {
+    let mut delta = LayerDelta::default();
+    let mut slots = HashMap::with_capacity(items.len());
+    let mut order = Vec::with_capacity(items.len());
+    for (id, (rect, texture)) in identify(keys, items.len()).into_iter().zip(items) {
+      let slot = match self.slots.get(&id) {
+        Some(slot) => {
+          let (old_rect, old_texture) = &self.items[slot];
+          if old_rect != rect {
+            delta.moved.push((*slot, *rect));
+          }
+          if old_texture != texture {
+            delta.retextured.push((*slot, texture.clone()));
+          }
+          *slot
+        }
+        None => {
+          let slot = self.next;
+          self.next += 1;
+          delta.added.push(DrawItem {
+            slot,
+            id: id.clone(),
+            rect: *rect,
+            texture: texture.clone(),
+          });
+          slot
+        }
+      };
+      slots.insert(id, slot);
+      order.push(slot);
+    }
+
+    let mut items_by_slot = HashMap::with_capacity(items.len());
+    for (slot, item) in order.iter().zip(items) {
+      items_by_slot.insert(*slot, item.clone());
+    }
+    delta.removed = self
+      .order
+      .iter()
+      .filter(|slot| !items_by_slot.contains_key(slot))
+      .copied()
+      .collect();
+    // added items append in order, so only send order if it differs
+    let kept: Vec<u32> = self
+      .order
+      .iter()
+      .filter(|slot| items_by_slot.contains_key(slot))
+      .chain(delta.added.iter().map(|item| &item.slot))
+      .copied()
+      .collect();
+    if kept != order {
+      delta.order = Some(order.clone());
+    }
+
+    self.slots = slots;
+    self.items = items_by_slot;
+    self.order = order;
+    delta
+  }
+
+  fn keyframe(&mut self, items: &[(Rect, Texture)], keys: &[Option<DrawKey>]) -> Vec<DrawItem> {
+    self.reset();
+    self.diff(items, keys).added
+  }
+
+  fn apply(&mut self, delta: LayerDelta) {
+    for slot in delta.removed.iter() {
+      self.items.remove(slot);
+    }
+    self.order.retain(|slot| self.items.contains_key(slot));
+    for item in delta.added {
+      self.items.insert(item.slot, (item.rect, item.texture));
+      self.order.push(item.slot);
+    }
+    for (slot, rect) in delta.moved {
+      if let Some(item) = self.items.get_mut(&slot) {
+        item.0 = rect;
+      }
+    }
+    for (slot, texture) in delta.retextured {
+      if let Some(item) = self.items.get_mut(&slot) {
+        item.1 = texture;
+      }
+    }
+    if let Some(order) = delta.order {
+      self.order = order;
+    }
+  }
+
+  fn load(&mut self, items: Vec<DrawItem>) {
+    self.reset();
+    self.apply(LayerDelta {
+      added: items,
+      ..Default::default()
+    });
+  }
+
+  fn items(&self) -> impl Iterator<Item = &(Rect, Texture)> {
+    self.order.iter().filter_map(|slot| self.items.get(slot))
+  }
+}
+
+/// keyed items by key, others by position among unkeyed items
+fn identify(keys: &[Option<DrawKey>], length: usize) -> Vec<DrawId> {
+  let mut seen = HashSet::new();
+  let mut anonymous = 0;
+  let mut ids = Vec::with_capacity(length);
+  for index in 0..length {
+    let key = keys.get(index).cloned().flatten();
+    let id = match key {
+      Some(key) if seen.insert(key.clone()) => DrawId::Keyed(key),
+      _ => {
+        anonymous += 1;
+        DrawId::Anonymous(anonymous - 1)
+      }
+    };
+    ids.push(id);
+  }
+  ids
+}
+
+///=========================================================================================
+/// FrameDiffer
+///=========================================================================================
+/// # 畫面差異編碼
+/// 以繪製鍵比對前後畫面, 輸出差異並定期輸出關鍵幀
+#[derive(Debug, Clone)]
+pub struct FrameDiffer {
+  interval: u64,
+  sequence: u64,
+  since_keyframe: Option<u64>,
+  viewport: Option<ViewPort>,
+  world: Layer,
+  ui: Layer,
+}
+
+impl Default for FrameDiffer {
+  fn default() -> Self {
+    FrameDiffer::new(KEYFRAME_INTERVAL)
+  }
+}
+
+impl FrameDiffer {
+  /// keyframe every `interval` frames
+  pub fn new(interval: u64) -> Self {
+    FrameDiffer {
+      interval: interval.max(1),
+      sequence: 0,
+      since_keyframe: None,
+      viewport: None,
+      world: Layer::default(),
+      ui: Layer::default(),
+    }
+  }
+
+  /// next frame is keyframe, e.g. frontend attached
+  pub fn force_keyframe(&mut self) {
+    self.since_keyframe = None;
+  }
+
+  pub fn encode(&mut self, frame: &RenderFrame) -> FrameUpdate {
+    let sequence = self.sequence;
+    self.sequence += 1;
+    let viewport = frame.viewport();
+    let keyframe = match self.since_keyframe {
+      Some(since) => since + 1 >= self.interval,
+      None => true,
+    };
+
+    if keyframe {
+      self.since_keyframe = Some(0);
+      self.viewport = Some(viewport);
+      return FrameUpdate::Keyframe {
+        sequence,
+        viewport,
+        world: self.world.keyframe(frame.get(), frame.keys()),
+        ui: self.ui.keyframe(frame.get_ui(), frame.ui_keys()),
+      };
+    }
+
+    self.since_keyframe = self.since_keyframe.map(|since| since + 1);
+    let changed = self.viewport != Some(viewport);
+    self.viewport = Some(viewport);
+    FrameUpdate::Delta {
+      sequence,
+      viewport: changed.then_some(viewport),
+      world: self.world.diff(frame.get(), frame.keys()),
+      ui: self.ui.diff(frame.get_ui(), frame.ui_keys()),
+    }
+  }
+}
+
+///=========================================================================================
+/// FrameApplier
+///=========================================================================================
+/// # 畫面差異解碼
+/// 前端以此重建畫面, 序號不連續時等待下一個關鍵幀
+#[derive(Debug, Clone, Default)]
+pub struct FrameApplier {
+  sequence: Option<u64>,
+  viewport: Option<ViewPort>,
+  world: Layer,
+  ui: Layer,
+}
+
+impl FrameApplier {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// rebuilt frame, `None` while waiting for keyframe
+  pub fn apply(&mut self, update: FrameUpdate) -> Option<RenderFrame> {
+    match update {
+      FrameUpdate::Keyframe {
+        sequence,
+        viewport,
+        world,
+        ui,
+      } => {
+        self.sequence = Some(sequence);
+        self.viewport = Some(viewport);
+        self.world.load(world);
+        self.ui.load(ui);
+      }
+      FrameUpdate::Delta {
+        sequence,
+        viewport,
+        world,
+        ui,
+      } => {
+        if self.sequence.map(|last| last + 1) != Some(sequence) {
+          self.sequence = None;
+          return None;
+        }
+        self.sequence = Some(sequence);
+        if viewport.is_some() {
+          self.viewport = viewport;
+        }
+        self.world.apply(world);
+        self.ui.apply(ui);
+      }
+    }
+    Some(self.frame())
+  }
+
+  /// current frame without keys
+  pub fn frame(&self) -> RenderFrame {
+    let mut frame = RenderFrame::new();
+    self.world.items().for_each(|item| frame.push(item.clone()));
+    self.ui.items().for_each(|item| frame.push_ui(item.clone()));
+    if let Some(viewport) = self.viewport {
+      frame.set_viewport(viewport);
+    }
+    frame
+  }
+}
+
+#[test]
+fn test() {
+  use crate::utils::vector::Vector;
+  use uuid::Uuid;
+
+  let hero = Uuid::new_v4();
+  let rect = |x: f32| Rect::new(Vector::new(x, 0.), Vector::new(10., 10.));
+  let texture = |color: &str| Texture::Color(color.to_string());
+  let build = |items: Vec<(&str, f32, &str)>| {
+    let mut frame = RenderFrame::new();
+    frame.push((rect(-100.), texture("#000000")));
+    for (part, x, color) in items {
+      frame.push_keyed(DrawKey::new(hero, part, 0), (rect(x), texture(color)));
+    }
+    frame.push_ui((rect(0.), texture("#ffffff")));
+    frame
+  };
+  // strip keys for comparison with rebuilt frames
+  let plain = |frame: &RenderFrame| {
+    let mut plain = RenderFrame::new();
+    plain.append(frame.get());
+    plain.append_ui(frame.get_ui());
+    plain.set_viewport(frame.viewport());
+    plain
+  };
+
+  let frames = [
+    build(vec![("body", 0., "#ff0000"), ("hat", 0., "#00ff00")]),
+    // body moves
+    build(vec![("body", 5., "#ff0000"), ("hat", 0., "#00ff00")]),
+    // hat recolored, sword added
+    build(vec![
+      ("body", 5., "#ff0000"),
+      ("hat", 0., "#0000ff"),
+      ("sword", 8., "#888888"),
+    ]),
+    // hat removed, order swapped
+    build(vec![("sword", 8., "#888888"), ("body", 5., "#ff0000")]),
+    build(vec![("sword", 8., "#888888"), ("body", 5., "#ff0000")]),
+  ];
+
+  let mut differ = FrameDiffer::new(4);
+  let mut applier = FrameApplier::new();
+  let mut updates = vec![];
+  for frame in frames.iter() {
+    let update = differ.encode(frame);
+    assert_eq!(applier.apply(update.clone()), Some(plain(frame)));
+    updates.push(update);
+  }
+  assert!(updates[0].is_keyframe());
+  let FrameUpdate::Delta {
+    world,
+    ui,
+    viewport,
+    ..
+  } = &updates[1]
+  else {
+    panic!("expect delta");
+  };
+  assert_eq!(world.moved, vec![(1, rect(5.))]);
+  assert!(world.added.is_empty() && world.retextured.is_empty() && world.order.is_none());
+  assert!(ui.is_empty() && viewport.is_none());
+  let FrameUpdate::Delta { world, .. } = &updates[2] else {
+    panic!("expect delta");
+  };
+  assert_eq!(world.retextured, vec![(2, texture("#0000ff"))]);
+  assert_eq!(world.added.len(), 1);
+  let FrameUpdate::Delta { world, .. } = &updates[3] else {
+    panic!("expect delta");
+  };
+  assert_eq!(world.removed, vec![2]);
+  assert_eq!(world.order, Some(vec![0, 3, 1]));
+  // periodic keyframe
+  assert!(updates[4].is_keyframe());
+
+  // missing delta waits for keyframe
+  let mut late = FrameApplier::new();
+  assert!(late.apply(updates[1].clone()).is_none());
+  differ.force_keyframe();
+  let update = differ.encode(&frames[4]);
+  assert!(update.is_keyframe());
+  assert_eq!(late.apply(update), Some(plain(&frames[4])));
+}
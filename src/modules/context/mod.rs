@@ -2,6 +2,12 @@ use self::{control::Control, render::RenderFrame};
 
 pub mod render;
 pub mod control;
+pub mod stream;
+pub mod diff;
+pub mod svg;
+pub mod raster;
+pub mod terminal;
+pub mod record;
 
 pub trait Context {
   fn control(&self) -> Option<Control>;
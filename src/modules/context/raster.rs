@@ -0,0 +1,465 @@
+use std::{collections::HashMap, fs};
+
+use crate::{
+  modules::asset::{image::Image, text::TextCache, AssetHandle, AssetRegistry},
+  utils::{
+    rect::Rect,
+    scalar::{scalar, to_f32},
+    vector::Vector,
+    viewbox::ViewBox,
+  },
+};
+
+use super::render::{GradientStop, Region, RenderFrame, Slice, Text, Texture, ViewPort};
+
+/// color of missing image or invalid color
+const MISSING: [u8; 4] = [255, 0, 255, 255];
+
+///=========================================================================================
+/// Rasterizer
+///=========================================================================================
+/// # 軟體點陣化
+/// 將 RenderFrame 畫到 RGBA 圖像, 依視口的縮放, 旋轉和寬高比策略投影
+/// 圖片依路徑從磁碟讀取 (PNG), 資源 handle 需先以 `load_assets` 載入
+pub struct Rasterizer {
+  background: [u8; 4],
+  images: HashMap<String, Option<Image>>,
+  assets: HashMap<AssetHandle, Option<Image>>,
+  texts: HashMap<Text, Option<Image>>,
+  text_cache: TextCache,
+}
+
+impl Default for Rasterizer {
+  fn default() -> Self {
+    Rasterizer {
+      background: [0, 0, 0, 255],
+      images: HashMap::new(),
+      assets: HashMap::new(),
+      texts: HashMap::new(),
+      text_cache: TextCache::new(),
+    }
+  }
+}
+
+impl Rasterizer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// clear color, `#rrggbb` or `#rrggbbaa`
+  pub fn with_background(mut self, color: &str) -> Self {
+    self.background = parse_color(color).unwrap_or(MISSING);
+    self
+  }
+  /// decode images of registry for asset textures
+  pub fn load_assets(&mut self, registry: &AssetRegistry) {
+    for (handle, asset) in registry.iter() {
+      self
+        .assets
+        .entry(*handle)
+        .or_insert_with(|| Image::decode_png(asset.bytes()).ok());
+    }
+  }
+  /// image by path, e.g. generated at runtime
+  pub fn insert_image(&mut self, path: &str, image: Image) {
+    self.images.insert(path.to_string(), Some(image));
+  }
+  pub fn clear(&mut self) {
+    self.images.clear();
+    self.assets.clear();
+    self.texts.clear();
+  }
+
+  /// draw frame into `width` x `height` pixels
+  pub fn render(&mut self, frame: &RenderFrame, width: u32, height: u32) -> Image {
+    let mut image = Image::new(width, height);
+    for pixel in image.pixels_mut().chunks_exact_mut(4) {
+      pixel.copy_from_slice(&self.background);
+    }
+    let viewport = frame
+      .viewport()
+      .with_screen(Vector::new(width as f32, height as f32));
+    let projection = Projection::new(&viewport);
+    for (rect, texture) in frame.get() {
+      self.draw(&mut image, &projection, rect, texture);
+    }
+    // ui is in normalized viewport space
+    let transform = viewport.transform();
+    for (rect, texture) in frame.get_ui() {
+      self.draw(
+        &mut image,
+        &projection,
+        &transform.apply_rect(*rect),
+        texture,
+      );
+    }
+    image
+  }
+
+  //================================================================================
+  // Draw
+  //================================================================================
+  fn draw(&mut self, image: &mut Image, projection: &Projection, rect: &Rect, texture: &Texture) {
+    let (width, height) = (to_f32(rect.size.0), to_f32(rect.size.1));
+    if width <= 0. || height <= 0. {
+      return;
+    }
+    let corners = rect.points().map(|point| projection.to_screen(point));
+    let bound = |pick: fn(f32, f32) -> f32, axis: fn(&(f32, f32)) -> f32| {
+      corners.iter().map(axis).fold(axis(&corners[0]), pick)
+    };
+    let (clip_min, clip_max) = projection.clip;
+    let left = bound(f32::min, |point| point.0).floor().max(clip_min.0) as u32;
+    let top = bound(f32::min, |point| point.1).floor().max(clip_min.1) as u32;
+    let right = bound(f32::max, |point| point.0)
+      .ceil()
+      .min(clip_max.0)
+      .max(0.) as u32;
+    let bottom = bound(f32::max, |point| point.1)
+      .ceil()
+      .min(clip_max.1)
+      .max(0.) as u32;
+    if left >= right || top >= bottom {
+      return;
+    }
+
+    let style = texture.style();
+    let tint = style.tint.as_deref().and_then(parse_color);
+    let sampler = self.sampler(texture.inner());
+    let center = (to_f32(rect.position.0), to_f32(rect.position.1));
+    let (sin, cos) = to_f32(rect.angle).sin_cos();
+    let scale = projection.scale;
+    let size = (width * scale.0, height * scale.1);
+    for y in top..bottom {
+      for x in left..right {
+        // pixel center into rect space, u to the right and v down, 0 ~ 1
+        let point = projection.to_scene(x as f32 + 0.5, y as f32 + 0.5);
+        let (dx, dy) = (point.0 - center.0, point.1 - center.1);
+        let local = (dx * cos + dy * sin, -dx * sin + dy * cos);
+        let mut u = local.0 / width + 0.5;
+        let mut v = 0.5 - local.1 / height;
+        if !(0. ..1.).contains(&u) || !(0. ..1.).contains(&v) {
+          continue;
+        }
+        if style.flip_x {
+          u = 1. - u;
+        }
+        if style.flip_y {
+          v = 1. - v;
+        }
+        let mut color = sampler.sample(u, v, size, scale);
+        if let Some(tint) = tint {
+          for channel in 0..3 {
+            color[channel] = (color[channel] as u32 * tint[channel] as u32 / 255) as u8;
+          }
+          color[3] = (color[3] as u32 * tint[3] as u32 / 255) as u8;
+        }
+        color[3] = (color[3] as f32 * style.opacity.clamp(0., 1.)).round() as u8;
+        image.blend(x, y, color);
+      }
+    }
+  }
+
+  fn sampler<'a>(&'a mut self, texture: &'a Texture) -> Sampler<'a> {
+    match texture {
+      Texture::Color(color) => Sampler::Color(parse_color(color).unwrap_or(MISSING)),
+      Texture::Bitmap(source) => self.image(source).map_or(MISSING.into(), Sampler::image),
+      Texture::Region { source, region } => match self.image(source) {
+        Some(image) => Sampler::Image(image, *region, None),
+        None => MISSING.into(),
+      },
+      Texture::NineSlice {
+        source,
+        region,
+        slice,
+      } => match self.image(source) {
+        Some(image) => {
+          let whole = Region::new(0, 0, image.width(), image.height());
+          Sampler::Image(image, region.unwrap_or(whole), Some(*slice))
+        }
+        None => MISSING.into(),
+      },
+      Texture::Asset(asset) => match self.assets.get(asset) {
+        Some(Some(image)) => Sampler::image(image),
+        _ => MISSING.into(),
+      },
+      Texture::AssetRegion { asset, region } => match self.assets.get(asset) {
+        Some(Some(image)) => Sampler::Image(image, *region, None),
+        _ => MISSING.into(),
+      },
+      Texture::LinearGradient { from, to, stops } => Sampler::Linear(*from, *to, stops),
+      Texture::RadialGradient {
+        center,
+        radius,
+        stops,
+      } => Sampler::Radial(*center, to_f32(*radius), stops),
+      Texture::Text(text) => {
+        if !self.texts.contains_key(text) {
+          let rendered = self.text_cache.rasterize(text).ok();
+          self.texts.insert(text.clone(), rendered);
+        }
+        match &self.texts[text] {
+          Some(image) => Sampler::image(image),
+          None => Sampler::Color([0; 4]),
+        }
+      }
+      Texture::Styled { texture, .. } => self.sampler(texture),
+    }
+  }
+
+  /// image from disk, cached even if missing
+  fn image(&mut self, path: &str) -> Option<&Image> {
+    self
+      .images
+      .entry(path.to_string())
+      .or_insert_with(|| Image::decode_png(&fs::read(path).ok()?).ok())
+      .as_ref()
+  }
+}
+
+//=========================================================================================
+// Projection
+//=========================================================================================
+/// scene space to pixel in f32, same as `ViewPort::to_screen`
+struct Projection {
+  center: (f32, f32),
+  /// sin and cos of camera angle
+  rotation: (f32, f32),
+  /// pixels per scene unit
+  scale: (f32, f32),
+  half: (f32, f32),
+  /// letterbox in pixels
+  clip: ((f32, f32), (f32, f32)),
+}
+
+impl Projection {
+  fn new(viewport: &ViewPort) -> Self {
+    let pair = |vector: Vector| (to_f32(vector.0), to_f32(vector.1));
+    let letterbox = viewport.letterbox();
+    Projection {
+      center: pair(viewport.position()),
+      rotation: to_f32(viewport.angle()).sin_cos(),
+      scale: pair(viewport.pixel_scale()),
+      half: pair(viewport.screen() / scalar(2.)),
+      clip: (pair(letterbox.min), pair(letterbox.max)),
+    }
+  }
+  fn to_screen(&self, point: Vector) -> (f32, f32) {
+    let (sin, cos) = self.rotation;
+    let (dx, dy) = (
+      to_f32(point.0) - self.center.0,
+      to_f32(point.1) - self.center.1,
+    );
+    let local = (dx * cos + dy * sin, -dx * sin + dy * cos);
+    (
+      self.half.0 + local.0 * self.scale.0,
+      self.half.1 - local.1 * self.scale.1,
+    )
+  }
+  fn to_scene(&self, x: f32, y: f32) -> (f32, f32) {
+    let (sin, cos) = self.rotation;
+    let local = (
+      (x - self.half.0) / self.scale.0,
+      (self.half.1 - y) / self.scale.1,
+    );
+    (
+      self.center.0 + local.0 * cos - local.1 * sin,
+      self.center.1 + local.0 * sin + local.1 * cos,
+    )
+  }
+}
+
+//=========================================================================================
+// Sampler
+//=========================================================================================
+enum Sampler<'a> {
+  Color([u8; 4]),
+  /// image, region and nine slice
+  Image(&'a Image, Region, Option<Slice>),
+  Linear(Vector, Vector, &'a Vec<GradientStop>),
+  Radial(Vector, f32, &'a Vec<GradientStop>),
+}
+
+impl From<[u8; 4]> for Sampler<'_> {
+  fn from(color: [u8; 4]) -> Self {
+    Sampler::Color(color)
+  }
+}
+
+impl<'a> Sampler<'a> {
+  fn image(image: &'a Image) -> Self {
+    Sampler::Image(
+      image,
+      Region::new(0, 0, image.width(), image.height()),
+      None,
+    )
+  }
+
+  /// color at `u`, `v` of rect, size and pixels per unit for nine slice
+  fn sample(&self, u: f32, v: f32, size: (f32, f32), scale: (f32, f32)) -> [u8; 4] {
+    match self {
+      Sampler::Color(color) => *color,
+      Sampler::Image(image, region, slice) => {
+        let (width, height) = (region.width as f32, region.height as f32);
+        let (x, y) = match slice {
+          None => (u * width, v * height),
+          Some(slice) => (
+            nine(u * size.0, size.0, width, slice.left, slice.right, scale.0),
+            nine(v * size.1, size.1, height, slice.top, slice.bottom, scale.1),
+          ),
+        };
+        let x = (x as u32).min(region.width.saturating_sub(1)) + region.x;
+        let y = (y as u32).min(region.height.saturating_sub(1)) + region.y;
+        image.get(x, y).unwrap_or([0; 4])
+      }
+      // gradient space is left-bottom (0, 0) to right-top (1, 1)
+      Sampler::Linear(from, to, stops) => {
+        let (from, to) = (
+          (to_f32(from.0), to_f32(from.1)),
+          (to_f32(to.0), to_f32(to.1)),
+        );
+        let direction = (to.0 - from.0, to.1 - from.1);
+        let length = direction.0 * direction.0 + direction.1 * direction.1;
+        let t = match length > 0. {
+          true => ((u - from.0) * direction.0 + (1. - v - from.1) * direction.1) / length,
+          false => 0.,
+        };
+        gradient(stops, t)
+      }
+      Sampler::Radial(center, radius, stops) => {
+        let (x, y) = (u - to_f32(center.0), 1. - v - to_f32(center.1));
+        let t = match *radius > 0. {
+          true => (x * x + y * y).sqrt() / radius,
+          false => 1.,
+        };
+        gradient(stops, t)
+      }
+    }
+  }
+}
+
+/// destination pixel to source pixel of nine slice along one axis
+fn nine(position: f32, length: f32, source: f32, start: u32, end: u32, scale: f32) -> f32 {
+  let (start, end) = (start as f32, end as f32);
+  // corners keep source size in scene units, shrink if rect is too small
+  let fit = (length / ((start + end) * scale)).min(1.);
+  let (head, tail) = (start * scale * fit, end * scale * fit);
+  if position < head {
+    position / (scale * fit)
+  } else if position >= length - tail {
+    source - (length - position) / (scale * fit)
+  } else {
+    let middle = (length - head - tail).max(f32::EPSILON);
+    start + (position - head) / middle * (source - start - end).max(0.)
+  }
+}
+
+fn gradient(stops: &[GradientStop], t: f32) -> [u8; 4] {
+  let color = |stop: &GradientStop| parse_color(&stop.color).unwrap_or(MISSING);
+  let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+    return [0; 4];
+  };
+  if t <= first.offset {
+    return color(first);
+  }
+  for pair in stops.windows(2) {
+    let (a, b) = (&pair[0], &pair[1]);
+    if t <= b.offset {
+      let span = (b.offset - a.offset).max(f32::EPSILON);
+      let k = (t - a.offset) / span;
+      let (a, b) = (color(a), color(b));
+      return [0, 1, 2, 3].map(|i| (a[i] as f32 + (b[i] as f32 - a[i] as f32) * k).round() as u8);
+    }
+  }
+  color(last)
+}
+
+/// `#rgb`, `#rrggbb` or `#rrggbbaa`
+pub fn parse_color(color: &str) -> Option<[u8; 4]> {
+  let hex = color.strip_prefix('#')?;
+  let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+  match hex.len() {
+    3 => {
+      let mut rgb = [0, 0, 0, 255];
+      for (index, char) in hex.chars().enumerate() {
+        rgb[index] = char.to_digit(16)? as u8 * 17;
+      }
+      Some(rgb)
+    }
+    6 => Some([channel(0)?, channel(2)?, channel(4)?, 255]),
+    8 => Some([channel(0)?, channel(2)?, channel(4)?, channel(6)?]),
+    _ => None,
+  }
+}
+
+#[test]
+fn test() {
+  assert_eq!(parse_color("#f80"), Some([255, 136, 0, 255]));
+  assert_eq!(parse_color("#11223344"), Some([17, 34, 51, 68]));
+  assert_eq!(parse_color("red"), None);
+
+  let mut viewport = ViewPort::new();
+  viewport.set_size(Vector::new(20., 10.));
+  let mut frame = RenderFrame::new();
+  // left half red, rotated bar in the right half
+  frame.push((
+    Rect::new(Vector::new(-5., 0.), Vector::new(10., 10.)),
+    Texture::Color("#ff0000".to_string()),
+  ));
+  frame.push((
+    Rect::new_with_angle(
+      Vector::new(5., 0.),
+      Vector::new(8., 2.),
+      scalar(std::f32::consts::FRAC_PI_2),
+    ),
+    Texture::Color("#00ff00".to_string()).with_opacity(0.5),
+  ));
+  frame.set_viewport(viewport);
+
+  let mut rasterizer = Rasterizer::new();
+  let mut checker = Image::new(2, 2);
+  checker.set(0, 0, [0, 0, 255, 255]);
+  rasterizer.insert_image("checker.png", checker);
+  frame.push_ui((
+    Rect::new(Vector::new(0., 0.5), Vector::new(0.2, 0.2)),
+    Texture::Bitmap("checker.png".to_string()).with_flip(true, false),
+  ));
+
+  // 40 x 40 image, viewport fitted to 40 x 20 in the middle
+  let image = rasterizer.render(&frame, 40, 40);
+  assert_eq!(image.get(0, 0), Some([0, 0, 0, 255]));
+  assert_eq!(image.get(5, 20), Some([255, 0, 0, 255]));
+  // rotated bar is 2 wide and 8 high, half transparent green over black
+  assert_eq!(image.get(30, 14), Some([0, 128, 0, 255]));
+  assert_eq!(image.get(30, 29), Some([0, 0, 0, 255]));
+  assert_eq!(image.get(33, 20), Some([0, 0, 0, 255]));
+  // ui at half height up, flipped so blue texel is on the right top over red
+  assert_eq!(image.get(20, 14), Some([0, 0, 255, 255]));
+  assert_eq!(image.get(19, 14), Some([255, 0, 0, 255]));
+  assert_eq!(rasterizer.render(&frame, 40, 40), image);
+
+  // camera turned left, red half is above center and clipped by letterbox
+  let mut turned = frame.viewport();
+  turned.set_angle(scalar(std::f32::consts::FRAC_PI_2));
+  frame.set_viewport(turned);
+  let image = rasterizer.render(&frame, 40, 40);
+  assert_eq!(image.get(20, 15), Some([255, 0, 0, 255]));
+  assert_eq!(image.get(20, 5), Some([0, 0, 0, 255]));
+  assert_eq!(image.get(5, 20), Some([0, 0, 0, 255]));
+
+  let gradient = Texture::linear_gradient(
+    Vector::ORIGIN,
+    Vector::new(1., 0.),
+    vec![
+      GradientStop::new(0., "#000000"),
+      GradientStop::new(1., "#ffffff"),
+    ],
+  );
+  let mut frame = RenderFrame::new();
+  frame.push((Rect::new(Vector::ORIGIN, Vector::new(10., 10.)), gradient));
+  let mut viewport = ViewPort::new();
+  viewport.set_size(Vector::new(10., 10.));
+  frame.set_viewport(viewport);
+  let image = rasterizer.render(&frame, 10, 10);
+  let red = |x| image.get(x, 5).unwrap()[0];
+  assert!(red(0) < 20 && red(9) > 235 && red(4) < red(5));
+}
@@ -0,0 +1,335 @@
+use std::{cell::RefCell, fs, ops::Range, path::Path};
+
+use crate::modules::asset::{image::Image, AssetError};
+
+use super::{control::Control, raster::Rasterizer, render::RenderFrame, Context};
+
+/// milliseconds per engine frame by default
+const FRAME_TIME: u32 = 16;
+/// frames kept by default, about a minute at 60 fps
+const LIMIT: usize = 3600;
+/// quantization speed of gif, 1 (best) ~ 30 (fastest)
+const GIF_SPEED: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordFormat {
+  #[default]
+  Gif,
+  Apng,
+}
+
+impl RecordFormat {
+  /// by file extension, `.png` and `.apng` are apng
+  pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+    let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+      "gif" => Some(RecordFormat::Gif),
+      "png" | "apng" => Some(RecordFormat::Apng),
+      _ => None,
+    }
+  }
+}
+
+///=========================================================================================
+/// Recorder
+///=========================================================================================
+/// # 錄影
+/// 以軟體點陣化擷取一段畫面, 輸出 GIF 或 APNG
+/// 每 `skip + 1` 幀保留一幀, 圖像大小為基準大小乘上縮放
+pub struct Recorder {
+  rasterizer: Rasterizer,
+  size: (u32, u32),
+  scale: f32,
+  skip: u32,
+  frame_time: u32,
+  range: Option<Range<u64>>,
+  limit: usize,
+  /// engine frames seen
+  count: u64,
+  frames: Vec<Image>,
+}
+
+impl Recorder {
+  /// base size in pixels
+  pub fn new(width: u32, height: u32) -> Self {
+    Recorder {
+      rasterizer: Rasterizer::new(),
+      size: (width, height),
+      scale: 1.,
+      skip: 0,
+      frame_time: FRAME_TIME,
+      range: None,
+      limit: LIMIT,
+      count: 0,
+      frames: vec![],
+    }
+  }
+  pub fn with_scale(mut self, scale: f32) -> Self {
+    self.scale = scale;
+    self
+  }
+  /// frames dropped after each kept frame
+  pub fn with_skip(mut self, skip: u32) -> Self {
+    self.skip = skip;
+    self
+  }
+  /// milliseconds per engine frame
+  pub fn with_frame_time(mut self, frame_time: u32) -> Self {
+    self.frame_time = frame_time;
+    self
+  }
+  /// engine frames to capture, counted from creation or last clear
+  pub fn with_range(mut self, range: Range<u64>) -> Self {
+    self.range = Some(range);
+    self
+  }
+  /// most frames kept, later frames are dropped
+  pub fn with_limit(mut self, limit: usize) -> Self {
+    self.limit = limit;
+    self
+  }
+  pub fn with_rasterizer(mut self, rasterizer: Rasterizer) -> Self {
+    self.rasterizer = rasterizer;
+    self
+  }
+  pub fn rasterizer_mut(&mut self) -> &mut Rasterizer {
+    &mut self.rasterizer
+  }
+
+  /// output size in pixels
+  pub fn size(&self) -> (u32, u32) {
+    let scale = |value: u32| ((value as f32 * self.scale).round() as u32).max(1);
+    (scale(self.size.0), scale(self.size.1))
+  }
+  pub fn frames(&self) -> &Vec<Image> {
+    &self.frames
+  }
+  pub fn len(&self) -> usize {
+    self.frames.len()
+  }
+  pub fn is_empty(&self) -> bool {
+    self.frames.is_empty()
+  }
+  /// range is finished or limit is reached
+  pub fn is_done(&self) -> bool {
+    self.is_done_at(self.count)
+  }
+  /// drop frames and restart counting
+  pub fn clear(&mut self) {
+    self.count = 0;
+    self.frames.clear();
+  }
+
+  /// called once per engine frame
+  pub fn capture(&mut self, frame: &RenderFrame) {
+    let index = self.count;
+    self.count += 1;
+    if self.is_done_at(index) {
+      return;
+    }
+    let start = self.range.as_ref().map_or(0, |range| range.start);
+    if index < start || !(index - start).is_multiple_of(self.skip as u64 + 1) {
+      return;
+    }
+    let (width, height) = self.size();
+    self
+      .frames
+      .push(self.rasterizer.render(frame, width, height));
+  }
+
+  fn is_done_at(&self, index: u64) -> bool {
+    let ended = match &self.range {
+      Some(range) => index >= range.end,
+      None => false,
+    };
+    ended || self.frames.len() >= self.limit
+  }
+
+  /// milliseconds between kept frames
+  pub fn delay(&self) -> u32 {
+    self.frame_time * (self.skip + 1)
+  }
+
+  //================================================================================
+  // Encode
+  //================================================================================
+  pub fn encode(&self, format: RecordFormat) -> Result<Vec<u8>, AssetError> {
+    if self.frames.is_empty() {
+      return Err(AssetError::Image("no frame recorded".to_string()));
+    }
+    match format {
+      RecordFormat::Gif => self.encode_gif(),
+      RecordFormat::Apng => self.encode_apng(),
+    }
+  }
+
+  /// format by extension of path
+  pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+    let path = path.as_ref();
+    let format = RecordFormat::from_path(path)
+      .ok_or_else(|| AssetError::Format(format!("unknown record format `{}`", path.display())))?;
+    fs::write(path, self.encode(format)?)?;
+    Ok(())
+  }
+
+  fn encode_gif(&self) -> Result<Vec<u8>, AssetError> {
+    let error = |error: gif::EncodingError| AssetError::Image(error.to_string());
+    let (width, height) = self.size();
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+      return Err(AssetError::Image(format!("gif of {width} x {height}")));
+    };
+    let mut bytes = vec![];
+    {
+      let mut encoder = gif::Encoder::new(&mut bytes, width, height, &[]).map_err(error)?;
+      encoder.set_repeat(gif::Repeat::Infinite).map_err(error)?;
+      // gif delay is in centiseconds
+      let delay = (self.delay() as f32 / 10.)
+        .round()
+        .clamp(1., u16::MAX as f32) as u16;
+      for image in self.frames.iter() {
+        let mut pixels = image.pixels().to_vec();
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, GIF_SPEED);
+        frame.delay = delay;
+        encoder.write_frame(&frame).map_err(error)?;
+      }
+    }
+    Ok(bytes)
+  }
+
+  fn encode_apng(&self) -> Result<Vec<u8>, AssetError> {
+    let error = |error: png::EncodingError| AssetError::Image(error.to_string());
+    let (width, height) = self.size();
+    let delay = self.delay().min(u16::MAX as u32) as u16;
+    let mut bytes = vec![];
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // 0 plays is infinite loop
+    encoder
+      .set_animated(self.frames.len() as u32, 0)
+      .map_err(error)?;
+    encoder.set_frame_delay(delay, 1000).map_err(error)?;
+    let mut writer = encoder.write_header().map_err(error)?;
+    for image in self.frames.iter() {
+      writer.write_image_data(image.pixels()).map_err(error)?;
+    }
+    writer.finish().map_err(error)?;
+    Ok(bytes)
+  }
+}
+
+///=========================================================================================
+/// RecordContext
+///=========================================================================================
+/// # 錄影 Context
+/// 包裝其他 Context, 轉送畫面和輸入, 同時錄下畫面
+pub struct RecordContext<C: Context> {
+  context: C,
+  recorder: RefCell<Recorder>,
+}
+
+impl<C: Context> RecordContext<C> {
+  pub fn new(context: C, recorder: Recorder) -> Self {
+    RecordContext {
+      context,
+      recorder: RefCell::new(recorder),
+    }
+  }
+  pub fn context(&self) -> &C {
+    &self.context
+  }
+  pub fn recorder(&self) -> std::cell::Ref<'_, Recorder> {
+    self.recorder.borrow()
+  }
+  pub fn recorder_mut(&self) -> std::cell::RefMut<'_, Recorder> {
+    self.recorder.borrow_mut()
+  }
+  pub fn into_inner(self) -> (C, Recorder) {
+    (self.context, self.recorder.into_inner())
+  }
+}
+
+impl<C: Context> Context for RecordContext<C> {
+  fn control(&self) -> Option<Control> {
+    self.context.control()
+  }
+  fn render(&self, frame: RenderFrame) -> Option<()> {
+    self.recorder.borrow_mut().capture(&frame);
+    self.context.render(frame)
+  }
+}
+
+#[test]
+fn test() {
+  use std::cell::Cell;
+
+  use crate::utils::{rect::Rect, vector::Vector};
+
+  use super::render::{Texture, ViewPort};
+
+  struct Counter(Cell<usize>);
+  impl Context for Counter {
+    fn control(&self) -> Option<Control> {
+      None
+    }
+    fn render(&self, _: RenderFrame) -> Option<()> {
+      self.0.set(self.0.get() + 1);
+      Some(())
+    }
+  }
+
+  let frame = |x: f32| {
+    let mut frame = RenderFrame::new();
+    frame.push((
+      Rect::new(Vector::new(x, 0.), Vector::new(4., 4.)),
+      Texture::Color("#ff0000".to_string()),
+    ));
+    let mut viewport = ViewPort::new();
+    viewport.set_size(Vector::new(16., 16.));
+    frame.set_viewport(viewport);
+    frame
+  };
+
+  // frames 2, 4, 6 of 0..7
+  let recorder = Recorder::new(16, 16)
+    .with_scale(0.5)
+    .with_skip(1)
+    .with_range(2..7);
+  let context = RecordContext::new(Counter(Cell::new(0)), recorder);
+  for index in 0..10 {
+    context.render(frame(index as f32 - 5.));
+  }
+  let (counter, recorder) = context.into_inner();
+  assert_eq!(counter.0.get(), 10);
+  assert!(recorder.is_done());
+  assert_eq!(recorder.len(), 3);
+  assert_eq!(recorder.size(), (8, 8));
+  assert_eq!(recorder.delay(), 32);
+  // square moves right by 2 units each kept frame, 1 pixel at half scale
+  let red = |image: &Image| (0..8).find(|x| image.get(*x, 4).unwrap()[0] == 255);
+  let columns: Vec<_> = recorder.frames().iter().map(red).collect();
+  assert_eq!(columns, vec![Some(1), Some(2), Some(3)]);
+
+  let gif = recorder.encode(RecordFormat::Gif).unwrap();
+  assert!(gif.starts_with(b"GIF89a"));
+  let mut decoder = gif::DecodeOptions::new();
+  decoder.set_color_output(gif::ColorOutput::RGBA);
+  let mut decoder = decoder.read_info(&gif[..]).unwrap();
+  let mut count = 0;
+  while let Some(frame) = decoder.read_next_frame().unwrap() {
+    assert_eq!((frame.width, frame.height, frame.delay), (8, 8, 3));
+    count += 1;
+  }
+  assert_eq!(count, 3);
+
+  let apng = recorder.encode(RecordFormat::Apng).unwrap();
+  let decoder = png::Decoder::new(&apng[..]);
+  let reader = decoder.read_info().unwrap();
+  let animation = reader.info().animation_control.unwrap();
+  assert_eq!((animation.num_frames, animation.num_plays), (3, 0));
+  // first frame is also default image
+  assert_eq!(Image::decode_png(&apng).unwrap(), recorder.frames()[0]);
+
+  assert_eq!(RecordFormat::from_path("bug.GIF"), Some(RecordFormat::Gif));
+  assert!(Recorder::new(4, 4).encode(RecordFormat::Gif).is_err());
+}
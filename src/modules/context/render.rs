@@ -2,7 +2,17 @@ use std::mem;
 
 use serde::{Deserialize, Serialize};
 
-use crate::utils::{rect::Rect, vector::Vector, viewbox::ViewBox};
+use crate::{
+  modules::asset::AssetHandle,
+  utils::{
+    geometry::Aabb,
+    rect::Rect,
+    scalar::{scalar, Scalar},
+    transform::Transform2D,
+    vector::Vector,
+    viewbox::ViewBox,
+  },
+};
 
 ///==================================================================
 /// Traits
@@ -15,11 +25,14 @@ pub trait Render {
 ///==================================================================
 /// RenderFrame
 ///==================================================================
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RenderFrame {
   entities: Vec<(Rect, Texture)>,
   ui: Vec<(Rect, Texture)>,
   viewport: ViewPort,
+  /// key of each item in entities, `None` if pushed without key
+  keys: Vec<Option<DrawKey>>,
+  ui_keys: Vec<Option<DrawKey>>,
 }
 
 impl RenderFrame {
@@ -28,6 +41,8 @@ impl RenderFrame {
       ui: vec![],
       entities: vec![],
       viewport: ViewPort::new(),
+      keys: vec![],
+      ui_keys: vec![],
     }
   }
   pub fn with_capacity(capacity: usize) -> Self {
@@ -35,6 +50,8 @@ impl RenderFrame {
       ui: Vec::with_capacity(capacity),
       entities: Vec::with_capacity(capacity),
       viewport: ViewPort::new(),
+      keys: Vec::with_capacity(capacity),
+      ui_keys: Vec::with_capacity(capacity),
     }
   }
   pub fn extend(&self) -> Self {
@@ -42,19 +59,33 @@ impl RenderFrame {
       ui: Vec::with_capacity(self.ui.capacity()),
       entities: Vec::with_capacity(self.entities.capacity()),
       viewport: self.viewport.clone(),
+      keys: Vec::with_capacity(self.keys.capacity()),
+      ui_keys: Vec::with_capacity(self.ui_keys.capacity()),
     }
   }
   pub fn append(&mut self, input: &Vec<(Rect, Texture)>) {
-    self.entities.extend(input.iter().cloned())
+    self.entities.extend(input.iter().cloned());
+    self.keys.resize(self.entities.len(), None);
   }
   pub fn push(&mut self, input: (Rect, Texture)) {
-    self.entities.push(input)
+    self.entities.push(input);
+    self.keys.push(None);
+  }
+  pub fn push_keyed(&mut self, key: DrawKey, input: (Rect, Texture)) {
+    self.entities.push(input);
+    self.keys.push(Some(key));
   }
   pub fn append_ui(&mut self, input: &Vec<(Rect, Texture)>) {
-    self.ui.extend(input.iter().cloned())
+    self.ui.extend(input.iter().cloned());
+    self.ui_keys.resize(self.ui.len(), None);
   }
   pub fn push_ui(&mut self, input: (Rect, Texture)) {
-    self.ui.push(input)
+    self.ui.push(input);
+    self.ui_keys.push(None);
+  }
+  pub fn push_ui_keyed(&mut self, key: DrawKey, input: (Rect, Texture)) {
+    self.ui.push(input);
+    self.ui_keys.push(Some(key));
   }
   pub fn get(&self) -> &Vec<(Rect, Texture)> {
     &self.entities
@@ -62,6 +93,12 @@ impl RenderFrame {
   pub fn get_ui(&self) -> &Vec<(Rect, Texture)> {
     &self.ui
   }
+  pub fn keys(&self) -> &Vec<Option<DrawKey>> {
+    &self.keys
+  }
+  pub fn ui_keys(&self) -> &Vec<Option<DrawKey>> {
+    &self.ui_keys
+  }
   pub fn set_viewport(&mut self, viewport: ViewPort) {
     self.viewport = viewport
   }
@@ -73,19 +110,78 @@ impl RenderFrame {
     mem::replace(self, frame)
   }
   pub fn sort(&mut self) {
-    self
-      .entities
-      .sort_by(|a, b| a.0.position.1.partial_cmp(&b.0.position.1).unwrap())
+    let mut keyed: Vec<_> = mem::take(&mut self.entities)
+      .into_iter()
+      .zip(mem::take(&mut self.keys))
+      .collect();
+    keyed.sort_by(|a, b| a.0 .0.position.1.partial_cmp(&b.0 .0.position.1).unwrap());
+    (self.entities, self.keys) = keyed.into_iter().unzip();
+  }
+}
+
+/// # 繪製鍵
+/// 以實體 uuid, 部件名稱和部件內索引識別繪製項目, 用於畫面差異
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DrawKey {
+  pub enity: uuid::Uuid,
+  pub part: String,
+  pub index: u32,
+}
+
+impl DrawKey {
+  pub fn new(enity: uuid::Uuid, part: &str, index: u32) -> Self {
+    DrawKey {
+      enity,
+      part: part.to_string(),
+      index,
+    }
   }
 }
 
 ///==================================================================
 /// Texture
 ///==================================================================
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # 材質
+/// 顏色為 `#rrggbb` 或 `#rrggbbaa`, 圖片區域以像素計, 左上為原點
+/// 漸層座標以繪製矩形為單位空間, 左下 (0, 0) 至右上 (1, 1)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Texture {
   Color(String),
   Bitmap(String),
+  /// sub-rectangle of an image, e.g. sprite in atlas
+  Region {
+    source: String,
+    region: Region,
+  },
+  LinearGradient {
+    from: Vector,
+    to: Vector,
+    stops: Vec<GradientStop>,
+  },
+  RadialGradient {
+    center: Vector,
+    radius: Scalar,
+    stops: Vec<GradientStop>,
+  },
+  /// corners keep size, edges and center stretch
+  NineSlice {
+    source: String,
+    region: Option<Region>,
+    slice: Slice,
+  },
+  /// image loaded in asset registry
+  Asset(AssetHandle),
+  AssetRegion {
+    asset: AssetHandle,
+    region: Region,
+  },
+  /// text rasterized and cached by engine
+  Text(Text),
+  /// per-draw tint, opacity and flip
+  Styled {
+    texture: Box<Texture>,
+    style: Style,
+  },
 }
 
 impl Default for Texture {
@@ -94,13 +190,255 @@ impl Default for Texture {
   }
 }
 
+impl Texture {
+  pub fn region(source: &str, region: Region) -> Self {
+    Texture::Region {
+      source: source.to_string(),
+      region,
+    }
+  }
+  pub fn nine_slice(source: &str, slice: Slice) -> Self {
+    Texture::NineSlice {
+      source: source.to_string(),
+      region: None,
+      slice,
+    }
+  }
+  pub fn linear_gradient(from: Vector, to: Vector, stops: Vec<GradientStop>) -> Self {
+    Texture::LinearGradient { from, to, stops }
+  }
+  pub fn radial_gradient(center: Vector, radius: Scalar, stops: Vec<GradientStop>) -> Self {
+    Texture::RadialGradient {
+      center,
+      radius,
+      stops,
+    }
+  }
+
+  /// texture without style
+  pub fn inner(&self) -> &Texture {
+    match self {
+      Texture::Styled { texture, .. } => texture.inner(),
+      texture => texture,
+    }
+  }
+  pub fn style(&self) -> Style {
+    match self {
+      Texture::Styled { style, .. } => style.clone(),
+      _ => Style::default(),
+    }
+  }
+  /// image path drawn from, if any
+  pub fn source(&self) -> Option<&str> {
+    match self.inner() {
+      Texture::Bitmap(source)
+      | Texture::Region { source, .. }
+      | Texture::NineSlice { source, .. } => Some(source),
+      _ => None,
+    }
+  }
+
+  /// asset handle drawn from, if any
+  pub fn asset(&self) -> Option<AssetHandle> {
+    match self.inner() {
+      Texture::Asset(asset) | Texture::AssetRegion { asset, .. } => Some(*asset),
+      _ => None,
+    }
+  }
+
+  //================================================================================
+  // Style
+  //================================================================================
+  pub fn with_style(self, style: Style) -> Self {
+    match self {
+      Texture::Styled { texture, .. } => Texture::Styled { texture, style },
+      texture => Texture::Styled {
+        texture: Box::new(texture),
+        style,
+      },
+    }
+  }
+  pub fn with_tint(self, color: &str) -> Self {
+    let style = self.style();
+    self.with_style(Style {
+      tint: Some(color.to_string()),
+      ..style
+    })
+  }
+  pub fn with_opacity(self, opacity: f32) -> Self {
+    let style = self.style();
+    self.with_style(Style { opacity, ..style })
+  }
+  pub fn with_flip(self, flip_x: bool, flip_y: bool) -> Self {
+    let style = self.style();
+    self.with_style(Style {
+      flip_x,
+      flip_y,
+      ..style
+    })
+  }
+}
+
+/// pixel rectangle in image, left-top origin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Region {
+  pub x: u32,
+  pub y: u32,
+  pub width: u32,
+  pub height: u32,
+}
+
+impl Region {
+  pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+    Region {
+      x,
+      y,
+      width,
+      height,
+    }
+  }
+}
+
+/// border width in pixels of nine-slice image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Slice {
+  pub left: u32,
+  pub top: u32,
+  pub right: u32,
+  pub bottom: u32,
+}
+
+impl Slice {
+  pub fn new(left: u32, top: u32, right: u32, bottom: u32) -> Self {
+    Slice {
+      left,
+      top,
+      right,
+      bottom,
+    }
+  }
+  pub fn uniform(border: u32) -> Self {
+    Slice::new(border, border, border, border)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+  /// `0.0 ~ 1.0` along gradient
+  pub offset: f32,
+  pub color: String,
+}
+
+impl GradientStop {
+  pub fn new(offset: f32, color: &str) -> Self {
+    GradientStop {
+      offset,
+      color: color.to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum TextAlign {
+  #[default]
+  Left,
+  Center,
+  Right,
+}
+
+/// # 文字
+/// 以 `\n` 換行, 設定 wrap 時依像素寬度自動換行
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Text {
+  pub text: String,
+  /// font size in pixels
+  pub size: u32,
+  pub color: String,
+  #[serde(default)]
+  pub wrap: Option<u32>,
+  #[serde(default)]
+  pub align: TextAlign,
+}
+
+impl Text {
+  pub fn new(text: &str, size: u32, color: &str) -> Self {
+    Text {
+      text: text.to_string(),
+      size,
+      color: color.to_string(),
+      wrap: None,
+      align: TextAlign::Left,
+    }
+  }
+  pub fn with_wrap(mut self, width: u32) -> Self {
+    self.wrap = Some(width);
+    self
+  }
+  pub fn with_align(mut self, align: TextAlign) -> Self {
+    self.align = align;
+    self
+  }
+}
+
+/// tint multiplies texture color, opacity multiplies alpha
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Style {
+  #[serde(default)]
+  pub tint: Option<String>,
+  #[serde(default = "opacity")]
+  pub opacity: f32,
+  #[serde(default)]
+  pub flip_x: bool,
+  #[serde(default)]
+  pub flip_y: bool,
+}
+
+impl Default for Style {
+  fn default() -> Self {
+    Style {
+      tint: None,
+      opacity: opacity(),
+      flip_x: false,
+      flip_y: false,
+    }
+  }
+}
+
+fn opacity() -> f32 {
+  1.
+}
+
 ///==================================================================
 /// ViewPort
 ///==================================================================
-#[derive(Debug, Clone, Copy)]
+/// # 寬高比策略
+/// 螢幕形狀和視口不同時的縮放方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Aspect {
+  /// whole viewport visible, letterbox bars fill the rest
+  #[default]
+  Fit,
+  /// screen covered, viewport cropped
+  Fill,
+  /// viewport stretched to screen, not uniform
+  Stretch,
+  /// height kept, width follows screen
+  FixedHeight,
+}
+
+/// # 視口
+/// 中心位置, 大小, 縮放和旋轉決定可見的場景範圍
+/// 螢幕大小 (像素) 未設定時視為和視口同比例
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct ViewPort {
   position: Vector,
   size: Vector,
+  /// larger shows less of scene
+  zoom: Scalar,
+  /// counter-clockwise camera rotation in radians
+  angle: Scalar,
+  aspect: Aspect,
+  screen: Option<Vector>,
 }
 
 impl ViewPort {
@@ -108,11 +446,15 @@ impl ViewPort {
     ViewPort {
       position: Vector::ORIGIN,
       size: Vector::new(1000., 1000.),
+      zoom: scalar(1.),
+      angle: scalar(0.),
+      aspect: Aspect::Fit,
+      screen: None,
     }
   }
   //left - bottom
   pub fn origin(&self) -> Vector {
-    self.position() - self.size() * 0.5
+    self.position() - self.size() * scalar(0.5)
   }
   pub fn set_position(&mut self, vector: Vector) {
     self.position = vector
@@ -120,19 +462,194 @@ impl ViewPort {
   pub fn set_size(&mut self, vector: Vector) {
     self.size = vector
   }
+  pub fn zoom(&self) -> Scalar {
+    self.zoom
+  }
+  /// ignored if not positive
+  pub fn set_zoom(&mut self, zoom: Scalar) {
+    if zoom > 0. {
+      self.zoom = zoom
+    }
+  }
+  pub fn set_angle(&mut self, angle: Scalar) {
+    self.angle = angle
+  }
+  pub fn aspect(&self) -> Aspect {
+    self.aspect
+  }
+  pub fn set_aspect(&mut self, aspect: Aspect) {
+    self.aspect = aspect
+  }
+  /// screen size in pixels
+  pub fn screen(&self) -> Vector {
+    self.screen.unwrap_or(self.size)
+  }
+  pub fn set_screen(&mut self, screen: Option<Vector>) {
+    self.screen = screen
+  }
+  pub fn with_screen(mut self, screen: Vector) -> Self {
+    self.screen = Some(screen);
+    self
+  }
+
+  //================================================================================
+  // Aspect
+  //================================================================================
+  /// scene area shown on screen, before rotation
+  pub fn visible_size(&self) -> Vector {
+    let size = self.size / self.zoom;
+    let screen = self.screen();
+    if size.0 <= 0. || size.1 <= 0. || screen.0 <= 0. || screen.1 <= 0. {
+      return size;
+    }
+    let ratio = screen.0 / screen.1;
+    match self.aspect {
+      Aspect::Fit | Aspect::Stretch => size,
+      Aspect::Fill if ratio > size.0 / size.1 => Vector(size.0, size.0 / ratio),
+      Aspect::Fill | Aspect::FixedHeight => Vector(size.1 * ratio, size.1),
+    }
+  }
+  /// pixels per scene unit on each axis
+  pub fn pixel_scale(&self) -> Vector {
+    let visible = self.visible_size();
+    let screen = self.screen();
+    if visible.0 <= 0. || visible.1 <= 0. {
+      return Vector::new(1., 1.);
+    }
+    let scale = screen / visible;
+    match self.aspect {
+      Aspect::Stretch => scale,
+      Aspect::Fit => Vector(scale.0.min(scale.1), scale.0.min(scale.1)),
+      Aspect::Fill | Aspect::FixedHeight => Vector(scale.1, scale.1),
+    }
+  }
+  /// drawn area in pixels, smaller than screen with letterbox
+  pub fn letterbox(&self) -> Aabb {
+    let screen = self.screen();
+    let content = self.visible_size() * self.pixel_scale();
+    let content = Vector(content.0.min(screen.0), content.1.min(screen.1));
+    let min = (screen - content) / scalar(2.);
+    Aabb::new(min, min + content)
+  }
+
+  //================================================================================
+  // Mapping
+  //================================================================================
+  /// normalized viewport space (-1 ~ 1 by visible height) to scene transform
+  pub fn transform(&self) -> Transform2D {
+    let scale = self.visible_size().1 / scalar(2.);
+    Transform2D::new(self.position, self.angle).with_scale(Vector(scale, scale))
+  }
   pub fn map_to_viewport(&self, vector: Vector) -> Vector {
-    vector * self.size.1 / 2. + self.position
+    self.transform().apply(vector)
+  }
+  /// scene point to screen pixel, left-top origin and y down
+  pub fn to_screen(&self, point: Vector) -> Vector {
+    let local = Transform2D::from_rotation(-self.angle).apply(point - self.position);
+    let local = local * self.pixel_scale();
+    self.screen() / scalar(2.) + Vector(local.0, -local.1)
+  }
+  /// screen pixel to scene point
+  pub fn from_screen(&self, pixel: Vector) -> Vector {
+    let local = pixel - self.screen() / scalar(2.);
+    let local = Vector(local.0, -local.1) / self.pixel_scale();
+    self.position + Transform2D::from_rotation(self.angle).apply(local)
   }
 }
 
 impl ViewBox for ViewPort {
-  fn angle(&self) -> f32 {
-    0.0
+  fn angle(&self) -> Scalar {
+    self.angle
   }
   fn position(&self) -> Vector {
     self.position
   }
+  /// visible size with zoom and aspect
   fn size(&self) -> Vector {
-    self.size
+    self.visible_size()
   }
 }
+
+#[test]
+fn test() {
+  let texture = Texture::region("atlas.png", Region::new(16, 0, 16, 16))
+    .with_tint("#ff0000")
+    .with_flip(true, false)
+    .with_opacity(0.5);
+  assert_eq!(texture.source(), Some("atlas.png"));
+  assert_eq!(
+    texture.inner(),
+    &Texture::region("atlas.png", Region::new(16, 0, 16, 16))
+  );
+  let style = texture.style();
+  assert_eq!(style.tint.as_deref(), Some("#ff0000"));
+  assert!(style.flip_x && !style.flip_y);
+  assert_eq!(style.opacity, 0.5);
+
+  let gradient = Texture::linear_gradient(
+    Vector::ORIGIN,
+    Vector::new(1., 0.),
+    vec![
+      GradientStop::new(0., "#000000"),
+      GradientStop::new(1., "#ffffff"),
+    ],
+  );
+  for texture in [
+    texture,
+    gradient,
+    Texture::nine_slice("panel.png", Slice::uniform(4)),
+  ] {
+    let json = serde_json::to_string(&texture).unwrap();
+    assert_eq!(serde_json::from_str::<Texture>(&json).unwrap(), texture);
+  }
+  // style fields are optional
+  let style: Style = serde_json::from_str("{}").unwrap();
+  assert_eq!(style, Style::default());
+
+  // 200 x 100 viewport on 400 x 400 screen
+  let close = |a: Vector, b: Vector| a.to(b).distance() < 1e-3;
+  let mut viewport = ViewPort::new();
+  viewport.set_position(Vector::new(10., 0.));
+  viewport.set_size(Vector::new(200., 100.));
+  viewport.set_screen(Some(Vector::new(400., 400.)));
+  for (aspect, visible, scale, letterbox) in [
+    (Aspect::Fit, (200., 100.), (2., 2.), (0., 100., 400., 300.)),
+    (Aspect::Fill, (100., 100.), (4., 4.), (0., 0., 400., 400.)),
+    (Aspect::Stretch, (200., 100.), (2., 4.), (0., 0., 400., 400.)),
+    (Aspect::FixedHeight, (100., 100.), (4., 4.), (0., 0., 400., 400.)),
+  ] {
+    viewport.set_aspect(aspect);
+    assert_eq!(viewport.visible_size(), Vector::new(visible.0, visible.1));
+    assert_eq!(viewport.pixel_scale(), Vector::new(scale.0, scale.1));
+    let Aabb { min, max } = viewport.letterbox();
+    assert_eq!((min, max), {
+      let (x, y, right, bottom) = letterbox;
+      (Vector::new(x, y), Vector::new(right, bottom))
+    });
+  }
+
+  // zoom shows less, rotation turns the camera
+  viewport.set_aspect(Aspect::Fit);
+  viewport.set_zoom(scalar(2.));
+  viewport.set_zoom(scalar(0.));
+  assert_eq!(viewport.zoom(), 2.);
+  assert_eq!(ViewBox::size(&viewport), Vector::new(100., 50.));
+  assert_eq!(viewport.to_screen(Vector::new(10., 0.)), Vector::new(200., 200.));
+  assert!(close(
+    viewport.to_screen(Vector::new(20., 5.)),
+    Vector::new(240., 180.)
+  ));
+  viewport.set_angle(scalar(std::f32::consts::FRAC_PI_2));
+  // camera turned left, so point right of center is below it on screen
+  assert!(close(
+    viewport.to_screen(Vector::new(20., 0.)),
+    Vector::new(200., 240.)
+  ));
+  let point = Vector::new(-3., 7.);
+  assert!(close(viewport.from_screen(viewport.to_screen(point)), point));
+  // normalized (1, 0) is half visible height along rotated x axis
+  assert!(close(
+    viewport.map_to_viewport(Vector::new(1., 0.)),
+    Vector::new(10., 25.)
+  ));
+}
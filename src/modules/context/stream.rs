@@ -0,0 +1,464 @@
+//! stream frames to external frontends
+//! 將畫面串流給外部前端
+//!
+//! Every message is an [`Envelope`] carrying [`STREAM_VERSION`].
+//! - `Json`: one JSON envelope per line (`\n` delimited)
+//! - `Postcard`: `u32` little-endian byte length, then postcard envelope
+//!
+//! The engine sends [`ServerMessage::Hello`] on attach, then one [`ServerMessage::Frame`]
+//! per render, or one [`ServerMessage::Update`] when diffing is enabled.
+//! The frontend sends [`ClientMessage::Control`] whenever its input changes.
+//!
+//! Coordinates are engine scalars, `Hello` tells which [`ScalarFormat`] is on the wire:
+//! - `Float`: `f32` numbers
+//! - `Fixed`: raw `i64` of 48.16 fixed point (built with feature `fixed`), divide by `65536`
+
+use std::{
+  io::{self, BufRead, BufReader, Read, Write},
+  net::{SocketAddr, TcpListener, ToSocketAddrs},
+  sync::{
+    mpsc::{self, SyncSender},
+    Arc, Mutex,
+  },
+  thread,
+  time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+  control::Control,
+  diff::{FrameDiffer, FrameUpdate},
+  render::RenderFrame,
+  Context,
+};
+
+/// bump when message layout changes
+pub const STREAM_VERSION: u32 = 4;
+
+/// largest postcard message accepted
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+
+/// default time a tcp write may block before the frontend is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// messages queued per frontend, a frontend falling further behind is dropped
+const QUEUE_SIZE: usize = 16;
+
+///=========================================================================================
+/// Message
+///=========================================================================================
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Encoding {
+  #[default]
+  Json,
+  Postcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<T> {
+  pub version: u32,
+  pub message: T,
+}
+
+/// representation of scalars in messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScalarFormat {
+  Float,
+  Fixed,
+}
+
+impl ScalarFormat {
+  /// format of this build
+  pub const fn current() -> Self {
+    match cfg!(feature = "fixed") {
+      true => ScalarFormat::Fixed,
+      false => ScalarFormat::Float,
+    }
+  }
+}
+
+/// engine to frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ServerMessage {
+  Hello {
+    version: u32,
+    encoding: Encoding,
+    scalar: ScalarFormat,
+  },
+  Frame(RenderFrame),
+  /// keyframe or delta, see [`super::diff::FrameApplier`]
+  Update(FrameUpdate),
+}
+
+/// frontend to engine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+  Control(Control),
+}
+
+/// write one framed message
+pub fn write_message<T: Serialize>(
+  writer: &mut impl Write,
+  encoding: Encoding,
+  message: &T,
+) -> io::Result<()> {
+  let envelope = Envelope {
+    version: STREAM_VERSION,
+    message,
+  };
+  match encoding {
+    Encoding::Json => {
+      serde_json::to_writer(&mut *writer, &envelope)?;
+      writer.write_all(b"\n")?;
+    }
+    Encoding::Postcard => {
+      let bytes = postcard::to_stdvec(&envelope).map_err(invalid)?;
+      writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+      writer.write_all(&bytes)?;
+    }
+  }
+  writer.flush()
+}
+
+/// read one framed message, `None` at end of stream
+pub fn read_message<T: DeserializeOwned>(
+  reader: &mut impl BufRead,
+  encoding: Encoding,
+) -> io::Result<Option<T>> {
+  let envelope: Envelope<T> = match encoding {
+    Encoding::Json => {
+      let mut line = String::new();
+      loop {
+        if reader.read_line(&mut line)? == 0 {
+          return Ok(None);
+        }
+        if !line.trim().is_empty() {
+          break;
+        }
+        line.clear();
+      }
+      serde_json::from_str(&line)?
+    }
+    Encoding::Postcard => {
+      let mut length = [0; 4];
+      match reader.read_exact(&mut length) {
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        result => result?,
+      }
+      let length = u32::from_le_bytes(length) as usize;
+      if length > MAX_MESSAGE {
+        return Err(invalid(format!("message of {length} bytes")));
+      }
+      let mut bytes = vec![0; length];
+      reader.read_exact(&mut bytes)?;
+      postcard::from_bytes(&bytes).map_err(invalid)?
+    }
+  };
+  match envelope.version {
+    STREAM_VERSION => Ok(Some(envelope.message)),
+    version => Err(invalid(format!(
+      "stream version {version}, expect {STREAM_VERSION}"
+    ))),
+  }
+}
+
+fn invalid(error: impl ToString) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+///=========================================================================================
+/// StreamContext
+///=========================================================================================
+type Writer = Box<dyn Write + Send>;
+
+struct Shared {
+  encoding: Encoding,
+  write_timeout: Mutex<Duration>,
+  /// queue of every frontend, written by its own thread
+  writers: Mutex<Vec<SyncSender<Arc<Vec<u8>>>>>,
+  control: Mutex<Option<Control>>,
+  differ: Mutex<Option<FrameDiffer>>,
+}
+
+impl Shared {
+  /// send hello and read controls in background
+  fn attach(self: &Arc<Self>, reader: impl Read + Send + 'static, mut writer: Writer) {
+    let hello = ServerMessage::Hello {
+      version: STREAM_VERSION,
+      encoding: self.encoding,
+      scalar: ScalarFormat::current(),
+    };
+    let mut bytes = vec![];
+    if write_message(&mut bytes, self.encoding, &hello).is_err() {
+      return;
+    }
+    let (sender, receiver) = mpsc::sync_channel::<Arc<Vec<u8>>>(QUEUE_SIZE);
+    // empty queue always takes hello
+    let _ = sender.try_send(Arc::new(bytes));
+    // slow frontend only blocks its own thread,
+    // queue is dropped on write error and removed on next send
+    thread::spawn(move || {
+      for bytes in receiver {
+        if writer
+          .write_all(&bytes)
+          .and_then(|_| writer.flush())
+          .is_err()
+        {
+          break;
+        }
+      }
+    });
+    self.writers.lock().unwrap().push(sender);
+    // new frontend has no previous frame
+    if let Some(differ) = self.differ.lock().unwrap().as_mut() {
+      differ.force_keyframe();
+    }
+
+    let shared = self.clone();
+    thread::spawn(move || {
+      let mut reader = BufReader::new(reader);
+      while let Ok(Some(message)) = read_message(&mut reader, shared.encoding) {
+        match message {
+          ClientMessage::Control(control) => *shared.control.lock().unwrap() = Some(control),
+        }
+      }
+    });
+  }
+}
+
+/// # 串流 Context
+/// 無頭引擎經由 stdio 或 TCP 將畫面送給前端, 並接收前端的 Control
+/// 每個前端由各自的執行緒寫入, 斷線或落後太多的前端會在下次 render 時移除,
+/// TCP 前端寫入逾時也會被移除
+pub struct StreamContext {
+  shared: Arc<Shared>,
+  address: Option<SocketAddr>,
+}
+
+impl StreamContext {
+  /// without frontend, attach streams later
+  pub fn new(encoding: Encoding) -> Self {
+    StreamContext {
+      shared: Arc::new(Shared {
+        encoding,
+        write_timeout: Mutex::new(WRITE_TIMEOUT),
+        writers: Mutex::new(vec![]),
+        control: Mutex::new(None),
+        differ: Mutex::new(None),
+      }),
+      address: None,
+    }
+  }
+
+  /// frontend on stdin and stdout
+  pub fn stdio(encoding: Encoding) -> Self {
+    let context = StreamContext::new(encoding);
+    context.attach(io::stdin(), io::stdout());
+    context
+  }
+
+  /// accept frontends on address, e.g. `127.0.0.1:0`
+  pub fn tcp(address: impl ToSocketAddrs, encoding: Encoding) -> io::Result<Self> {
+    let listener = TcpListener::bind(address)?;
+    let mut context = StreamContext::new(encoding);
+    context.address = Some(listener.local_addr()?);
+    let shared = context.shared.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        let _ = stream.set_nodelay(true);
+        // stalled frontend would keep its writer thread forever
+        let timeout = *shared.write_timeout.lock().unwrap();
+        if stream.set_write_timeout(Some(timeout)).is_err() {
+          continue;
+        }
+        if let Ok(reader) = stream.try_clone() {
+          shared.attach(reader, Box::new(stream));
+        }
+      }
+    });
+    Ok(context)
+  }
+
+  /// send deltas with keyframe every `interval` frames instead of whole frames
+  pub fn with_diff(self, interval: u64) -> Self {
+    *self.shared.differ.lock().unwrap() = Some(FrameDiffer::new(interval));
+    self
+  }
+
+  /// drop tcp frontends blocking a write longer than `timeout`, applies to frontends accepted later
+  pub fn with_write_timeout(self, timeout: Duration) -> Self {
+    *self.shared.write_timeout.lock().unwrap() = timeout;
+    self
+  }
+
+  pub fn attach(&self, reader: impl Read + Send + 'static, writer: impl Write + Send + 'static) {
+    self.shared.attach(reader, Box::new(writer));
+  }
+
+  /// bound address of tcp server
+  pub fn address(&self) -> Option<SocketAddr> {
+    self.address
+  }
+  pub fn encoding(&self) -> Encoding {
+    self.shared.encoding
+  }
+  pub fn clients(&self) -> usize {
+    self.shared.writers.lock().unwrap().len()
+  }
+
+  /// queue message for every frontend, drop disconnected or stalled ones
+  pub fn send(&self, message: &ServerMessage) {
+    // encode once for every frontend
+    let mut bytes = vec![];
+    if write_message(&mut bytes, self.shared.encoding, message).is_err() {
+      return;
+    }
+    let bytes = Arc::new(bytes);
+    self
+      .shared
+      .writers
+      .lock()
+      .unwrap()
+      .retain(|writer| writer.try_send(bytes.clone()).is_ok());
+  }
+}
+
+impl Context for StreamContext {
+  /// latest control from any frontend
+  fn control(&self) -> Option<Control> {
+    self.shared.control.lock().unwrap().clone()
+  }
+  fn render(&self, frame: RenderFrame) -> Option<()> {
+    let update = match self.shared.differ.lock().unwrap().as_mut() {
+      Some(differ) => ServerMessage::Update(differ.encode(&frame)),
+      None => ServerMessage::Frame(frame),
+    };
+    self.send(&update);
+    Some(())
+  }
+}
+
+#[test]
+fn test() {
+  use std::net::TcpStream;
+
+  use crate::{
+    modules::context::{
+      control::KeyEvent,
+      render::{Texture, ViewPort},
+    },
+    utils::{rect::Rect, vector::Vector},
+  };
+
+  let mut frame = RenderFrame::new();
+  frame.push((
+    Rect::new(Vector::ORIGIN, Vector::new(10., 10.)),
+    Texture::Bitmap("hero.png".to_string()),
+  ));
+  frame.push_ui((
+    Rect::new(Vector::ORIGIN, Vector::new(1., 1.)),
+    Texture::default(),
+  ));
+  let mut viewport = ViewPort::new();
+  viewport.set_position(Vector::new(5., 5.));
+  frame.set_viewport(viewport);
+
+  // framing round trip
+  for encoding in [Encoding::Json, Encoding::Postcard] {
+    let mut bytes = vec![];
+    let message = ServerMessage::Frame(frame.clone());
+    write_message(&mut bytes, encoding, &message).unwrap();
+    write_message(&mut bytes, encoding, &message).unwrap();
+    let mut reader = io::Cursor::new(bytes);
+    for _ in 0..2 {
+      let read: Option<ServerMessage> = read_message(&mut reader, encoding).unwrap();
+      assert_eq!(read, Some(message.clone()));
+    }
+    assert_eq!(
+      read_message::<ServerMessage>(&mut reader, encoding).unwrap(),
+      None
+    );
+  }
+  // scalar on the wire follows hello
+  let json = serde_json::to_string(&Vector::new(1.5, 0.)).unwrap();
+  match ScalarFormat::current() {
+    ScalarFormat::Float => assert_eq!(json, "[1.5,0.0]"),
+    ScalarFormat::Fixed => assert_eq!(json, "[98304,0]"),
+  }
+  let old = br#"{"version":0,"message":{"Control":{"keys":[],"click":[null,null],"mouse":[0,0]}}}"#;
+  assert!(read_message::<ClientMessage>(&mut &old[..], Encoding::Json).is_err());
+
+  // tcp loopback
+  let context = StreamContext::tcp("127.0.0.1:0", Encoding::Postcard)
+    .unwrap()
+    .with_write_timeout(Duration::from_millis(100));
+  let stream = TcpStream::connect(context.address().unwrap()).unwrap();
+  stream
+    .set_read_timeout(Some(Duration::from_secs(5)))
+    .unwrap();
+  let mut reader = BufReader::new(stream.try_clone().unwrap());
+  let mut writer = stream;
+  let hello: Option<ServerMessage> = read_message(&mut reader, Encoding::Postcard).unwrap();
+  assert_eq!(
+    hello,
+    Some(ServerMessage::Hello {
+      version: STREAM_VERSION,
+      encoding: Encoding::Postcard,
+      scalar: ScalarFormat::current(),
+    })
+  );
+
+  let mut control = Control::new();
+  control.keys.push(KeyEvent {
+    code: "KeyW".to_string(),
+    alt: false,
+    ctrl: false,
+    meta: false,
+    shift: false,
+    repeat: false,
+  });
+  write_message(
+    &mut writer,
+    Encoding::Postcard,
+    &ClientMessage::Control(control.clone()),
+  )
+  .unwrap();
+  let mut received = None;
+  for _ in 0..500 {
+    received = context.control();
+    if received.is_some() {
+      break;
+    }
+    thread::sleep(Duration::from_millis(10));
+  }
+  assert_eq!(received, Some(control));
+
+  context.render(frame.clone());
+  let streamed: Option<ServerMessage> = read_message(&mut reader, Encoding::Postcard).unwrap();
+  assert_eq!(streamed, Some(ServerMessage::Frame(frame.clone())));
+  assert_eq!(context.clients(), 1);
+
+  // frontend that never reads is dropped once its queue fills,
+  // without blocking the other frontend
+  struct Stalled(mpsc::Receiver<()>);
+  impl Write for Stalled {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+      let _ = self.0.recv();
+      Err(io::ErrorKind::BrokenPipe.into())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+  let (release, stalled) = mpsc::channel();
+  context.attach(io::empty(), Stalled(stalled));
+  assert_eq!(context.clients(), 2);
+  for _ in 0..=QUEUE_SIZE {
+    context.render(frame.clone());
+    let read: Option<ServerMessage> = read_message(&mut reader, Encoding::Postcard).unwrap();
+    assert!(matches!(read, Some(ServerMessage::Frame(_))));
+  }
+  assert_eq!(context.clients(), 1);
+  drop(release);
+}
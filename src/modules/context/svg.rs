@@ -0,0 +1,409 @@
+use std::{collections::HashMap, fmt::Write, fs, io, path::Path};
+
+use crate::{
+  modules::asset::{AssetHandle, AssetRegistry},
+  utils::{
+    rect::Rect,
+    scalar::{scalar, to_f32, Scalar},
+    vector::Vector,
+    viewbox::ViewBox,
+  },
+};
+
+use super::render::{Aspect, GradientStop, Region, RenderFrame, Style, Text, TextAlign, Texture};
+
+///=========================================================================================
+/// SvgExport
+///=========================================================================================
+/// # SVG 輸出
+/// 將 RenderFrame 輸出為 SVG 文件, 視口即 viewBox, 場景座標 y 軸向上
+/// 圖片以路徑引用, 九宮格以整張拉伸表示, 不需點陣化
+#[derive(Debug, Clone)]
+pub struct SvgExport {
+  hitboxes: Vec<Rect>,
+  hitbox_color: String,
+  assets: HashMap<AssetHandle, String>,
+  ui: bool,
+}
+
+impl Default for SvgExport {
+  fn default() -> Self {
+    SvgExport {
+      hitboxes: vec![],
+      hitbox_color: "#ff00ff".to_string(),
+      assets: HashMap::new(),
+      ui: true,
+    }
+  }
+}
+
+impl SvgExport {
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// outline rects over the frame, in scene space
+  pub fn with_hitboxes(mut self, hitboxes: Vec<Rect>) -> Self {
+    self.hitboxes = hitboxes;
+    self
+  }
+  pub fn with_hitbox_color(mut self, color: &str) -> Self {
+    self.hitbox_color = color.to_string();
+    self
+  }
+  /// reference asset textures by registry path
+  pub fn with_assets(mut self, registry: &AssetRegistry) -> Self {
+    self.assets = registry
+      .iter()
+      .map(|(handle, asset)| (*handle, asset.path().to_string()))
+      .collect();
+    self
+  }
+  /// draw ui layer over viewport, default true
+  pub fn with_ui(mut self, ui: bool) -> Self {
+    self.ui = ui;
+    self
+  }
+
+  pub fn save(&self, frame: &RenderFrame, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, self.render(frame))
+  }
+
+  pub fn render(&self, frame: &RenderFrame) -> String {
+    let viewport = frame.viewport();
+    let mut svg = Svg::default();
+    let mut body = String::new();
+
+    // ui is in normalized viewport space
+    let transform = viewport.transform();
+    let ui: Vec<(Rect, Texture)> = match self.ui {
+      true => frame
+        .get_ui()
+        .iter()
+        .map(|(rect, texture)| (transform.apply_rect(*rect), texture.clone()))
+        .collect(),
+      false => vec![],
+    };
+    for (layer, items) in [("world", frame.get()), ("ui", &ui)] {
+      if items.is_empty() {
+        continue;
+      }
+      let _ = writeln!(body, r#"<g class="{layer}">"#);
+      for (rect, texture) in items {
+        body.push_str(&svg.item(rect, texture, &self.assets));
+      }
+      body.push_str("</g>\n");
+    }
+    if !self.hitboxes.is_empty() {
+      let _ = writeln!(
+        body,
+        r#"<g class="hitbox" fill="{0}" fill-opacity="0.2" stroke="{0}" stroke-width="{1}">"#,
+        escape(&self.hitbox_color),
+        number(viewport.size().1 / scalar(500.)),
+      );
+      for hitbox in self.hitboxes.iter() {
+        let points: Vec<String> = hitbox.points().iter().map(|point| pair(*point)).collect();
+        let _ = writeln!(body, r#"<polygon points="{}"/>"#, points.join(" "));
+      }
+      body.push_str("</g>\n");
+    }
+
+    // unrotated visible area, camera rotation turns the scene the other way
+    let (position, visible) = (viewport.position(), viewport.visible_size());
+    let aspect = match viewport.aspect() {
+      Aspect::Fit | Aspect::FixedHeight => "xMidYMid meet",
+      Aspect::Fill => "xMidYMid slice",
+      Aspect::Stretch => "none",
+    };
+    let mut document = String::new();
+    let _ = writeln!(
+      document,
+      r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" preserveAspectRatio="{aspect}">"#,
+      number(position.0 - visible.0 / scalar(2.)),
+      number(-position.1 - visible.1 / scalar(2.)),
+      number(visible.0),
+      number(visible.1),
+    );
+    if !svg.defs.is_empty() {
+      let _ = writeln!(document, "<defs>\n{}</defs>", svg.defs);
+    }
+    let angle = to_f32(viewport.angle());
+    if angle != 0. {
+      let _ = writeln!(
+        document,
+        r#"<g transform="rotate({} {} {})">"#,
+        trim(angle.to_degrees()),
+        number(position.0),
+        number(-position.1)
+      );
+      document.push_str(&body);
+      document.push_str("</g>\n");
+    } else {
+      document.push_str(&body);
+    }
+    document.push_str("</svg>\n");
+    document
+  }
+}
+
+//=========================================================================================
+// Svg
+//=========================================================================================
+/// defs shared by items, e.g. gradients and tint filters
+#[derive(Debug, Default)]
+struct Svg {
+  defs: String,
+  next: usize,
+  filters: HashMap<String, String>,
+}
+
+impl Svg {
+  fn id(&mut self, prefix: &str) -> String {
+    self.next += 1;
+    format!("{prefix}{}", self.next)
+  }
+
+  /// one draw item, rotated around its center
+  fn item(
+    &mut self,
+    rect: &Rect,
+    texture: &Texture,
+    assets: &HashMap<AssetHandle, String>,
+  ) -> String {
+    let style = texture.style();
+    let (width, height) = (rect.size.0, rect.size.1);
+    let (x, y) = (number(-width / scalar(2.)), number(-height / scalar(2.)));
+    let size = format!(
+      r#"x="{x}" y="{y}" width="{}" height="{}""#,
+      number(width),
+      number(height)
+    );
+    let shape = match texture.inner() {
+      Texture::Color(color) => format!(r#"<rect {size} fill="{}"/>"#, escape(color)),
+      Texture::Bitmap(source) => image(&size, source, None),
+      Texture::Region { source, region } => image(&size, source, Some(region)),
+      Texture::NineSlice { source, region, .. } => image(&size, source, region.as_ref()),
+      Texture::Asset(asset) => image(&size, &asset_path(assets, asset), None),
+      Texture::AssetRegion { asset, region } => {
+        image(&size, &asset_path(assets, asset), Some(region))
+      }
+      Texture::LinearGradient { from, to, stops } => {
+        let id = self.id("gradient");
+        let _ = writeln!(
+          self.defs,
+          r#"<linearGradient id="{id}" x1="{}" y1="{}" x2="{}" y2="{}">{}</linearGradient>"#,
+          number(from.0),
+          number(scalar(1.) - from.1),
+          number(to.0),
+          number(scalar(1.) - to.1),
+          gradient_stops(stops),
+        );
+        format!(r#"<rect {size} fill="url(#{id})"/>"#)
+      }
+      Texture::RadialGradient {
+        center,
+        radius,
+        stops,
+      } => {
+        let id = self.id("gradient");
+        let _ = writeln!(
+          self.defs,
+          r#"<radialGradient id="{id}" cx="{}" cy="{}" r="{}">{}</radialGradient>"#,
+          number(center.0),
+          number(scalar(1.) - center.1),
+          number(*radius),
+          gradient_stops(stops),
+        );
+        format!(r#"<rect {size} fill="url(#{id})"/>"#)
+      }
+      Texture::Text(text) => text_element(text, width, height),
+      Texture::Styled { .. } => unreachable!("inner texture is not styled"),
+    };
+
+    let mut transform = format!("translate({})", pair(rect.position));
+    let angle = to_f32(rect.angle);
+    if angle != 0. {
+      // scene is counter-clockwise with y up, svg rotates clockwise with y down
+      let _ = write!(transform, " rotate({})", trim(-angle.to_degrees()));
+    }
+    if style.flip_x || style.flip_y {
+      let flip = |flip: bool| if flip { -1 } else { 1 };
+      let _ = write!(
+        transform,
+        " scale({} {})",
+        flip(style.flip_x),
+        flip(style.flip_y)
+      );
+    }
+    format!(
+      "<g transform=\"{transform}\"{}>{shape}</g>\n",
+      self.style(&style)
+    )
+  }
+
+  /// opacity and tint attributes
+  fn style(&mut self, style: &Style) -> String {
+    let mut attributes = String::new();
+    if style.opacity != 1. {
+      let _ = write!(attributes, r#" opacity="{}""#, trim(style.opacity));
+    }
+    if let Some(tint) = &style.tint {
+      let id = match self.filters.get(tint) {
+        Some(id) => id.clone(),
+        None => {
+          let id = self.id("tint");
+          let _ = writeln!(
+            self.defs,
+            r#"<filter id="{id}"><feFlood flood-color="{}"/><feComposite in2="SourceAlpha" operator="in"/><feBlend in2="SourceGraphic" mode="multiply"/><feComposite in2="SourceAlpha" operator="in"/></filter>"#,
+            escape(tint)
+          );
+          self.filters.insert(tint.clone(), id.clone());
+          id
+        }
+      };
+      let _ = write!(attributes, r#" filter="url(#{id})""#);
+    }
+    attributes
+  }
+}
+
+/// whole image, or region of it cropped by nested viewBox
+fn image(size: &str, source: &str, region: Option<&Region>) -> String {
+  let href = escape(source);
+  match region {
+    None => format!(r#"<image {size} preserveAspectRatio="none" href="{href}"/>"#),
+    Some(region) => format!(
+      r#"<svg {size} viewBox="{} {} {} {}" preserveAspectRatio="none"><image href="{href}"/></svg>"#,
+      region.x, region.y, region.width, region.height
+    ),
+  }
+}
+
+fn asset_path(assets: &HashMap<AssetHandle, String>, asset: &AssetHandle) -> String {
+  match assets.get(asset) {
+    Some(path) => path.clone(),
+    None => format!("asset:{}", asset.0),
+  }
+}
+
+fn gradient_stops(stops: &[GradientStop]) -> String {
+  stops
+    .iter()
+    .map(|stop| {
+      format!(
+        r#"<stop offset="{}" stop-color="{}"/>"#,
+        trim(stop.offset),
+        escape(&stop.color)
+      )
+    })
+    .collect()
+}
+
+/// text fitted in rect by line count
+fn text_element(text: &Text, width: Scalar, height: Scalar) -> String {
+  let lines: Vec<&str> = text.text.split('\n').collect();
+  let pitch = to_f32(height) / lines.len() as f32;
+  let (anchor, x) = match text.align {
+    TextAlign::Left => ("start", -to_f32(width) / 2.),
+    TextAlign::Center => ("middle", 0.),
+    TextAlign::Right => ("end", to_f32(width) / 2.),
+  };
+  let top = -to_f32(height) / 2.;
+  let spans: String = lines
+    .iter()
+    .enumerate()
+    .map(|(index, line)| {
+      format!(
+        r#"<tspan x="{}" y="{}">{}</tspan>"#,
+        trim(x),
+        trim(top + pitch * (index as f32 + 0.8)),
+        escape(line)
+      )
+    })
+    .collect();
+  format!(
+    r#"<text font-size="{}" text-anchor="{anchor}" fill="{}">{spans}</text>"#,
+    trim(pitch * 0.8),
+    escape(&text.color)
+  )
+}
+
+//=========================================================================================
+// Format
+//=========================================================================================
+fn number(value: Scalar) -> String {
+  trim(to_f32(value))
+}
+
+/// at most 3 decimals, without trailing zeros
+fn trim(value: f32) -> String {
+  let text = format!("{value:.3}");
+  let text = text.trim_end_matches('0').trim_end_matches('.');
+  match text {
+    "-0" => "0".to_string(),
+    text => text.to_string(),
+  }
+}
+
+/// scene point to svg point, y flipped
+fn pair(point: Vector) -> String {
+  format!("{},{}", number(point.0), number(-point.1))
+}
+
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+#[test]
+fn test() {
+  use super::render::ViewPort;
+
+  let mut frame = RenderFrame::new();
+  frame.push((
+    Rect::new(Vector::ORIGIN, Vector::new(100., 50.)),
+    Texture::Color("#336699".to_string()),
+  ));
+  frame.push((
+    Rect::new_with_angle(
+      Vector::new(10., 20.),
+      Vector::new(16., 16.),
+      scalar(std::f32::consts::FRAC_PI_2),
+    ),
+    Texture::region("hero & co.png", Region::new(16, 0, 16, 16))
+      .with_tint("#ff0000")
+      .with_opacity(0.5),
+  ));
+  frame.push_ui((
+    Rect::new(Vector::ORIGIN, Vector::new(0.5, 0.5)),
+    Texture::Text(Text::new("hp", 12, "#ffffff")),
+  ));
+  let mut viewport = ViewPort::new();
+  viewport.set_position(Vector::new(50., 0.));
+  viewport.set_size(Vector::new(200., 100.));
+  frame.set_viewport(viewport);
+
+  let hitbox = Rect::new(Vector::new(10., 20.), Vector::new(4., 2.));
+  let svg = SvgExport::new().with_hitboxes(vec![hitbox]).render(&frame);
+  assert!(svg.starts_with("<svg"));
+  assert!(svg.contains(r#"viewBox="-50 -50 200 100""#));
+  assert!(svg.contains(
+    r##"<g transform="translate(0,0)"><rect x="-50" y="-25" width="100" height="50" fill="#336699"/></g>"##
+  ));
+  // y flipped, counter-clockwise becomes negative degrees
+  assert!(svg.contains(r#"translate(10,-20) rotate(-90)"#));
+  assert!(svg.contains(r#"viewBox="16 0 16 16""#));
+  assert!(svg.contains("hero &amp; co.png"));
+  assert!(svg.contains(r#"opacity="0.5" filter="url(#tint1)""#));
+  assert!(svg.contains(r##"<filter id="tint1"><feFlood flood-color="#ff0000"/>"##));
+  // ui is mapped from normalized viewport space, 0.5 * 50 = 25 high
+  assert!(svg.contains(r#"<g class="ui">"#));
+  assert!(svg.contains(r#"translate(50,0)"><text font-size="20""#));
+  assert!(svg.contains(r#"<polygon points="8,-21 12,-21 12,-19 8,-19"/>"#));
+  assert!(svg.trim_end().ends_with("</svg>"));
+
+  let plain = SvgExport::new().with_ui(false).render(&frame);
+  assert!(!plain.contains("class=\"ui\"") && !plain.contains("hitbox"));
+}
@@ -0,0 +1,367 @@
+use std::{
+  cell::RefCell,
+  fmt::Write as _,
+  fs::File,
+  io::{self, Read, Write},
+  process::{Command, Stdio},
+  sync::{Arc, Mutex},
+  thread,
+  time::{Duration, Instant},
+};
+
+use crate::modules::asset::image::Image;
+
+use super::{
+  control::{Control, KeyEvent},
+  raster::Rasterizer,
+  render::RenderFrame,
+  Context,
+};
+
+/// terminals only send key presses, key is held until this long after last press
+const HOLD: Duration = Duration::from_millis(500);
+/// how often terminal size is queried
+const RESIZE_INTERVAL: Duration = Duration::from_secs(1);
+
+///=========================================================================================
+/// TerminalContext
+///=========================================================================================
+/// # 終端機 Context
+/// 以 ANSI truecolor 半格字元 `▀` 繪製畫面, 每個字元上下兩個像素
+/// 視口等比縮放到終端機大小, stdin 的按鍵轉為 `Control::keys`
+/// 建立時切換到 raw 模式和替代畫面, drop 時還原
+pub struct TerminalContext {
+  rasterizer: RefCell<Rasterizer>,
+  size: RefCell<(Instant, (u32, u32))>,
+  keys: Arc<Mutex<Vec<(Instant, KeyEvent)>>>,
+  hold: Duration,
+  /// `stty -g` before raw mode
+  saved: Option<String>,
+}
+
+impl TerminalContext {
+  /// raw mode on controlling terminal, keys read from stdin
+  pub fn new() -> Self {
+    let saved = stty(&["-g"]).map(|saved| saved.trim().to_string());
+    if saved.is_some() {
+      stty(&["-icanon", "-echo", "min", "1"]);
+    }
+    let context = TerminalContext {
+      rasterizer: RefCell::new(Rasterizer::new()),
+      size: RefCell::new((Instant::now(), terminal_size())),
+      keys: Arc::new(Mutex::new(vec![])),
+      hold: HOLD,
+      saved,
+    };
+    // alternate screen, hide cursor
+    let mut stdout = io::stdout().lock();
+    let _ = stdout.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J");
+    let _ = stdout.flush();
+    context.listen(io::stdin());
+    context
+  }
+
+  /// how long a key counts as pressed after its last repeat
+  pub fn with_hold(mut self, hold: Duration) -> Self {
+    self.hold = hold;
+    self
+  }
+  pub fn with_rasterizer(self, rasterizer: Rasterizer) -> Self {
+    *self.rasterizer.borrow_mut() = rasterizer;
+    self
+  }
+  pub fn rasterizer_mut(&self) -> std::cell::RefMut<'_, Rasterizer> {
+    self.rasterizer.borrow_mut()
+  }
+
+  /// size in pixels, one column and two rows per cell
+  pub fn size(&self) -> (u32, u32) {
+    let mut size = self.size.borrow_mut();
+    if size.0.elapsed() >= RESIZE_INTERVAL {
+      *size = (Instant::now(), terminal_size());
+    }
+    let (columns, rows) = size.1;
+    (columns, rows * 2)
+  }
+
+  fn listen(&self, mut reader: impl Read + Send + 'static) {
+    let keys = self.keys.clone();
+    thread::spawn(move || {
+      let mut buffer = [0; 64];
+      while let Ok(length @ 1..) = reader.read(&mut buffer) {
+        let now = Instant::now();
+        let mut keys = keys.lock().unwrap();
+        for mut key in parse_keys(&buffer[..length]) {
+          let held = keys.iter().position(|(_, held)| held.code == key.code);
+          if let Some(index) = held {
+            key.repeat = true;
+            keys.remove(index);
+          }
+          keys.push((now, key));
+        }
+      }
+    });
+  }
+}
+
+impl Default for TerminalContext {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Drop for TerminalContext {
+  fn drop(&mut self) {
+    let mut stdout = io::stdout().lock();
+    let _ = stdout.write_all(b"\x1b[0m\x1b[?25h\x1b[?1049l");
+    let _ = stdout.flush();
+    if let Some(saved) = &self.saved {
+      stty(&[saved]);
+    }
+  }
+}
+
+impl Context for TerminalContext {
+  /// keys pressed within hold time
+  fn control(&self) -> Option<Control> {
+    let mut keys = self.keys.lock().unwrap();
+    keys.retain(|(time, _)| time.elapsed() < self.hold);
+    let mut control = Control::new();
+    control.keys = keys.iter().map(|(_, key)| key.clone()).collect();
+    Some(control)
+  }
+  fn render(&self, frame: RenderFrame) -> Option<()> {
+    let (width, height) = self.size();
+    let image = self.rasterizer.borrow_mut().render(&frame, width, height);
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(half_blocks(&image).as_bytes()).ok()?;
+    stdout.flush().ok()
+  }
+}
+
+//=========================================================================================
+// Output
+//=========================================================================================
+/// ANSI text drawing image from top-left, upper pixel as foreground of `▀`
+pub fn half_blocks(image: &Image) -> String {
+  let mut text = String::with_capacity(image.width() as usize * image.height() as usize * 10);
+  text.push_str("\x1b[H");
+  for row in 0..image.height().div_ceil(2) {
+    if row > 0 {
+      text.push_str("\x1b[0m\r\n");
+    }
+    let mut last = None;
+    for column in 0..image.width() {
+      let color = |y| opaque(image.get(column, y).unwrap_or([0, 0, 0, 255]));
+      let cell = (color(row * 2), color(row * 2 + 1));
+      if last != Some(cell) {
+        let ((fr, fg, fb), (br, bg, bb)) = cell;
+        let _ = write!(text, "\x1b[38;2;{fr};{fg};{fb}m\x1b[48;2;{br};{bg};{bb}m");
+        last = Some(cell);
+      }
+      text.push('▀');
+    }
+  }
+  text.push_str("\x1b[0m");
+  text
+}
+
+/// over black
+fn opaque([r, g, b, a]: [u8; 4]) -> (u8, u8, u8) {
+  let over = |channel: u8| (channel as u32 * a as u32 / 255) as u8;
+  (over(r), over(g), over(b))
+}
+
+//=========================================================================================
+// Terminal
+//=========================================================================================
+/// run stty on controlling terminal
+fn stty(arguments: &[&str]) -> Option<String> {
+  let output = Command::new("stty")
+    .args(arguments)
+    .stdin(File::open("/dev/tty").ok()?)
+    .stderr(Stdio::null())
+    .output()
+    .ok()?;
+  match output.status.success() {
+    true => String::from_utf8(output.stdout).ok(),
+    false => None,
+  }
+}
+
+/// columns and rows, 80 x 24 if unknown
+fn terminal_size() -> (u32, u32) {
+  let queried = stty(&["size"]).and_then(|size| {
+    let mut size = size
+      .split_whitespace()
+      .map(|value| value.parse::<u32>().ok());
+    let (rows, columns) = (size.next()??, size.next()??);
+    Some((columns, rows))
+  });
+  let variable = |name: &str| std::env::var(name).ok()?.parse::<u32>().ok();
+  let (columns, rows) = queried
+    .or_else(|| Some((variable("COLUMNS")?, variable("LINES")?)))
+    .unwrap_or((80, 24));
+  // keep last row for cursor
+  (columns.max(1), rows.saturating_sub(1).max(1))
+}
+
+//=========================================================================================
+// Input
+//=========================================================================================
+/// keys of raw terminal input, codes follow `KeyboardEvent.code`
+pub fn parse_keys(bytes: &[u8]) -> Vec<KeyEvent> {
+  let key = |code: &str, shift: bool| KeyEvent {
+    code: code.to_string(),
+    alt: false,
+    ctrl: false,
+    meta: false,
+    shift,
+    repeat: false,
+  };
+  let mut keys = vec![];
+  let mut index = 0;
+  let text = String::from_utf8_lossy(bytes);
+  let chars: Vec<char> = text.chars().collect();
+  while index < chars.len() {
+    let char = chars[index];
+    index += 1;
+    if char == '\x1b' {
+      match chars.get(index) {
+        // csi or ss3, e.g. `ESC [ A`, `ESC O A`, `ESC [ 3 ~`
+        Some('[') | Some('O') => {
+          let start = index + 1;
+          let mut end = start;
+          while end < chars.len() && !chars[end].is_ascii_alphabetic() && chars[end] != '~' {
+            end += 1;
+          }
+          let sequence: String = chars[start..(end + 1).min(chars.len())].iter().collect();
+          index = end + 1;
+          let code = match sequence.as_str() {
+            "A" => "ArrowUp",
+            "B" => "ArrowDown",
+            "C" => "ArrowRight",
+            "D" => "ArrowLeft",
+            "H" | "1~" => "Home",
+            "F" | "4~" => "End",
+            "2~" => "Insert",
+            "3~" => "Delete",
+            "5~" => "PageUp",
+            "6~" => "PageDown",
+            "P" => "F1",
+            "Q" => "F2",
+            "R" => "F3",
+            "S" => "F4",
+            _ => continue,
+          };
+          keys.push(key(code, false));
+        }
+        // alt with key
+        Some(next) if *next != '\x1b' => {
+          index += 1;
+          if let Some(mut event) = char_key(*next) {
+            event.alt = true;
+            keys.push(event);
+          }
+        }
+        _ => keys.push(key("Escape", false)),
+      }
+      continue;
+    }
+    if let Some(event) = char_key(char) {
+      keys.push(event);
+    }
+  }
+  keys
+}
+
+/// key of one char, ctrl for control chars
+fn char_key(char: char) -> Option<KeyEvent> {
+  let event = |code: String, shift: bool, ctrl: bool| KeyEvent {
+    code,
+    alt: false,
+    ctrl,
+    meta: false,
+    shift,
+    repeat: false,
+  };
+  const SHIFTED: &str = ")!@#$%^&*(";
+  let punctuation = [
+    ('-', '_', "Minus"),
+    ('=', '+', "Equal"),
+    ('[', '{', "BracketLeft"),
+    (']', '}', "BracketRight"),
+    ('\\', '|', "Backslash"),
+    (';', ':', "Semicolon"),
+    ('\'', '"', "Quote"),
+    (',', '<', "Comma"),
+    ('.', '>', "Period"),
+    ('/', '?', "Slash"),
+    ('`', '~', "Backquote"),
+  ];
+  Some(match char {
+    '\r' | '\n' => event("Enter".to_string(), false, false),
+    '\t' => event("Tab".to_string(), false, false),
+    ' ' => event("Space".to_string(), false, false),
+    '\x7f' | '\x08' => event("Backspace".to_string(), false, false),
+    '\x01'..='\x1a' => {
+      let letter = (b'A' + char as u8 - 1) as char;
+      event(format!("Key{letter}"), false, true)
+    }
+    'a'..='z' => event(format!("Key{}", char.to_ascii_uppercase()), false, false),
+    'A'..='Z' => event(format!("Key{char}"), true, false),
+    '0'..='9' => event(format!("Digit{char}"), false, false),
+    char => {
+      if let Some(digit) = SHIFTED.find(char) {
+        event(format!("Digit{digit}"), true, false)
+      } else {
+        let (plain, _, code) = punctuation
+          .iter()
+          .find(|(plain, shifted, _)| *plain == char || *shifted == char)?;
+        event(code.to_string(), *plain != char, false)
+      }
+    }
+  })
+}
+
+#[test]
+fn test() {
+  let codes = |bytes: &[u8]| -> Vec<(String, bool, bool, bool)> {
+    parse_keys(bytes)
+      .into_iter()
+      .map(|key| (key.code, key.shift, key.ctrl, key.alt))
+      .collect()
+  };
+  let code = |code: &str, shift, ctrl, alt| (code.to_string(), shift, ctrl, alt);
+  assert_eq!(
+    codes(b"wA \x1b[D\x1b[3~"),
+    vec![
+      code("KeyW", false, false, false),
+      code("KeyA", true, false, false),
+      code("Space", false, false, false),
+      code("ArrowLeft", false, false, false),
+      code("Delete", false, false, false),
+    ]
+  );
+  assert_eq!(
+    codes(b"\x03\x1bx!?\r\x1b"),
+    vec![
+      code("KeyC", false, true, false),
+      code("KeyX", false, false, true),
+      code("Digit1", true, false, false),
+      code("Slash", true, false, false),
+      code("Enter", false, false, false),
+      code("Escape", false, false, false),
+    ]
+  );
+
+  // 2 x 3 pixels is 2 x 2 cells, missing bottom row is black
+  let mut image = Image::new(2, 3);
+  image.set(0, 0, [255, 0, 0, 255]);
+  image.set(1, 0, [255, 0, 0, 255]);
+  image.set(0, 2, [0, 0, 255, 128]);
+  let text = half_blocks(&image);
+  assert!(text.starts_with("\x1b[H\x1b[38;2;255;0;0m\x1b[48;2;0;0;0m▀▀\x1b[0m\r\n"));
+  assert!(text.contains("\x1b[38;2;0;0;128m\x1b[48;2;0;0;0m▀\x1b[38;2;0;0;0m"));
+  assert_eq!(text.matches('▀').count(), 4);
+}
@@ -0,0 +1,295 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  modules::context::render::Texture,
+  utils::{
+    rect::Rect,
+    scalar::{scalar, Scalar},
+  },
+};
+
+/// frames skipped in one tick at most
+const MAX_STEPS: usize = 1024;
+
+///=========================================================================================
+/// Clip
+///=========================================================================================
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+  pub texture: Texture,
+  /// replace part rect when set
+  pub rect: Option<Rect>,
+  /// millisecond
+  pub duration: usize,
+  /// fire event when frame is entered
+  pub tag: Option<String>,
+}
+
+impl Frame {
+  pub fn new(texture: Texture, duration: usize) -> Self {
+    Frame {
+      texture,
+      rect: None,
+      duration,
+      tag: None,
+    }
+  }
+  pub fn with_rect(mut self, rect: Rect) -> Self {
+    self.rect = Some(rect);
+    self
+  }
+  pub fn with_tag(mut self, tag: &str) -> Self {
+    self.tag = Some(tag.to_string());
+    self
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LoopMode {
+  /// stop on last frame
+  Once,
+  #[default]
+  Loop,
+  /// forward then backward
+  PingPong,
+}
+
+/// # 動畫片段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clip {
+  pub frames: Vec<Frame>,
+  pub mode: LoopMode,
+}
+
+impl Clip {
+  pub fn new(frames: Vec<Frame>, mode: LoopMode) -> Self {
+    Clip { frames, mode }
+  }
+}
+
+///=========================================================================================
+/// Animator
+///=========================================================================================
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationEventKind {
+  /// clip reached its end (every cycle when looping)
+  End,
+  /// tagged frame entered
+  Tag(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnimationEvent {
+  pub enity: uuid::Uuid,
+  pub part: String,
+  pub clip: String,
+  pub kind: AnimationEventKind,
+}
+
+/// # 動畫播放器
+/// 掛載在視圖部件上, 播放其中一個片段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Animator {
+  clips: IndexMap<String, Clip>,
+  /// index of item in part
+  pub item: usize,
+  /// playback speed, `1.0` is normal
+  pub speed: Scalar,
+  playing: Option<String>,
+  frame: usize,
+  elapsed: Scalar,
+  forward: bool,
+  finished: bool,
+  entered: bool,
+}
+
+impl Default for Animator {
+  fn default() -> Self {
+    Animator {
+      clips: IndexMap::new(),
+      item: 0,
+      speed: scalar(1.),
+      playing: None,
+      frame: 0,
+      elapsed: scalar(0.),
+      forward: true,
+      finished: false,
+      entered: false,
+    }
+  }
+}
+
+impl Animator {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, name: &str, clip: Clip) -> Option<Clip> {
+    self.clips.insert(name.to_string(), clip)
+  }
+  pub fn clip(&self, name: &str) -> Option<&Clip> {
+    self.clips.get(name)
+  }
+
+  /// play clip from first frame
+  pub fn play(&mut self, name: &str) -> bool {
+    if !self.clips.contains_key(name) {
+      return false;
+    }
+    self.playing = Some(name.to_string());
+    self.frame = 0;
+    self.elapsed = scalar(0.);
+    self.forward = true;
+    self.finished = false;
+    self.entered = true;
+    true
+  }
+  pub fn stop(&mut self) {
+    self.playing = None;
+  }
+  pub fn playing(&self) -> Option<&String> {
+    self.playing.as_ref()
+  }
+  pub fn frame(&self) -> usize {
+    self.frame
+  }
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  pub(crate) fn textures_mut(&mut self) -> impl Iterator<Item = &mut Texture> {
+    self
+      .clips
+      .values_mut()
+      .flat_map(|clip| clip.frames.iter_mut().map(|frame| &mut frame.texture))
+  }
+
+  /// current frame of playing clip
+  pub fn current(&self) -> Option<&Frame> {
+    let clip = self.clips.get(self.playing.as_ref()?)?;
+    clip.frames.get(self.frame)
+  }
+
+  /// advance by millisecond, return (clip, event) fired
+  /// 推進動畫, 回傳觸發的事件
+  pub fn advance(&mut self, delta: usize) -> Vec<(String, AnimationEventKind)> {
+    let mut events = vec![];
+    let Some(name) = self.playing.clone() else {
+      return events;
+    };
+    let Some(clip) = self.clips.get(&name) else {
+      return events;
+    };
+    if clip.frames.is_empty() || self.finished {
+      return events;
+    }
+
+    let tag = |frame: usize, events: &mut Vec<(String, AnimationEventKind)>| {
+      if let Some(tag) = &clip.frames[frame].tag {
+        events.push((name.clone(), AnimationEventKind::Tag(tag.clone())));
+      }
+    };
+    if self.entered {
+      self.entered = false;
+      tag(self.frame, &mut events);
+    }
+
+    let last = clip.frames.len() - 1;
+    self.elapsed += scalar(delta as f32) * self.speed.max(scalar(0.));
+    for _ in 0..MAX_STEPS {
+      let duration = scalar(clip.frames[self.frame].duration.max(1) as f32);
+      if self.elapsed < duration {
+        break;
+      }
+      self.elapsed -= duration;
+
+      match (clip.mode, self.forward) {
+        (_, true) if self.frame < last => self.frame += 1,
+        (LoopMode::Once, _) => {
+          events.push((name.clone(), AnimationEventKind::End));
+          self.finished = true;
+          self.elapsed = scalar(0.);
+          break;
+        }
+        (LoopMode::Loop, _) => {
+          events.push((name.clone(), AnimationEventKind::End));
+          self.frame = 0;
+        }
+        (LoopMode::PingPong, true) => {
+          self.forward = false;
+          self.frame = last.saturating_sub(1);
+        }
+        (LoopMode::PingPong, false) if self.frame > 0 => self.frame -= 1,
+        (LoopMode::PingPong, false) => {
+          events.push((name.clone(), AnimationEventKind::End));
+          self.forward = true;
+          self.frame = last.min(1);
+        }
+      }
+      tag(self.frame, &mut events);
+    }
+    events
+  }
+}
+
+#[test]
+fn test() {
+  use super::view::EnityView;
+  use crate::utils::vector::Vector;
+
+  let frame =
+    |color: &str, duration: usize| Frame::new(Texture::Color(color.to_string()), duration);
+  let walk = Clip::new(
+    vec![
+      frame("#000000", 100),
+      frame("#111111", 100).with_tag("step"),
+      frame("#222222", 100),
+    ],
+    LoopMode::PingPong,
+  );
+  let hit = Clip::new(
+    vec![frame("#ff0000", 50).with_rect(Rect::new(Vector::ORIGIN, Vector::new(20., 20.)))],
+    LoopMode::Once,
+  );
+
+  let part = Rect::new(Vector::ORIGIN, Vector::new(10., 10.));
+  let mut view = EnityView::new(vec![(part, Texture::default())], vec![]);
+  view.add_clip("base", "walk", walk);
+  view.add_clip("base", "hit", hit);
+  assert!(view.play("base", "walk"));
+  let texture = |view: &EnityView| view.part("base").unwrap()[0].1.clone();
+
+  assert!(view.animate(50).is_empty());
+  assert_eq!(texture(&view), Texture::Color("#000000".to_string()));
+  // 0 -> 1 (tag) -> 2 -> 1 (tag) -> 0
+  let events = view.animate(400);
+  let kinds: Vec<_> = events.into_iter().map(|(_, _, kind)| kind).collect();
+  let step = AnimationEventKind::Tag("step".to_string());
+  assert_eq!(kinds, vec![step.clone(), step.clone()]);
+  assert_eq!(texture(&view), Texture::Color("#000000".to_string()));
+
+  // half speed, 0 (end) -> 1 (tag)
+  view.animator_mut("base").unwrap().speed = scalar(0.5);
+  let events = view.animate(100);
+  let kinds: Vec<_> = events.into_iter().map(|(_, _, kind)| kind).collect();
+  assert_eq!(kinds, vec![AnimationEventKind::End, step]);
+  assert_eq!(texture(&view), Texture::Color("#111111".to_string()));
+  assert!(view.animate(100).is_empty());
+  assert_eq!(texture(&view), Texture::Color("#111111".to_string()));
+
+  // speed is kept across clips
+  view.play("base", "hit");
+  let events = view.animate(120);
+  assert_eq!(
+    events,
+    vec![(
+      "base".to_string(),
+      "hit".to_string(),
+      AnimationEventKind::End
+    )]
+  );
+  assert_eq!(view.part("base").unwrap()[0].0.size, Vector::new(20., 20.));
+  assert!(view.animator("base").unwrap().is_finished());
+  assert!(view.animate(60).is_empty());
+}
@@ -1,13 +1,19 @@
 use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::utils::scalar::Scalar;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EnityBase {
   name: String,
-  speed: f32,
+  speed: Scalar,
   destroy: bool,
   /// default 1  
   /// no collision: 0
   collision: usize,
+  /// never moves, used by navigation
+  #[serde(default)]
+  is_static: bool,
   groups: IndexSet<String>,
 }
 
@@ -26,10 +32,10 @@ impl EnityBase {
   }
 
   //speed
-  pub fn speed(&self) -> f32 {
+  pub fn speed(&self) -> Scalar {
     self.speed
   }
-  pub fn set_speed(&mut self, speed: f32) {
+  pub fn set_speed(&mut self, speed: Scalar) {
     self.speed = speed
   }
 
@@ -44,6 +50,14 @@ impl EnityBase {
     self.collision = 0
   }
 
+  //static
+  pub fn is_static(&self) -> bool {
+    self.is_static
+  }
+  pub fn set_static(&mut self, is_static: bool) {
+    self.is_static = is_static
+  }
+
   //group
   pub fn groups(&self) -> Vec<String> {
     self.groups.iter().cloned().collect()
@@ -58,13 +72,14 @@ impl EnityBase {
     self.groups.swap_remove(group)
   }
 
-  pub fn new(name: String, groups: Vec<String>, speed: f32) -> EnityBase {
+  pub fn new(name: String, groups: Vec<String>, speed: Scalar) -> EnityBase {
     let groups = IndexSet::from_iter(groups);
     EnityBase {
       name,
       speed,
       groups,
       collision: 1,
+      is_static: false,
       destroy: false,
     }
   }
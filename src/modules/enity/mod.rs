@@ -4,8 +4,10 @@ use uuid::Uuid;
 
 use self::{base::EnityBase, position::EnityPosition, track::EnityTrack, view::EnityView};
 
+pub mod animation;
 pub mod base;
 pub mod position;
+pub mod steering;
 pub mod track;
 pub mod view;
 
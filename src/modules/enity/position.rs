@@ -2,33 +2,47 @@ use std::mem;
 
 use uuid::Uuid;
 
-use crate::utils::vector::Vector;
-
-use super::track::EnityTrack;
+use crate::{
+  modules::navigation::{flow::FlowField, NavPath},
+  utils::{
+    rchash::RcHash,
+    scalar::{scalar, Scalar},
+    transform::Transform2D,
+    vector::Vector,
+  },
+};
+
+use super::{
+  steering::{self, Follow, Orbit, Patrol, Wander},
+  track::EnityTrack,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct EnityPosition {
   main: MoveEvent,
-  drifts: Vec<(Vector, f32)>,
+  drifts: Vec<(Vector, Scalar)>,
   position: Vector,
-  angle: f32,
+  angle: Scalar,
+  /// movement of last action (per second)
+  velocity: Vector,
 }
 
 impl EnityPosition {
   pub fn new(position: Vector) -> Self {
     EnityPosition {
       position,
-      angle: 0.0,
+      angle: scalar(0.),
       main: MoveEvent::Stop,
       drifts: vec![],
+      velocity: Vector::ORIGIN,
     }
   }
 
-  pub fn set_angle(&mut self, angle: f32) {
+  pub fn set_angle(&mut self, angle: Scalar) {
     self.angle = angle;
   }
 
-  pub fn get_angle(&self) -> f32 {
+  pub fn get_angle(&self) -> Scalar {
     self.angle
   }
 
@@ -40,7 +54,7 @@ impl EnityPosition {
     &self.main
   }
 
-  pub fn offset(&mut self, offset: Vector, spend: f32) {
+  pub fn offset(&mut self, offset: Vector, spend: Scalar) {
     self.drifts.push((offset, spend));
   }
 
@@ -52,10 +66,23 @@ impl EnityPosition {
     mem::replace(&mut self.position, position)
   }
 
-  pub fn action(&mut self, scene_uuid: Uuid, speed: f32, delta: usize) {
+  pub fn velocity(&self) -> Vector {
+    self.velocity
+  }
+  /// per second, for moves made outside `action`
+  pub fn set_velocity(&mut self, velocity: Vector) {
+    self.velocity = velocity
+  }
+
+  /// local to scene transform
+  pub fn transform(&self) -> Transform2D {
+    Transform2D::new(self.position, self.angle)
+  }
+
+  pub fn action(&mut self, scene_uuid: Uuid, speed: Scalar, delta: usize) {
     let mut position = self.position;
     let mut force_drift = false;
-    let delta = delta as f32 / 1000.;
+    let delta = scalar(delta as f32 / 1000.);
     match &mut self.main {
       MoveEvent::Stop => {
         //notthing to do
@@ -73,8 +100,8 @@ impl EnityPosition {
       }
       MoveEvent::Drift(vector, spend) => {
         force_drift = true;
-        if *spend > f32::EPSILON {
-          let used = delta.min(*spend).max(0.);
+        if *spend > Scalar::EPSILON {
+          let used = delta.min(*spend).max(scalar(0.));
           let left = *spend - used;
           let scale = *vector * (used / *spend);
           position += scale;
@@ -82,6 +109,48 @@ impl EnityPosition {
           *spend = left;
         }
       }
+      MoveEvent::Arrive(target, radius) => {
+        let distance = position.to(*target).distance();
+        let speed = if distance < *radius {
+          (speed * distance / *radius).max(speed * scalar(0.1))
+        } else {
+          speed
+        };
+        position = steering::approach(position, *target, speed * delta);
+      }
+      MoveEvent::Flee(from) => {
+        position += from.to(position).by_length(speed) * delta;
+      }
+      MoveEvent::Wander(wander) => {
+        position = wander.step(position, speed * delta, delta);
+      }
+      MoveEvent::Orbit(orbit) => {
+        position = orbit.step(scene_uuid, position, speed * delta);
+      }
+      MoveEvent::Patrol(patrol) => {
+        position = patrol.step(position, speed * delta);
+      }
+      MoveEvent::FollowOffset(follow) => {
+        position = follow.step(scene_uuid, position, speed * delta);
+      }
+      MoveEvent::Pursue(enity) => {
+        if !enity.base().is_destroy() {
+          let target = steering::predict(scene_uuid, position, speed, enity);
+          position = steering::approach(position, target, speed * delta);
+        }
+      }
+      MoveEvent::Evade(enity) => {
+        if !enity.base().is_destroy() {
+          let target = steering::predict(scene_uuid, position, speed, enity);
+          position += target.to(position).by_length(speed) * delta;
+        }
+      }
+      MoveEvent::Path(path) => {
+        position = path.step(position, speed * delta);
+      }
+      MoveEvent::Flow(field) => {
+        position = field.borrow().step(position, speed * delta);
+      }
       MoveEvent::Following(enity) => {
         position += if !enity.base().is_destroy() {
           let vector = enity.position(scene_uuid).get();
@@ -97,12 +166,12 @@ impl EnityPosition {
     let mut offset = Vector::ORIGIN;
     if self.drifts.len() != 0 {
       self.drifts.retain_mut(|(vector, total)| {
-        if *total < f32::EPSILON {
+        if *total < Scalar::EPSILON {
           offset += *vector;
           return false;
         }
 
-        let used = delta.min(*total).max(0.);
+        let used = delta.min(*total).max(scalar(0.));
         let left = *total - used;
         let scale = *vector * used / *total;
 
@@ -110,13 +179,16 @@ impl EnityPosition {
         *vector -= scale;
         *total = left;
 
-        left > f32::EPSILON //unfinished if true
+        left > Scalar::EPSILON //unfinished if true
       });
     }
     if !force_drift {
       position += offset;
     }
 
+    if delta > Scalar::EPSILON {
+      self.velocity = (position - self.position) / delta;
+    }
     self.position = position
   }
 }
@@ -128,8 +200,26 @@ pub enum MoveEvent {
   Moveto(Vector),
   ///by relative position
   Moving(Vector),
-  Drift(Vector, f32),
+  Drift(Vector, Scalar),
+  ///toward current position of enity, see `FollowOffset` to keep distance
   Following(EnityTrack),
+  ///by absolute position, slow down inside radius
+  Arrive(Vector, Scalar),
+  ///away from absolute position
+  Flee(Vector),
+  Wander(Wander),
+  Orbit(Orbit),
+  Patrol(Patrol),
+  ///like `Following`, with keep-distance and offset
+  FollowOffset(Follow),
+  ///toward predicted position of enity
+  Pursue(EnityTrack),
+  ///away from predicted position of enity
+  Evade(EnityTrack),
+  ///by waypoints on navigation grid
+  Path(NavPath),
+  ///by shared flow field toward its goal
+  Flow(RcHash<FlowField>),
 }
 
 impl Default for MoveEvent {
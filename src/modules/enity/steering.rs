@@ -0,0 +1,346 @@
+use uuid::Uuid;
+
+use crate::utils::{
+  scalar::{scalar, Scalar},
+  transform::Transform2D,
+  vector::Vector,
+};
+
+use super::track::EnityTrack;
+
+/// longest time to lead a moving target (second)
+const MAX_PREDICTION: Scalar = scalar(1.);
+
+/// move toward target by at most length
+/// 朝目標移動, 最多移動 length
+pub fn approach(position: Vector, target: Vector, length: Scalar) -> Vector {
+  let moving = position.to(target);
+  if moving.distance() > length {
+    position + moving.by_length(length)
+  } else {
+    target
+  }
+}
+
+/// target position after its current velocity, lead time by distance and speed
+/// 依目標速度預測其位置
+pub fn predict(scene_uuid: Uuid, position: Vector, speed: Scalar, target: &EnityTrack) -> Vector {
+  let target = target.position(scene_uuid);
+  let distance = position.to(target.get()).distance();
+  let time = if speed > Scalar::EPSILON {
+    (distance / speed).min(MAX_PREDICTION)
+  } else {
+    MAX_PREDICTION
+  };
+  target.get() + target.velocity() * time
+}
+
+///=========================================================================================
+/// Wander
+///=========================================================================================
+/// 隨機漫步, 以固定種子保證可重現
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wander {
+  /// max heading change (radian per second)
+  pub turn: Scalar,
+  heading: Scalar,
+  seed: u64,
+}
+
+impl Wander {
+  pub fn new(turn: Scalar, seed: u64) -> Self {
+    Wander {
+      turn,
+      heading: scalar(0.),
+      seed: seed.max(1),
+    }
+  }
+
+  pub fn heading(&self) -> Scalar {
+    self.heading
+  }
+
+  /// xorshift64 in `-1.0 ~ 1.0`
+  fn random(&mut self) -> Scalar {
+    let mut x = self.seed;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    self.seed = x;
+    scalar((x % 2001) as f32 / 1000. - 1.)
+  }
+
+  pub fn step(&mut self, position: Vector, length: Scalar, delta: Scalar) -> Vector {
+    let turn = self.random() * self.turn * delta;
+    self.heading += turn;
+    let (sin, cos) = self.heading.sin_cos();
+    position + Vector(cos, sin) * length
+  }
+}
+
+///=========================================================================================
+/// Orbit
+///=========================================================================================
+/// 繞實體公轉
+#[derive(Debug, Clone)]
+pub struct Orbit {
+  pub target: EnityTrack,
+  pub radius: Scalar,
+  pub clockwise: bool,
+}
+
+impl Orbit {
+  pub fn new(target: EnityTrack, radius: Scalar, clockwise: bool) -> Self {
+    Orbit {
+      target,
+      radius,
+      clockwise,
+    }
+  }
+
+  pub fn step(&self, scene_uuid: Uuid, position: Vector, length: Scalar) -> Vector {
+    if self.target.base().is_destroy() {
+      return position;
+    }
+    let center = self.target.position(scene_uuid).get();
+    let angle = center.to(position).radian();
+    let radius = self.radius.max(Scalar::EPSILON);
+    let turn = length / radius;
+    let angle = if self.clockwise {
+      angle - turn
+    } else {
+      angle + turn
+    };
+    let (sin, cos) = angle.sin_cos();
+    approach(position, center + Vector(cos, sin) * self.radius, length)
+  }
+}
+
+///=========================================================================================
+/// Patrol
+///=========================================================================================
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatrolMode {
+  /// back to first waypoint after last
+  Loop,
+  /// walk back and forth
+  PingPong,
+}
+
+/// 沿路徑點巡邏
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patrol {
+  pub waypoints: Vec<Vector>,
+  pub mode: PatrolMode,
+  index: usize,
+  forward: bool,
+}
+
+impl Patrol {
+  pub fn new(waypoints: Vec<Vector>, mode: PatrolMode) -> Self {
+    Patrol {
+      waypoints,
+      mode,
+      index: 0,
+      forward: true,
+    }
+  }
+
+  /// index of waypoint heading to
+  pub fn index(&self) -> usize {
+    self.index
+  }
+
+  fn advance(&mut self) {
+    let last = self.waypoints.len() - 1;
+    match self.mode {
+      PatrolMode::Loop => self.index = (self.index + 1) % self.waypoints.len(),
+      PatrolMode::PingPong => {
+        if self.forward && self.index == last {
+          self.forward = false;
+        } else if !self.forward && self.index == 0 {
+          self.forward = true;
+        }
+        self.index = match self.forward {
+          true => (self.index + 1).min(last),
+          false => self.index.saturating_sub(1),
+        };
+      }
+    }
+  }
+
+  pub fn step(&mut self, position: Vector, length: Scalar) -> Vector {
+    if self.waypoints.len() < 2 {
+      return match self.waypoints.first() {
+        Some(waypoint) => approach(position, *waypoint, length),
+        None => position,
+      };
+    }
+
+    let mut position = position;
+    let mut left = length;
+    // bounded, in case of zero-length legs
+    for _ in 0..self.waypoints.len() * 2 {
+      let waypoint = self.waypoints[self.index];
+      let distance = position.to(waypoint).distance();
+      if distance > left {
+        return approach(position, waypoint, left);
+      }
+      position = waypoint;
+      left -= distance;
+      self.advance();
+    }
+    position
+  }
+}
+
+///=========================================================================================
+/// Follow
+///=========================================================================================
+/// follow enity with distance and offset, used by `MoveEvent::FollowOffset`
+/// (`MoveEvent::Following` chases the target position itself)
+/// 跟隨實體, 保持距離和相對偏移 (隨目標角度旋轉)
+#[derive(Debug, Clone)]
+pub struct Follow {
+  pub target: EnityTrack,
+  /// stop when closer than distance
+  pub distance: Scalar,
+  /// offset in target local space
+  pub offset: Vector,
+}
+
+impl Follow {
+  pub fn new(target: EnityTrack, distance: Scalar, offset: Vector) -> Self {
+    Follow {
+      target,
+      distance,
+      offset,
+    }
+  }
+
+  pub fn step(&self, scene_uuid: Uuid, position: Vector, length: Scalar) -> Vector {
+    if self.target.base().is_destroy() {
+      return position;
+    }
+    let target = self.target.position(scene_uuid);
+    let goal = Transform2D::new(target.get(), target.get_angle()).apply(self.offset);
+    drop(target);
+
+    let moving = position.to(goal);
+    let distance = moving.distance();
+    if distance <= self.distance {
+      return position;
+    }
+    position + moving.by_length(length.min(distance - self.distance))
+  }
+}
+
+#[test]
+fn test() {
+  use crate::{
+    modules::{
+      enity::{base::EnityBase, position::MoveEvent, view::EnityView},
+      scene::NormalScene,
+    },
+    utils::scalar::to_f32,
+  };
+
+  let mut scene = NormalScene::new(Vector::new(1000., 1000.));
+  let spawn = |scene: &mut NormalScene, position: Vector, event: MoveEvent| {
+    let mut base = EnityBase::new("steering".to_string(), vec![], scalar(100.));
+    base.set_no_collision();
+    let track = EnityTrack::new(base, EnityView::new(vec![], vec![]));
+    let mut enity = track.position(scene.uuid());
+    enity.set(position);
+    enity.set_action(event);
+    drop(enity);
+    scene.insert(&track);
+    track
+  };
+  let at = |track: &EnityTrack, scene: &NormalScene| track.position(scene.uuid()).get();
+
+  let runner = spawn(
+    &mut scene,
+    Vector::ORIGIN,
+    MoveEvent::Moving(Vector::new(0., 1.)),
+  );
+  runner.base_mut().set_speed(scalar(50.));
+  let arrive = spawn(
+    &mut scene,
+    Vector::new(-100., 0.),
+    MoveEvent::Arrive(Vector::new(-50., 0.), scalar(20.)),
+  );
+  let patrol = spawn(
+    &mut scene,
+    Vector::new(200., 0.),
+    MoveEvent::Patrol(Patrol::new(
+      vec![Vector::new(200., 0.), Vector::new(210., 0.)],
+      PatrolMode::PingPong,
+    )),
+  );
+  let orbit = spawn(
+    &mut scene,
+    Vector::new(30., 0.),
+    MoveEvent::Orbit(Orbit::new(runner.clone(), scalar(30.), false)),
+  );
+  let follow = spawn(
+    &mut scene,
+    Vector::new(0., -100.),
+    MoveEvent::FollowOffset(Follow::new(
+      runner.clone(),
+      scalar(5.),
+      Vector::new(-10., 0.),
+    )),
+  );
+  let pursue = spawn(
+    &mut scene,
+    Vector::new(100., 0.),
+    MoveEvent::Pursue(runner.clone()),
+  );
+  let wander = spawn(
+    &mut scene,
+    Vector::ORIGIN,
+    MoveEvent::Wander(Wander::new(scalar(3.), 7)),
+  );
+  let wander_again = spawn(
+    &mut scene,
+    Vector::ORIGIN,
+    MoveEvent::Wander(Wander::new(scalar(3.), 7)),
+  );
+
+  for _ in 0..300 {
+    scene.update(10);
+  }
+  // runner moves 50 per second along y
+  assert!((to_f32(at(&runner, &scene).1) - 150.).abs() < 0.1);
+  assert!((at(&arrive, &scene) - Vector::new(-50., 0.)).distance() < scalar(0.01));
+  assert!((to_f32(at(&orbit, &scene).to(at(&runner, &scene)).distance()) - 30.).abs() < 1.);
+  let gap = at(&follow, &scene)
+    .to(at(&runner, &scene) + Vector::new(-10., 0.))
+    .distance();
+  assert!((to_f32(gap) - 5.).abs() < 1.);
+  // pursuer leads target, so it ends above the target's past track
+  assert!(at(&pursue, &scene).1 > scalar(0.));
+  assert_eq!(at(&wander, &scene), at(&wander_again, &scene));
+  assert!(at(&wander, &scene) != Vector::ORIGIN);
+  let x = to_f32(at(&patrol, &scene).0);
+  assert!((200. ..=210.).contains(&x));
+
+  // ping-pong turns back at the last waypoint
+  let mut patrol = Patrol::new(
+    vec![
+      Vector::new(0., 0.),
+      Vector::new(10., 0.),
+      Vector::new(20., 0.),
+    ],
+    PatrolMode::PingPong,
+  );
+  let position = patrol.step(Vector::ORIGIN, scalar(25.));
+  assert_eq!(position, Vector::new(15., 0.));
+  assert_eq!(patrol.index(), 1);
+  let mut patrol = Patrol::new(patrol.waypoints, PatrolMode::Loop);
+  let position = patrol.step(Vector::ORIGIN, scalar(25.));
+  // loop heads straight back to the first waypoint
+  assert_eq!(position, Vector::new(15., 0.));
+  assert_eq!(patrol.index(), 0);
+}
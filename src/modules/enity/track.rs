@@ -4,7 +4,7 @@ use indexmap::IndexMap;
 use std::cell::Ref;
 use uuid::Uuid;
 
-use crate::{modules::context::render::Texture, utils::{hitbox::HitBox, rchash::RcHash, rect::Rect, vector::Vector, viewbox::ViewBox}};
+use crate::{modules::context::render::{DrawKey, Texture}, utils::{hitbox::HitBox, rchash::RcHash, rect::Rect, scalar::scalar, vector::Vector, viewbox::ViewBox}};
 
 use super::{base::EnityBase, position::EnityPosition, view::EnityView};
 
@@ -39,8 +39,13 @@ impl Eq for EnityTrack {}
 
 impl EnityTrack {
   pub fn new(base: EnityBase, view: EnityView) -> EnityTrack {
+    Self::with_uuid(Uuid::new_v4(), base, view)
+  }
+
+  /// create with known uuid (e.g. replicated from server)
+  pub fn with_uuid(uuid: Uuid, base: EnityBase, view: EnityView) -> EnityTrack {
     EnityTrack {
-      uuid: Uuid::new_v4(),
+      uuid,
       base: RcHash::new(base),
       view: RcHash::new(view),
       position: RcHash::default(),
@@ -88,7 +93,7 @@ impl EnityTrack {
     }
 
     let size = max - min;
-    let center = size / 2. + min;
+    let center = size / scalar(2.) + min;
 
     Rect::new(center, size)
   }
@@ -110,21 +115,34 @@ impl EnityTrack {
     }
 
     let size = max - min;
-    let center = size / 2. + min;
+    let center = size / scalar(2.) + min;
 
     Rect::new(center, size)
   }
 
+  /// part rects placed by entity transform
+  /// 部件以實體變換放置
+  ///
+  /// part offset is rotated with entity angle, part angle is relative to entity angle
+  /// 部件位移隨實體角度旋轉, 部件角度相對於實體角度
   pub fn viewbox_object(&self, scene_uuid: Uuid) -> Vec<(Rect,Texture)> {
-    let position = self.position(scene_uuid);
-    let view = self.view();
-
-    let angle = position.get_angle();
-    let offset = position.get();
-    let mut viewboxes = view.viewboxes();
+    let transform = self.position(scene_uuid).transform();
+    let mut viewboxes = self.view().viewboxes();
     for (rect, _) in viewboxes.iter_mut() {
-      rect.angle = angle;
-      rect.position = rect.position.rotate(angle) + offset;
+      *rect = transform.apply_rect(*rect);
+    }
+    viewboxes
+  }
+
+  /// viewboxes keyed by uuid, part name and index
+  pub fn keyed_viewbox_object(&self, scene_uuid: Uuid) -> Vec<(DrawKey, (Rect, Texture))> {
+    let transform = self.position(scene_uuid).transform();
+    let mut viewboxes = vec![];
+    for (name, part) in self.view().parts() {
+      for (index, (rect, texture)) in part.iter().enumerate() {
+        let key = DrawKey::new(self.uuid, name, index as u32);
+        viewboxes.push((key, (transform.apply_rect(*rect), texture.clone())));
+      }
     }
     viewboxes
   }
@@ -158,15 +176,13 @@ impl EnityTrack {
     false
   }
 
+  /// hitbox rects placed like `viewbox_object`
+  /// 碰撞箱以 `viewbox_object` 相同方式放置
   pub fn hitbox_object(&self, scene_uuid: Uuid) -> Vec<Rect> {
-    let position = self.position(scene_uuid);
-    let view = self.view();
-    let angle = position.get_angle();
-    let offset = position.get();
-    let mut hitboxes = view.hitboxes();
+    let transform = self.position(scene_uuid).transform();
+    let mut hitboxes = self.view().hitboxes();
     for rect in hitboxes.iter_mut() {
-      rect.angle = angle;
-      rect.position = rect.position.rotate(angle) + offset;
+      *rect = transform.apply_rect(*rect);
     }
     hitboxes
   }
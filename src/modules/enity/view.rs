@@ -1,14 +1,19 @@
 use std::cell::Cell;
 
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
 use crate::{modules::context::render::Texture, utils::rect::Rect};
 
-#[derive(Debug, Clone)]
+use super::animation::{AnimationEventKind, Animator, Clip};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EnityView {
   viewbox: Cell<Option<Rect>>,
   viewboxes: IndexMap<String, Vec<(Rect, Texture)>>,
   hitboxes: IndexMap<String, Vec<Rect>>,
+  #[serde(default)]
+  animators: IndexMap<String, Animator>,
 }
 
 impl EnityView {
@@ -29,6 +34,7 @@ impl EnityView {
       viewbox,
       viewboxes,
       hitboxes,
+      animators: IndexMap::new(),
     }
   }
 
@@ -56,6 +62,21 @@ impl EnityView {
     }
   }
 
+  pub fn part(&self, name: &str) -> Option<&Vec<(Rect, Texture)>> {
+    self.viewboxes.get(name)
+  }
+  pub fn part_mut(&mut self, name: &str) -> Option<&mut Vec<(Rect, Texture)>> {
+    self.viewboxes.get_mut(name)
+  }
+
+  /// textures of every part and animation frame
+  pub fn textures_mut(&mut self) -> impl Iterator<Item = &mut Texture> {
+    let parts = self
+      .viewboxes
+      .values_mut()
+      .flat_map(|part| part.iter_mut().map(|(_, texture)| texture));
+    parts.chain(self.animators.values_mut().flat_map(Animator::textures_mut))
+  }
   pub fn insert_hitbox(&mut self, name: String, part: Vec<Rect>) -> Option<Vec<Rect>> {
     let result = self.hitboxes.insert(name, part);
     result
@@ -72,8 +93,79 @@ impl EnityView {
     }
   }
   //================================================================================
+  // Animation
+  //================================================================================
+  /// add clip to animator of part
+  pub fn add_clip(&mut self, part: &str, name: &str, clip: Clip) -> Option<Clip> {
+    self
+      .animators
+      .entry(part.to_string())
+      .or_default()
+      .insert(name, clip)
+  }
+  pub fn play(&mut self, part: &str, name: &str) -> bool {
+    let played = self
+      .animators
+      .get_mut(part)
+      .is_some_and(|animator| animator.play(name));
+    if played {
+      self.apply_frame(part);
+    }
+    played
+  }
+  pub fn stop(&mut self, part: &str) {
+    if let Some(animator) = self.animators.get_mut(part) {
+      animator.stop()
+    }
+  }
+  pub fn animator(&self, part: &str) -> Option<&Animator> {
+    self.animators.get(part)
+  }
+  pub fn animator_mut(&mut self, part: &str) -> Option<&mut Animator> {
+    self.animators.get_mut(part)
+  }
+  /// advance all animators, return (part, clip, event)
+  pub fn animate(&mut self, delta: usize) -> Vec<(String, String, AnimationEventKind)> {
+    let mut events = vec![];
+    let parts: Vec<String> = self.animators.keys().cloned().collect();
+    for part in parts {
+      let Some(animator) = self.animators.get_mut(&part) else {
+        continue;
+      };
+      for (clip, event) in animator.advance(delta) {
+        events.push((part.clone(), clip, event));
+      }
+      self.apply_frame(&part);
+    }
+    events
+  }
+  fn apply_frame(&mut self, part: &str) {
+    let Some(animator) = self.animators.get(part) else {
+      return;
+    };
+    let Some(frame) = animator.current() else {
+      return;
+    };
+    let Some(item) = self
+      .viewboxes
+      .get_mut(part)
+      .and_then(|items| items.get_mut(animator.item))
+    else {
+      return;
+    };
+    if let Some(rect) = frame.rect {
+      item.0 = rect;
+    }
+    if item.1 != frame.texture {
+      item.1 = frame.texture.clone();
+    }
+  }
+  //================================================================================
   // ViewBox
   //================================================================================
+  pub fn parts(&self) -> impl Iterator<Item = (&String, &Vec<(Rect, Texture)>)> {
+    self.viewboxes.iter()
+  }
   pub fn viewboxes(&self) -> Vec<(Rect, Texture)> {
     self.viewboxes.values().flatten().cloned().collect()
   }
@@ -1,6 +1,11 @@
+pub mod asset;
 pub mod context;
 pub mod enity;
 pub mod scene;
+pub mod navigation;
+pub mod net;
+pub mod tilemap;
+pub mod tween;
 // pub mod ui;
 
 #[macro_use]
@@ -0,0 +1,168 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::utils::{scalar::Scalar, vector::Vector};
+
+use super::NavGrid;
+
+/// # 流場
+/// 從目標向外計算整張導航網格的距離, 每個格子指向下一個更接近目標的格子
+/// 大量單位前往同一目標時, 只需計算一次
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowField {
+  grid: NavGrid,
+  goal: Vector,
+  /// step cost to goal, `u32::MAX` is unreachable
+  cost: Vec<u32>,
+  /// next cell index toward goal
+  next: Vec<usize>,
+}
+
+impl FlowField {
+  /// compute field over grid toward goal
+  /// 計算前往目標的流場
+  pub fn new(grid: &NavGrid, goal: Vector) -> Self {
+    let mut field = FlowField {
+      grid: grid.clone(),
+      goal,
+      cost: vec![],
+      next: vec![],
+    };
+    field.compute();
+    field
+  }
+
+  pub fn goal(&self) -> Vector {
+    self.goal
+  }
+
+  /// recompute toward new goal
+  pub fn set_goal(&mut self, goal: Vector) {
+    self.goal = goal;
+    self.compute();
+  }
+
+  /// recompute after grid changed
+  pub fn set_grid(&mut self, grid: &NavGrid) {
+    self.grid = grid.clone();
+    self.compute();
+  }
+
+  fn compute(&mut self) {
+    let (width, height) = self.grid.dimension();
+    self.cost = vec![u32::MAX; width * height];
+    self.next = vec![usize::MAX; width * height];
+
+    let Some(goal) = self.grid.cell_at(self.goal) else {
+      return;
+    };
+    if self.grid.is_blocked(goal.0, goal.1) {
+      return;
+    }
+
+    // dijkstra from goal
+    let index = |(x, y): (usize, usize)| x + y * width;
+    let mut open = BinaryHeap::new();
+    self.cost[index(goal)] = 0;
+    open.push(Reverse((0, goal)));
+    while let Some(Reverse((cost, cell))) = open.pop() {
+      if cost > self.cost[index(cell)] {
+        continue;
+      }
+      for (neighbour, step) in self.grid.neighbours(cell.0, cell.1) {
+        let next_cost = cost + step;
+        if next_cost < self.cost[index(neighbour)] {
+          self.cost[index(neighbour)] = next_cost;
+          self.next[index(neighbour)] = index(cell);
+          open.push(Reverse((next_cost, neighbour)));
+        }
+      }
+    }
+  }
+
+  /// step cost to goal from point
+  pub fn cost_at(&self, point: Vector) -> Option<u32> {
+    let (x, y) = self.grid.cell_at(point)?;
+    let (width, _) = self.grid.dimension();
+    match self.cost[x + y * width] {
+      u32::MAX => None,
+      cost => Some(cost),
+    }
+  }
+
+  pub fn is_reachable(&self, point: Vector) -> bool {
+    self.cost_at(point).is_some()
+  }
+
+  /// point to move toward from point, goal itself inside goal cell
+  /// 得到下一個前進的目標點
+  pub fn sample(&self, point: Vector) -> Option<Vector> {
+    let (x, y) = self.grid.cell_at(point)?;
+    let (width, _) = self.grid.dimension();
+    let index = x + y * width;
+    match (self.cost[index], self.next[index]) {
+      (u32::MAX, _) => None,
+      (0, _) => Some(self.goal),
+      (_, next) => Some(self.grid.cell_center(next % width, next / width)),
+    }
+  }
+
+  /// next position after moving `length` along field
+  pub fn step(&self, position: Vector, length: Scalar) -> Vector {
+    let Some(target) = self.sample(position) else {
+      return position;
+    };
+    let moving = position.to(target);
+    if moving.distance() > length {
+      position + moving.by_length(length)
+    } else {
+      target
+    }
+  }
+}
+
+#[test]
+fn test() {
+  use crate::{
+    modules::{
+      enity::{base::EnityBase, position::MoveEvent, track::EnityTrack, view::EnityView},
+      scene::NormalScene,
+    },
+    utils::{rchash::RcHash, rect::Rect, scalar::scalar},
+  };
+
+  // wall in the middle with a gap at the top
+  let mut grid = NavGrid::new(Vector::new(-50., -50.), Vector::new(10., 10.), 10, 10);
+  for y in 0..8 {
+    grid.set_blocked(4, y, true);
+    grid.set_blocked(5, y, true);
+  }
+  let goal = Vector::new(30., -30.);
+  let field = FlowField::new(&grid, goal);
+  assert_eq!(field.cost_at(goal), Some(0));
+  assert!(!field.is_reachable(Vector::new(0., 0.)));
+  assert!(
+    field.cost_at(Vector::new(-30., -30.)).unwrap() > field.cost_at(Vector::new(0., 45.)).unwrap()
+  );
+
+  let field = RcHash::new(field);
+  let mut scene = NormalScene::new(Vector::new(100., 100.));
+  let mut tracks = vec![];
+  for x in [-45., -35., -25.] {
+    let base = EnityBase::new("unit".to_string(), vec![], scalar(100.));
+    let view = EnityView::new(vec![], vec![Rect::new(Vector::ORIGIN, Vector::new(2., 2.))]);
+    let track = EnityTrack::new(base, view);
+    let mut position = track.position(scene.uuid());
+    position.set(Vector::new(x, -45.));
+    position.set_action(MoveEvent::Flow(field.clone()));
+    drop(position);
+    scene.insert(&track);
+    tracks.push(track);
+  }
+
+  for _ in 0..200 {
+    scene.update(16);
+  }
+  for track in tracks {
+    assert!(track.position(scene.uuid()).get().to(goal).distance() < scalar(5.));
+  }
+}
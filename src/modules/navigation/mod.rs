@@ -0,0 +1,468 @@
+use std::{
+  cmp::Reverse,
+  collections::{BinaryHeap, VecDeque},
+};
+
+use crate::utils::{
+  geometry::Aabb,
+  hitbox::HitBox,
+  rchash::RcHash,
+  rect::Rect,
+  scalar::{scalar, to_f32, Scalar},
+  vector::Vector,
+};
+
+use super::scene::NormalScene;
+
+pub mod flow;
+
+/// straight and diagonal step cost
+const STRAIGHT: u32 = 10;
+const DIAGONAL: u32 = 14;
+
+///=========================================================================================
+/// NavGrid
+///=========================================================================================
+/// # 導航網格
+/// 以場景中的靜態碰撞體 (實心瓦片, 靜態實體) 標記不可通行的格子
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavGrid {
+  /// left-bottom of cell `(0, 0)`
+  origin: Vector,
+  cell_size: Vector,
+  width: usize,
+  height: usize,
+  blocked: Vec<bool>,
+}
+
+impl NavGrid {
+  pub fn new(origin: Vector, cell_size: Vector, width: usize, height: usize) -> Self {
+    NavGrid {
+      origin,
+      cell_size,
+      width,
+      height,
+      blocked: vec![false; width * height],
+    }
+  }
+
+  /// build grid covering scene from its static colliders
+  /// 由場景的靜態碰撞體建立導航網格
+  pub fn from_scene(scene: &NormalScene, cell_size: Vector) -> Self {
+    let size = scene.size();
+    let count = (size / cell_size).ceil();
+    let mut grid = NavGrid::new(
+      -size / scalar(2.),
+      cell_size,
+      to_f32(count.0) as usize,
+      to_f32(count.1) as usize,
+    );
+
+    let mut obstacles = vec![];
+    if let Some(tilemap) = scene.tilemap() {
+      obstacles.extend(tilemap.solid_rects(grid.bounds()));
+    }
+    for track in scene.entities().values() {
+      let base = track.base();
+      if base.is_static() && base.get_collision() != 0 && !base.is_destroy() {
+        obstacles.extend(track.hitbox_object(scene.uuid()));
+      }
+    }
+    for obstacle in obstacles {
+      grid.block_rect(obstacle);
+    }
+    grid
+  }
+
+  pub fn origin(&self) -> Vector {
+    self.origin
+  }
+  pub fn cell_size(&self) -> Vector {
+    self.cell_size
+  }
+  /// (width, height) in cells
+  pub fn dimension(&self) -> (usize, usize) {
+    (self.width, self.height)
+  }
+  pub fn bounds(&self) -> Aabb {
+    let size = self.cell_size * Vector::new(self.width as f32, self.height as f32);
+    Aabb::new(self.origin, self.origin + size)
+  }
+
+  //================================================================================
+  // Cell
+  //================================================================================
+  pub fn is_blocked(&self, x: usize, y: usize) -> bool {
+    x >= self.width || y >= self.height || self.blocked[x + y * self.width]
+  }
+  pub fn set_blocked(&mut self, x: usize, y: usize, blocked: bool) {
+    if x < self.width && y < self.height {
+      self.blocked[x + y * self.width] = blocked
+    }
+  }
+  /// block every cell overlapping rect
+  pub fn block_rect(&mut self, rect: Rect) {
+    let area = rect.aabb();
+    let (min_x, min_y) = self.cell_index(area.min);
+    let (max_x, max_y) = self.cell_index(area.max);
+    for y in min_y.max(0)..=max_y.min(self.height as isize - 1) {
+      for x in min_x.max(0)..=max_x.min(self.width as isize - 1) {
+        let (x, y) = (x as usize, y as usize);
+        // shrink cell so that touching edges are not blocked
+        let mut cell = self.cell_rect(x, y);
+        cell.size *= scalar(0.98);
+        if HitBox::collision(&cell, &rect) {
+          self.set_blocked(x, y, true);
+        }
+      }
+    }
+  }
+  /// cell containing point
+  pub fn cell_at(&self, point: Vector) -> Option<(usize, usize)> {
+    let (x, y) = self.cell_index(point);
+    if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+      return None;
+    }
+    Some((x as usize, y as usize))
+  }
+  pub fn cell_center(&self, x: usize, y: usize) -> Vector {
+    Vector::new(x as f32 + 0.5, y as f32 + 0.5) * self.cell_size + self.origin
+  }
+  pub fn cell_rect(&self, x: usize, y: usize) -> Rect {
+    Rect::new(self.cell_center(x, y), self.cell_size)
+  }
+  pub fn is_blocked_at(&self, point: Vector) -> bool {
+    self
+      .cell_at(point)
+      .is_none_or(|(x, y)| self.is_blocked(x, y))
+  }
+
+  fn cell_index(&self, point: Vector) -> (isize, isize) {
+    let local = (point - self.origin) / self.cell_size;
+    (
+      to_f32(local.0).floor() as isize,
+      to_f32(local.1).floor() as isize,
+    )
+  }
+
+  /// walkable neighbours with step cost, diagonal does not cut corners
+  pub(crate) fn neighbours(&self, x: usize, y: usize) -> Vec<((usize, usize), u32)> {
+    let mut neighbours = Vec::with_capacity(8);
+    for (dx, dy) in [
+      (1, 0),
+      (-1, 0),
+      (0, 1),
+      (0, -1),
+      (1, 1),
+      (1, -1),
+      (-1, 1),
+      (-1, -1),
+    ] {
+      let (nx, ny) = (x as isize + dx, y as isize + dy);
+      if nx < 0 || ny < 0 {
+        continue;
+      }
+      let (nx, ny) = (nx as usize, ny as usize);
+      if self.is_blocked(nx, ny) {
+        continue;
+      }
+      let cost = if dx != 0 && dy != 0 {
+        if self.is_blocked(nx, y) || self.is_blocked(x, ny) {
+          continue;
+        }
+        DIAGONAL
+      } else {
+        STRAIGHT
+      };
+      neighbours.push(((nx, ny), cost));
+    }
+    neighbours
+  }
+
+  //================================================================================
+  // A*
+  //================================================================================
+  /// find waypoints from start to goal, last waypoint is goal
+  /// 以 A* 尋找路徑, 回傳路徑點 (最後一點為目標)
+  pub fn find_path(&self, start: Vector, goal: Vector) -> Option<Vec<Vector>> {
+    let start_cell = self.cell_at(start)?;
+    let goal_cell = self.cell_at(goal)?;
+    if self.is_blocked(goal_cell.0, goal_cell.1) {
+      return None;
+    }
+
+    let index = |(x, y): (usize, usize)| x + y * self.width;
+    let heuristic = |(x, y): (usize, usize)| {
+      let dx = x.abs_diff(goal_cell.0) as u32;
+      let dy = y.abs_diff(goal_cell.1) as u32;
+      STRAIGHT * dx.max(dy) + (DIAGONAL - STRAIGHT) * dx.min(dy)
+    };
+
+    let mut cost = vec![u32::MAX; self.width * self.height];
+    let mut came_from = vec![usize::MAX; self.width * self.height];
+    let mut open = BinaryHeap::new();
+    cost[index(start_cell)] = 0;
+    // (f, h) ordering keeps search deterministic
+    open.push(Reverse((
+      heuristic(start_cell),
+      heuristic(start_cell),
+      start_cell,
+    )));
+
+    while let Some(Reverse((f, h, cell))) = open.pop() {
+      if cell == goal_cell {
+        break;
+      }
+      let current = cost[index(cell)];
+      // stale entry, cell was reached cheaper after it was pushed
+      if f - h > current {
+        continue;
+      }
+      for (next, step) in self.neighbours(cell.0, cell.1) {
+        let next_cost = current + step;
+        if next_cost < cost[index(next)] {
+          cost[index(next)] = next_cost;
+          came_from[index(next)] = index(cell);
+          let h = heuristic(next);
+          open.push(Reverse((next_cost + h, h, next)));
+        }
+      }
+    }
+
+    if cost[index(goal_cell)] == u32::MAX {
+      return None;
+    }
+
+    let mut cells = vec![goal_cell];
+    let mut current = index(goal_cell);
+    while current != index(start_cell) {
+      current = came_from[current];
+      cells.push((current % self.width, current / self.width));
+    }
+    cells.reverse();
+
+    // keep only turning points
+    let mut waypoints = vec![];
+    for i in 1..cells.len().saturating_sub(1) {
+      let (a, b, c) = (cells[i - 1], cells[i], cells[i + 1]);
+      let first = (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize);
+      let second = (c.0 as isize - b.0 as isize, c.1 as isize - b.1 as isize);
+      if first != second {
+        waypoints.push(self.cell_center(b.0, b.1));
+      }
+    }
+    waypoints.push(goal);
+    Some(waypoints)
+  }
+}
+
+///=========================================================================================
+/// NavPath
+///=========================================================================================
+/// 沿路徑點移動, 受阻時重新規劃
+/// re-plans when a waypoint becomes blocked on the grid, or when pushed back for a while
+/// by something not on the grid (e.g. a moving entity), then the cell ahead is avoided
+#[derive(Debug, Clone)]
+pub struct NavPath {
+  grid: RcHash<NavGrid>,
+  goal: Vector,
+  waypoints: VecDeque<Vector>,
+  /// position after last step, to detect being pushed back
+  expected: Option<Vector>,
+  stuck: u32,
+}
+
+impl NavPath {
+  /// ticks without progress before re-planning
+  pub const STUCK_LIMIT: u32 = 10;
+
+  pub fn new(grid: &RcHash<NavGrid>, start: Vector, goal: Vector) -> Self {
+    let mut path = NavPath {
+      grid: grid.clone(),
+      goal,
+      waypoints: VecDeque::new(),
+      expected: None,
+      stuck: 0,
+    };
+    path.plan(start);
+    path
+  }
+
+  pub fn goal(&self) -> Vector {
+    self.goal
+  }
+  pub fn waypoints(&self) -> &VecDeque<Vector> {
+    &self.waypoints
+  }
+  pub fn is_finished(&self) -> bool {
+    self.waypoints.is_empty()
+  }
+
+  /// plan again from position
+  pub fn plan(&mut self, position: Vector) -> bool {
+    let waypoints = self.grid.borrow().find_path(position, self.goal);
+    self.replace(waypoints)
+  }
+
+  /// plan again from position, treating the cell ahead as blocked
+  /// fall back to normal plan if there is no way around
+  pub fn plan_around(&mut self, position: Vector) -> bool {
+    let Some(waypoint) = self.waypoints.front().copied() else {
+      return self.plan(position);
+    };
+    let mut grid = self.grid.borrow().clone();
+    let ahead = position
+      + position
+        .to(waypoint)
+        .by_length(grid.cell_size.0.min(grid.cell_size.1));
+    let skip = [grid.cell_at(position), grid.cell_at(self.goal)];
+    if let Some((x, y)) = grid
+      .cell_at(ahead)
+      .filter(|cell| !skip.contains(&Some(*cell)))
+    {
+      grid.set_blocked(x, y, true);
+    }
+    match grid.find_path(position, self.goal) {
+      Some(waypoints) => self.replace(Some(waypoints)),
+      None => self.plan(position),
+    }
+  }
+
+  fn replace(&mut self, waypoints: Option<Vec<Vector>>) -> bool {
+    self.stuck = 0;
+    self.expected = None;
+    match waypoints {
+      Some(waypoints) => {
+        self.waypoints = waypoints.into();
+        true
+      }
+      None => {
+        self.waypoints.clear();
+        false
+      }
+    }
+  }
+
+  /// next position after moving `length` along path
+  pub fn step(&mut self, position: Vector, length: Scalar) -> Vector {
+    if self.is_waypoint_blocked() {
+      self.plan(position);
+    } else if self.is_stuck(position) {
+      self.plan_around(position);
+    }
+
+    let mut position = position;
+    let mut left = length;
+    while let Some(waypoint) = self.waypoints.front().copied() {
+      let moving = position.to(waypoint);
+      let distance = moving.distance();
+      if distance > left {
+        position += moving.by_length(left);
+        break;
+      }
+      position = waypoint;
+      left -= distance;
+      self.waypoints.pop_front();
+    }
+
+    self.expected = Some(position);
+    position
+  }
+
+  /// waypoint became blocked on grid
+  fn is_waypoint_blocked(&self) -> bool {
+    let grid = self.grid.borrow();
+    self
+      .waypoints
+      .iter()
+      .any(|waypoint| grid.is_blocked_at(*waypoint))
+  }
+
+  /// pushed back for a while
+  fn is_stuck(&mut self, position: Vector) -> bool {
+    let grid = self.grid.borrow();
+    match self.expected {
+      Some(expected)
+        if (expected - position).distance()
+          > grid.cell_size.0.min(grid.cell_size.1) / scalar(4.) =>
+      {
+        self.stuck += 1
+      }
+      _ => self.stuck = 0,
+    }
+    !self.waypoints.is_empty() && self.stuck >= Self::STUCK_LIMIT
+  }
+}
+
+#[test]
+fn test() {
+  use crate::modules::{
+    context::render::Texture,
+    enity::{base::EnityBase, position::MoveEvent, track::EnityTrack, view::EnityView},
+  };
+
+  // wall in the middle with a gap at the top
+  let mut scene = NormalScene::new(Vector::new(100., 100.));
+  let base = {
+    let mut base = EnityBase::new("wall".to_string(), vec![], scalar(0.));
+    base.set_static(true);
+    base
+  };
+  let wall = Rect::new(Vector::ORIGIN, Vector::new(10., 80.));
+  let view = EnityView::new(vec![(wall, Texture::default())], vec![wall]);
+  let track = EnityTrack::new(base, view);
+  track.position(scene.uuid()).set(Vector::new(0., -10.));
+  scene.insert(&track);
+
+  let grid = NavGrid::from_scene(&scene, Vector::new(10., 10.));
+  assert_eq!(grid.dimension(), (10, 10));
+  assert!(grid.is_blocked_at(Vector::new(0., 0.)));
+  assert!(grid.is_blocked_at(Vector::new(0., -45.)));
+  assert!(!grid.is_blocked_at(Vector::new(0., 45.)));
+  assert!(!grid.is_blocked_at(Vector::new(-15., 0.)));
+
+  let start = Vector::new(-30., -30.);
+  let goal = Vector::new(30., -30.);
+  let path = grid.find_path(start, goal).unwrap();
+  assert_eq!(*path.last().unwrap(), goal);
+  assert!(path.iter().any(|point| point.1 > scalar(30.)));
+  assert!(path.iter().all(|point| !grid.is_blocked_at(*point)));
+
+  let grid = RcHash::new(grid);
+  let base = EnityBase::new("walker".to_string(), vec![], scalar(100.));
+  let view = EnityView::new(vec![], vec![Rect::new(Vector::ORIGIN, Vector::new(4., 4.))]);
+  let walker = EnityTrack::new(base, view);
+  let mut position = walker.position(scene.uuid());
+  position.set(start);
+  position.set_action(MoveEvent::Path(NavPath::new(&grid, start, goal)));
+  drop(position);
+  scene.insert(&walker);
+
+  for _ in 0..200 {
+    scene.update(16);
+  }
+  assert!((walker.position(scene.uuid()).get() - goal).distance() < scalar(0.01));
+
+  // closing the gap makes goal unreachable
+  grid.borrow_mut().set_blocked(4, 8, true);
+  grid.borrow_mut().set_blocked(4, 9, true);
+  assert!(grid.borrow().find_path(start, goal).is_none());
+
+  // blocker not on grid keeps pushing back, re-plan goes around the cell ahead
+  let open = RcHash::new(NavGrid::new(
+    Vector::new(-50., -50.),
+    Vector::new(10., 10.),
+    10,
+    10,
+  ));
+  let (start, goal) = (Vector::new(-35., 5.), Vector::new(35., 5.));
+  let mut path = NavPath::new(&open, start, goal);
+  assert_eq!(path.waypoints(), &VecDeque::from([goal]));
+  for _ in 0..NavPath::STUCK_LIMIT + 1 {
+    path.step(start, scalar(5.));
+  }
+  assert!(path.waypoints().len() > 1);
+  let first = path.waypoints()[0];
+  assert!(open.borrow().cell_at(first) != open.borrow().cell_at(Vector::new(-25., 5.)));
+  assert!(first.1 != scalar(5.));
+}
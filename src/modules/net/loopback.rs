@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+
+use crate::utils::rchash::RcHash;
+
+use super::Transport;
+
+/// # 本地迴環網路
+/// 在同一個行程內模擬延遲和丟包, 用於測試
+/// 時間以 `advance` 的次數 (tick) 計算
+#[derive(Debug, Clone)]
+pub struct LoopbackNetwork(RcHash<LoopbackState>);
+
+#[derive(Debug)]
+struct LoopbackState {
+  config: LoopbackConfig,
+  clock: u64,
+  seed: u64,
+  /// (deliver at, from, to, packet)
+  queue: VecDeque<(u64, usize, usize, Vec<u8>)>,
+  endpoints: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LoopbackConfig {
+  /// ticks before a packet is delivered
+  pub latency: u64,
+  /// random extra ticks `0..=jitter`
+  pub jitter: u64,
+  /// drop rate `0.0 ~ 1.0`
+  pub loss: f32,
+  pub seed: u64,
+}
+
+impl Default for LoopbackConfig {
+  fn default() -> Self {
+    LoopbackConfig {
+      latency: 0,
+      jitter: 0,
+      loss: 0.,
+      seed: 0x9E3779B97F4A7C15,
+    }
+  }
+}
+
+impl LoopbackNetwork {
+  pub fn new(config: LoopbackConfig) -> Self {
+    LoopbackNetwork(RcHash::new(LoopbackState {
+      config,
+      clock: 0,
+      seed: config.seed.max(1),
+      queue: VecDeque::new(),
+      endpoints: 0,
+    }))
+  }
+
+  /// 創建新端點, 發送的封包會廣播至其他所有端點
+  pub fn endpoint(&self) -> LoopbackTransport {
+    let mut state = self.0.borrow_mut();
+    state.endpoints += 1;
+    LoopbackTransport {
+      id: state.endpoints - 1,
+      network: self.clone(),
+    }
+  }
+
+  /// 推進一個 tick
+  pub fn advance(&self) {
+    self.0.borrow_mut().clock += 1;
+  }
+
+  pub fn clock(&self) -> u64 {
+    self.0.borrow().clock
+  }
+}
+
+impl LoopbackState {
+  /// xorshift64, deterministic for tests
+  fn random(&mut self) -> u64 {
+    let mut x = self.seed;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    self.seed = x;
+    x
+  }
+
+  fn send(&mut self, from: usize, packet: Vec<u8>) {
+    for to in 0..self.endpoints {
+      if to == from {
+        continue;
+      }
+      let dropped = (self.random() % 10_000) as f32 / 10_000. < self.config.loss;
+      if dropped {
+        continue;
+      }
+      let jitter = match self.config.jitter {
+        0 => 0,
+        jitter => self.random() % (jitter + 1),
+      };
+      let deliver = self.clock + self.config.latency + jitter;
+      self.queue.push_back((deliver, from, to, packet.clone()));
+    }
+  }
+
+  fn receive(&mut self, to: usize) -> Vec<Vec<u8>> {
+    let clock = self.clock;
+    let mut received = vec![];
+    self.queue.retain(|(deliver, _, target, packet)| {
+      if *target == to && *deliver <= clock {
+        received.push(packet.clone());
+        return false;
+      }
+      true
+    });
+    received
+  }
+}
+
+/// 迴環網路的端點
+#[derive(Debug, Clone)]
+pub struct LoopbackTransport {
+  id: usize,
+  network: LoopbackNetwork,
+}
+
+impl LoopbackTransport {
+  pub fn id(&self) -> usize {
+    self.id
+  }
+}
+
+impl Transport for LoopbackTransport {
+  fn send(&mut self, packet: Vec<u8>) {
+    self.network.0.borrow_mut().send(self.id, packet)
+  }
+
+  fn receive(&mut self) -> Vec<Vec<u8>> {
+    self.network.0.borrow_mut().receive(self.id)
+  }
+}
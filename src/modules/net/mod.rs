@@ -0,0 +1,26 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+pub mod loopback;
+pub mod replication;
+pub mod rollback;
+
+/// # 傳輸層
+/// 以封包為單位收發資料, 不保證送達與順序
+pub trait Transport {
+  /// 發送封包
+  fn send(&mut self, packet: Vec<u8>);
+  /// 取出所有已到達的封包
+  fn receive(&mut self) -> Vec<Vec<u8>>;
+}
+
+/// encode message by postcard
+/// 以 postcard 編碼訊息
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+  postcard::to_stdvec(message).unwrap()
+}
+
+/// decode message by postcard, broken packet gives `None`
+/// 以 postcard 解碼訊息, 損壞的封包得到 `None`
+pub fn decode<T: DeserializeOwned>(packet: &[u8]) -> Option<T> {
+  postcard::from_bytes(packet).ok()
+}
@@ -0,0 +1,525 @@
+use std::collections::BTreeMap;
+
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+  modules::{
+    context::{control::Control, render::ViewPort},
+    enity::{base::EnityBase, track::EnityTrack, view::EnityView},
+    scene::NormalScene,
+  },
+  utils::{
+    geometry::Aabb,
+    rect::Rect,
+    scalar::{scalar, Scalar},
+    vector::Vector,
+  },
+};
+
+use super::{decode, encode, Transport};
+
+//==============================================================================================
+// Messages
+//==============================================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+  /// changes from `baseline` (empty if `None`) to `tick`
+  Tick {
+    tick: u64,
+    baseline: Option<u64>,
+    changes: Vec<EnityChange>,
+  },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClientMessage {
+  Control {
+    tick: u64,
+    control: Control,
+  },
+  /// client has applied this tick
+  Ack(u64),
+  /// client viewport for interest management
+  ViewPort(ViewPort),
+}
+
+/// 實體變化, 只包含改變的欄位
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EnityChange {
+  Spawn {
+    uuid: Uuid,
+    state: ReplicatedState,
+  },
+  Despawn(Uuid),
+  Update {
+    uuid: Uuid,
+    position: Option<Vector>,
+    angle: Option<Scalar>,
+    base: Option<EnityBase>,
+    view: Option<EnityView>,
+  },
+}
+
+/// 同步到客戶端的實體狀態
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicatedState {
+  pub position: Vector,
+  pub angle: Scalar,
+  pub base: EnityBase,
+  pub view: EnityView,
+}
+
+impl ReplicatedState {
+  pub fn capture(scene_uuid: Uuid, track: &EnityTrack) -> Self {
+    let position = track.position(scene_uuid);
+    ReplicatedState {
+      position: position.get(),
+      angle: position.get_angle(),
+      base: track.base().clone(),
+      view: track.view().clone(),
+    }
+  }
+
+  /// changes from `self` to `target`, `None` if nothing changed
+  pub fn diff(&self, uuid: Uuid, target: &ReplicatedState) -> Option<EnityChange> {
+    fn changed<T: PartialEq + Clone>(from: &T, to: &T) -> Option<T> {
+      (from != to).then(|| to.clone())
+    }
+    let change = EnityChange::Update {
+      uuid,
+      position: changed(&self.position, &target.position),
+      angle: changed(&self.angle, &target.angle),
+      base: changed(&self.base, &target.base),
+      view: changed(&self.view, &target.view),
+    };
+    (self != target).then_some(change)
+  }
+}
+
+//==============================================================================================
+// Server
+//==============================================================================================
+
+/// states of all replicated entities at one tick
+pub type ReplicatedStates = IndexMap<Uuid, ReplicatedState>;
+
+/// # 權威伺服器
+/// 每個 tick 將場景內實體的生成, 銷毀和變化同步到所有客戶端
+///
+/// 每個客戶端只會收到其 `ViewPort` 附近 (透過空間網格查詢) 的實體,
+/// 並以客戶端最後確認的 tick 為基準進行差分, 因此可以容忍丟包和亂序
+#[derive(Debug)]
+pub struct ReplicationServer<T: Transport> {
+  tick: u64,
+  /// extra range around client viewport
+  margin: Scalar,
+  /// max ticks kept for baseline
+  history: usize,
+  clients: Vec<ServerClient<T>>,
+}
+
+#[derive(Debug)]
+struct ServerClient<T: Transport> {
+  transport: T,
+  viewport: Option<ViewPort>,
+  acked: Option<u64>,
+  /// states sent to this client
+  sent: BTreeMap<u64, ReplicatedStates>,
+}
+
+impl<T: Transport> ReplicationServer<T> {
+  pub fn new() -> Self {
+    ReplicationServer {
+      tick: 0,
+      margin: scalar(100.),
+      history: 64,
+      clients: vec![],
+    }
+  }
+
+  pub fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  pub fn set_margin(&mut self, margin: Scalar) {
+    self.margin = margin
+  }
+
+  /// 加入客戶端, 下個 tick 會收到所有相關實體
+  pub fn connect(&mut self, transport: T) -> usize {
+    self.clients.push(ServerClient {
+      transport,
+      viewport: None,
+      acked: None,
+      sent: BTreeMap::new(),
+    });
+    self.clients.len() - 1
+  }
+
+  /// 取出客戶端送來的控制 (client id, tick, control)
+  pub fn receive(&mut self) -> Vec<(usize, u64, Control)> {
+    let mut controls = vec![];
+    for (id, client) in self.clients.iter_mut().enumerate() {
+      for packet in client.transport.receive() {
+        let Some(message) = decode(&packet) else {
+          continue;
+        };
+        match message {
+          ClientMessage::Control { tick, control } => controls.push((id, tick, control)),
+          ClientMessage::Ack(tick) => {
+            if client.sent.contains_key(&tick) && client.acked.is_none_or(|acked| acked < tick) {
+              client.acked = Some(tick);
+            }
+          }
+          ClientMessage::ViewPort(viewport) => client.viewport = Some(viewport),
+        }
+      }
+    }
+    controls
+  }
+
+  /// 將場景同步到所有客戶端
+  pub fn replicate(&mut self, scene: &NormalScene) {
+    let states: ReplicatedStates = scene
+      .entities()
+      .iter()
+      .map(|(uuid, track)| (*uuid, ReplicatedState::capture(scene.uuid(), track)))
+      .collect();
+
+    for client in self.clients.iter_mut() {
+      let relevant = match client.viewport {
+        Some(viewport) => {
+          let uuids = relevant(scene, &viewport, self.margin);
+          states
+            .iter()
+            .filter(|(uuid, _)| uuids.contains(*uuid))
+            .map(|(uuid, state)| (*uuid, state.clone()))
+            .collect()
+        }
+        None => states.clone(),
+      };
+
+      let empty = IndexMap::new();
+      let baseline = client.acked.filter(|acked| client.sent.contains_key(acked));
+      let from = baseline.map_or(&empty, |tick| &client.sent[&tick]);
+      let changes = diff_states(from, &relevant);
+      let message = ServerMessage::Tick {
+        tick: self.tick,
+        baseline,
+        changes,
+      };
+      client.transport.send(encode(&message));
+
+      client.sent.insert(self.tick, relevant);
+      let oldest = self.tick.saturating_sub(self.history as u64);
+      let keep = baseline.unwrap_or(oldest).max(oldest);
+      client.sent.retain(|tick, _| *tick >= keep);
+    }
+    self.tick += 1;
+  }
+}
+
+impl<T: Transport> Default for ReplicationServer<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// entities near viewport, found by the spatial grid
+/// entities without hitbox are found by viewbox instead
+fn relevant(scene: &NormalScene, viewport: &ViewPort, margin: Scalar) -> IndexSet<Uuid> {
+  // bounds of rotated and zoomed viewport
+  let bounds = Aabb::from_viewbox(viewport);
+  let area = Rect::new(bounds.center(), bounds.size() + margin * scalar(2.));
+  scene
+    .collision_by_rect(area)
+    .iter()
+    .chain(scene.view_by_rect(area).iter())
+    .map(|track| track.uuid())
+    .collect()
+}
+
+/// changes to turn `from` into `to`
+pub fn diff_states(from: &ReplicatedStates, to: &ReplicatedStates) -> Vec<EnityChange> {
+  let mut changes = vec![];
+  for uuid in from.keys() {
+    if !to.contains_key(uuid) {
+      changes.push(EnityChange::Despawn(*uuid));
+    }
+  }
+  for (uuid, state) in to.iter() {
+    match from.get(uuid) {
+      Some(known) => changes.extend(known.diff(*uuid, state)),
+      None => changes.push(EnityChange::Spawn {
+        uuid: *uuid,
+        state: state.clone(),
+      }),
+    }
+  }
+  changes
+}
+
+/// apply change to states
+pub fn apply_state_change(states: &mut ReplicatedStates, change: EnityChange) {
+  match change {
+    EnityChange::Spawn { uuid, state } => {
+      states.insert(uuid, state);
+    }
+    EnityChange::Despawn(uuid) => {
+      states.shift_remove(&uuid);
+    }
+    EnityChange::Update {
+      uuid,
+      position,
+      angle,
+      base,
+      view,
+    } => {
+      let Some(state) = states.get_mut(&uuid) else {
+        return;
+      };
+      if let Some(position) = position {
+        state.position = position;
+      }
+      if let Some(angle) = angle {
+        state.angle = angle;
+      }
+      if let Some(base) = base {
+        state.base = base;
+      }
+      if let Some(view) = view {
+        state.view = view;
+      }
+    }
+  }
+}
+
+//==============================================================================================
+// Client
+//==============================================================================================
+
+/// # 客戶端
+/// 將伺服器的變化套用到本地場景, 並送出控制
+#[derive(Debug)]
+pub struct ReplicationClient<T: Transport> {
+  transport: T,
+  /// last tick applied
+  tick: Option<u64>,
+  /// received states, used as baseline
+  received: BTreeMap<u64, ReplicatedStates>,
+}
+
+impl<T: Transport> ReplicationClient<T> {
+  pub fn new(transport: T) -> Self {
+    ReplicationClient {
+      transport,
+      tick: None,
+      received: BTreeMap::new(),
+    }
+  }
+
+  pub fn tick(&self) -> Option<u64> {
+    self.tick
+  }
+
+  /// 送出控制
+  pub fn send_control(&mut self, control: Control) {
+    let message = ClientMessage::Control {
+      tick: self.tick.unwrap_or(0),
+      control,
+    };
+    self.transport.send(encode(&message));
+  }
+
+  /// 送出視口, 伺服器只會同步附近的實體
+  pub fn send_viewport(&mut self, viewport: ViewPort) {
+    self
+      .transport
+      .send(encode(&ClientMessage::ViewPort(viewport)));
+  }
+
+  /// 接收並套用伺服器的變化
+  pub fn apply(&mut self, scene: &mut NormalScene) {
+    let mut packets: Vec<(u64, Option<u64>, Vec<EnityChange>)> = self
+      .transport
+      .receive()
+      .iter()
+      .filter_map(|packet| decode(packet))
+      .map(
+        |ServerMessage::Tick {
+           tick,
+           baseline,
+           changes,
+         }| (tick, baseline, changes),
+      )
+      .collect();
+    packets.sort_by_key(|(tick, _, _)| *tick);
+
+    for (tick, baseline, changes) in packets {
+      if self.tick.is_some_and(|last| tick <= last) {
+        continue;
+      }
+      let mut states = match baseline {
+        Some(baseline) => match self.received.get(&baseline) {
+          Some(states) => states.clone(),
+          None => continue,
+        },
+        None => IndexMap::new(),
+      };
+      for change in changes {
+        apply_state_change(&mut states, change);
+      }
+
+      let empty = IndexMap::new();
+      let current = self.tick.map_or(&empty, |tick| &self.received[&tick]);
+      for change in diff_states(current, &states) {
+        apply_change(scene, change);
+      }
+
+      self.received.insert(tick, states);
+      self
+        .received
+        .retain(|received, _| baseline.is_none_or(|baseline| *received >= baseline));
+      self.tick = Some(tick);
+      self.transport.send(encode(&ClientMessage::Ack(tick)));
+    }
+  }
+}
+
+/// 將變化套用到場景
+pub fn apply_change(scene: &mut NormalScene, change: EnityChange) {
+  let scene_uuid = scene.uuid();
+  match change {
+    EnityChange::Spawn { uuid, state } => {
+      let track = EnityTrack::with_uuid(uuid, state.base, state.view);
+      {
+        let mut position = track.position(scene_uuid);
+        position.set(state.position);
+        position.set_angle(state.angle);
+      }
+      scene.insert(&track);
+    }
+    EnityChange::Despawn(uuid) => {
+      if let Some(track) = scene.get(&uuid).cloned() {
+        scene.remove(&track);
+      }
+    }
+    EnityChange::Update {
+      uuid,
+      position,
+      angle,
+      base,
+      view,
+    } => {
+      let Some(track) = scene.get(&uuid).cloned() else {
+        return;
+      };
+      let mut track_position = track.position(scene_uuid);
+      if let Some(position) = position {
+        track_position.set(position);
+      }
+      if let Some(angle) = angle {
+        track_position.set_angle(angle);
+      }
+      if let Some(base) = base {
+        *track.base_mut() = base;
+      }
+      if let Some(view) = view {
+        *track.view_mut() = view;
+      }
+    }
+  }
+}
+
+#[test]
+fn test() {
+  use crate::{
+    modules::{
+      context::{control::KeyEvent, render::Texture},
+      enity::position::MoveEvent,
+      net::loopback::{LoopbackConfig, LoopbackNetwork},
+    },
+    utils::scalar::scalar,
+  };
+
+  let mut server_scene = NormalScene::new(Vector::new(1000., 1000.));
+  let mut client_scene = NormalScene::new(Vector::new(1000., 1000.));
+  let mut tracks = vec![];
+  for x in [0., 100., 200., 5000.] {
+    let base = EnityBase::new(format!("enity{x}"), vec![], scalar(50.));
+    let view = EnityView::new(
+      vec![],
+      vec![Rect::new(Vector::ORIGIN, Vector::new(10., 10.))],
+    );
+    let track = EnityTrack::new(base, view);
+    let mut position = track.position(server_scene.uuid());
+    position.set(Vector::new(x, 0.));
+    position.set_action(MoveEvent::Moving(Vector::new(1., 0.)));
+    drop(position);
+    server_scene.insert(&track);
+    tracks.push(track);
+  }
+  // decorations have no hitbox
+  for x in [-100., -5000.] {
+    let base = EnityBase::new(format!("decoration{x}"), vec![], scalar(0.));
+    let rect = Rect::new(Vector::ORIGIN, Vector::new(10., 10.));
+    let view = EnityView::new(vec![(rect, Texture::default())], vec![]);
+    let track = EnityTrack::new(base, view);
+    track.position(server_scene.uuid()).set(Vector::new(x, 0.));
+    server_scene.insert(&track);
+  }
+
+  let network = LoopbackNetwork::new(LoopbackConfig {
+    latency: 2,
+    jitter: 1,
+    loss: 0.3,
+    ..Default::default()
+  });
+  let mut server = ReplicationServer::new();
+  server.connect(network.endpoint());
+  let mut client = ReplicationClient::new(network.endpoint());
+
+  let mut controls = vec![];
+  for tick in 0..80 {
+    if tick == 10 {
+      tracks[1].base_mut().destroy();
+    }
+    if tick == 40 {
+      for track in tracks.iter() {
+        track
+          .position(server_scene.uuid())
+          .set_action(MoveEvent::Stop);
+      }
+    }
+    server_scene.update(16);
+    controls.extend(server.receive());
+    server.replicate(&server_scene);
+    network.advance();
+
+    client.apply(&mut client_scene);
+    client.send_viewport(*client_scene.viewport());
+    let mut control = Control::new();
+    control.keys.push(KeyEvent {
+      code: "KeyD".to_string(),
+      alt: false,
+      ctrl: false,
+      meta: false,
+      shift: false,
+      repeat: false,
+    });
+    client.send_control(control);
+  }
+
+  // far away enity and decoration are not replicated
+  assert_eq!(client_scene.entities().len(), 3);
+  for (uuid, client_track) in client_scene.entities() {
+    let server_track = server_scene.get(uuid).cloned().unwrap();
+    let server_state = ReplicatedState::capture(server_scene.uuid(), &server_track);
+    let client_state = ReplicatedState::capture(client_scene.uuid(), client_track);
+    assert_eq!(server_state, client_state);
+  }
+  assert!(!controls.is_empty());
+}
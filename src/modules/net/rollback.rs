@@ -0,0 +1,412 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::modules::{
+  context::control::Control,
+  scene::{snapshot::SceneSnapshot, NormalScene},
+};
+
+use super::{decode, encode, Transport};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RollbackConfig {
+  pub players: usize,
+  pub local_player: usize,
+  /// local inputs are scheduled `input_delay` ticks later
+  pub input_delay: u64,
+  /// stop advancing when unconfirmed ticks reach this
+  pub max_rollback: u64,
+}
+
+impl RollbackConfig {
+  pub fn new(players: usize, local_player: usize) -> Self {
+    RollbackConfig {
+      players,
+      local_player,
+      input_delay: 2,
+      max_rollback: 8,
+    }
+  }
+}
+
+///=========================================================================================
+/// Error
+///=========================================================================================
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollbackError {
+  /// late input needs a tick older than kept snapshots, peers may have diverged
+  MissingSnapshot(u64),
+}
+
+impl fmt::Display for RollbackError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RollbackError::MissingSnapshot(tick) => {
+        write!(f, "rollback snapshot of tick {tick} is missing")
+      }
+    }
+  }
+}
+
+impl std::error::Error for RollbackError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct InputMessage {
+  player: usize,
+  /// contiguous confirmed ticks of each player seen by sender
+  confirmed: Vec<u64>,
+  inputs: Vec<(u64, Control)>,
+}
+
+/// # 回滾會話
+/// 每個 tick 保存場景快照, 遠端輸入未到達時以最後的輸入預測,
+/// 遲到的輸入與預測不同時, 回到該 tick 重新模擬
+#[derive(Debug)]
+pub struct RollbackSession<T: Transport> {
+  config: RollbackConfig,
+  transport: T,
+  tick: u64,
+  inputs: Vec<BTreeMap<u64, Control>>,
+  /// count of contiguous confirmed ticks for each player
+  confirmed: Vec<u64>,
+  /// count of local ticks confirmed by each player
+  acked: Vec<u64>,
+  /// inputs used to simulate each tick
+  used: BTreeMap<u64, Vec<Control>>,
+  /// scene state before each tick
+  snapshots: BTreeMap<u64, SceneSnapshot>,
+  rollback: Option<u64>,
+  rollbacks: usize,
+  desynced: bool,
+}
+
+impl<T: Transport> RollbackSession<T> {
+  pub fn new(config: RollbackConfig, transport: T) -> Self {
+    let mut inputs = vec![BTreeMap::new(); config.players];
+    let mut confirmed = vec![0; config.players];
+    for tick in 0..config.input_delay {
+      inputs[config.local_player].insert(tick, Control::new());
+    }
+    confirmed[config.local_player] = config.input_delay;
+
+    RollbackSession {
+      config,
+      transport,
+      tick: 0,
+      inputs,
+      confirmed,
+      acked: vec![0; config.players],
+      used: BTreeMap::new(),
+      snapshots: BTreeMap::new(),
+      rollback: None,
+      rollbacks: 0,
+      desynced: false,
+    }
+  }
+
+  /// next tick to simulate
+  pub fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  /// ticks before this are confirmed by every player
+  pub fn confirmed_tick(&self) -> u64 {
+    self
+      .confirmed
+      .iter()
+      .copied()
+      .min()
+      .unwrap_or(0)
+      .min(self.tick)
+  }
+
+  /// count of rollbacks happened
+  pub fn rollbacks(&self) -> usize {
+    self.rollbacks
+  }
+
+  /// a rollback could not be done, this peer may differ from others
+  pub fn is_desynced(&self) -> bool {
+    self.desynced
+  }
+
+  pub fn transport(&self) -> &T {
+    &self.transport
+  }
+
+  /// 推進一個 tick
+  /// `step` 以所有玩家的輸入模擬一個 tick, 必須是確定性的
+  /// 等待遠端輸入 (超過 `max_rollback`) 時返回 `false`
+  /// 需要的快照已被移除時返回錯誤, 並標記為不同步
+  pub fn advance<F>(
+    &mut self,
+    scene: &mut NormalScene,
+    local: Control,
+    mut step: F,
+  ) -> Result<bool, RollbackError>
+  where
+    F: FnMut(&mut NormalScene, &[Control]),
+  {
+    self.poll();
+
+    if self.tick - self.confirmed_tick() >= self.config.max_rollback {
+      self.send_inputs();
+      return Ok(false);
+    }
+
+    let local_player = self.config.local_player;
+    self.inputs[local_player].insert(self.tick + self.config.input_delay, local);
+    self.update_confirmed(local_player);
+    self.send_inputs();
+
+    if let Some(from) = self.rollback.take() {
+      self.resimulate(scene, from, &mut step)?;
+    }
+    self.simulate(scene, &mut step);
+    self.prune();
+    Ok(true)
+  }
+
+  /// 讀取遠端輸入
+  fn poll(&mut self) {
+    for packet in self.transport.receive() {
+      let Some(message) = decode::<InputMessage>(&packet) else {
+        continue;
+      };
+      let player = message.player;
+      if player >= self.config.players || player == self.config.local_player {
+        continue;
+      }
+
+      if let Some(acked) = message.confirmed.get(self.config.local_player) {
+        self.acked[player] = self.acked[player].max(*acked);
+      }
+
+      for (tick, control) in message.inputs {
+        if tick < self.confirmed[player] || self.inputs[player].contains_key(&tick) {
+          continue;
+        }
+        let mispredicted = self
+          .used
+          .get(&tick)
+          .is_some_and(|used| used[player] != control);
+        if mispredicted {
+          self.rollback = Some(self.rollback.map_or(tick, |from| from.min(tick)));
+        }
+        self.inputs[player].insert(tick, control);
+      }
+      self.update_confirmed(player);
+    }
+  }
+
+  fn update_confirmed(&mut self, player: usize) {
+    while self.inputs[player].contains_key(&self.confirmed[player]) {
+      self.confirmed[player] += 1;
+    }
+  }
+
+  /// 發送未被確認的本地輸入 (重送以應對丟包)
+  fn send_inputs(&mut self) {
+    let local_player = self.config.local_player;
+    let from = self.remote_acked();
+    let inputs = self.inputs[local_player]
+      .range(from..)
+      .map(|(tick, control)| (*tick, control.clone()))
+      .collect();
+
+    let message = InputMessage {
+      player: local_player,
+      confirmed: self.confirmed.clone(),
+      inputs,
+    };
+    self.transport.send(encode(&message));
+  }
+
+  /// local ticks confirmed by every remote player
+  fn remote_acked(&self) -> u64 {
+    (0..self.config.players)
+      .filter(|player| *player != self.config.local_player)
+      .map(|player| self.acked[player])
+      .min()
+      .unwrap_or(self.confirmed[self.config.local_player])
+  }
+
+  /// confirmed input, or predict by last known input
+  fn input(&self, player: usize, tick: u64) -> Control {
+    self.inputs[player]
+      .range(..=tick)
+      .next_back()
+      .map(|(_, control)| control.clone())
+      .unwrap_or_else(Control::new)
+  }
+
+  fn simulate<F>(&mut self, scene: &mut NormalScene, step: &mut F)
+  where
+    F: FnMut(&mut NormalScene, &[Control]),
+  {
+    self.snapshots.insert(self.tick, scene.snapshot());
+    let inputs: Vec<Control> = (0..self.config.players)
+      .map(|player| self.input(player, self.tick))
+      .collect();
+    step(scene, &inputs);
+    self.used.insert(self.tick, inputs);
+    self.tick += 1;
+  }
+
+  fn resimulate<F>(
+    &mut self,
+    scene: &mut NormalScene,
+    from: u64,
+    step: &mut F,
+  ) -> Result<(), RollbackError>
+  where
+    F: FnMut(&mut NormalScene, &[Control]),
+  {
+    let Some(snapshot) = self.snapshots.get(&from) else {
+      self.desynced = true;
+      return Err(RollbackError::MissingSnapshot(from));
+    };
+    scene.restore(snapshot);
+
+    let end = self.tick;
+    self.tick = from;
+    while self.tick < end {
+      self.simulate(scene, step);
+    }
+    self.rollbacks += 1;
+    Ok(())
+  }
+
+  /// 移除不再需要的快照和輸入
+  fn prune(&mut self) {
+    let keep = self.confirmed_tick();
+    self.snapshots.retain(|tick, _| *tick >= keep);
+    self.used.retain(|tick, _| *tick >= keep);
+
+    let acked = self.remote_acked();
+    for (player, inputs) in self.inputs.iter_mut().enumerate() {
+      let mut keep = keep;
+      if player == self.config.local_player {
+        keep = keep.min(acked);
+      }
+      // last input before `keep` is still needed for prediction
+      let Some(last) = inputs.range(..keep).next_back().map(|(tick, _)| *tick) else {
+        continue;
+      };
+      inputs.retain(|tick, _| *tick >= last);
+    }
+  }
+}
+
+#[test]
+fn test() {
+  use crate::{
+    modules::{
+      context::control::{GetMoveVector, KeyEvent},
+      enity::{base::EnityBase, position::MoveEvent, track::EnityTrack, view::EnityView},
+      net::loopback::{LoopbackConfig, LoopbackNetwork},
+    },
+    utils::{rect::Rect, scalar::scalar, vector::Vector},
+  };
+
+  fn create_scene() -> NormalScene {
+    let mut scene = NormalScene::new(Vector::new(1000., 1000.));
+    for i in 0..2 {
+      let base = EnityBase::new(format!("player{i}"), vec![], scalar(100.));
+      let view = EnityView::new(
+        vec![],
+        vec![Rect::new(Vector::ORIGIN, Vector::new(10., 10.))],
+      );
+      let track = EnityTrack::new(base, view);
+      track
+        .position(scene.uuid())
+        .set(Vector::new(i as f32 * 500., 0.));
+      scene.insert(&track);
+    }
+    scene
+  }
+
+  fn step(scene: &mut NormalScene, inputs: &[Control]) {
+    let uuid = scene.uuid();
+    for (track, control) in scene.entities().values().zip(inputs) {
+      let vector = control.move_vector();
+      let action = if vector == Vector::ORIGIN {
+        MoveEvent::Stop
+      } else {
+        MoveEvent::Moving(vector)
+      };
+      track.position(uuid).set_action(action);
+    }
+    scene.update(16);
+  }
+
+  fn control(player: usize, tick: u64) -> Control {
+    let mut control = Control::new();
+    let pressed = match player {
+      0 => (10..40).contains(&tick) && !tick.is_multiple_of(7),
+      _ => (20..50).contains(&tick) && !tick.is_multiple_of(5),
+    };
+    if pressed {
+      control.keys.push(KeyEvent {
+        code: ["KeyD", "KeyW"][player].to_string(),
+        alt: false,
+        ctrl: false,
+        meta: false,
+        shift: false,
+        repeat: false,
+      });
+    }
+    control
+  }
+
+  let network = LoopbackNetwork::new(LoopbackConfig {
+    latency: 3,
+    jitter: 2,
+    loss: 0.2,
+    ..Default::default()
+  });
+  let mut peers: Vec<_> = (0..2)
+    .map(|player| {
+      let config = RollbackConfig::new(2, player);
+      (
+        RollbackSession::new(config, network.endpoint()),
+        create_scene(),
+      )
+    })
+    .collect();
+
+  let mut frame = 0;
+  while peers.iter().any(|(session, _)| session.tick() < 150) {
+    frame += 1;
+    assert!(frame < 1000, "session stalled");
+    for (player, (session, scene)) in peers.iter_mut().enumerate() {
+      let local = control(player, session.tick());
+      session.advance(scene, local, step).unwrap();
+    }
+    network.advance();
+  }
+
+  let [(first, first_scene), (second, second_scene)] = peers.as_slice() else {
+    unreachable!()
+  };
+  assert!(first.rollbacks() + second.rollbacks() > 0);
+  assert!(!first.is_desynced() && !second.is_desynced());
+  let tracks = first_scene
+    .entities()
+    .values()
+    .zip(second_scene.entities().values());
+  for (a, b) in tracks {
+    let a = a.position(first_scene.uuid()).get();
+    let b = b.position(second_scene.uuid()).get();
+    assert_eq!(a, b);
+  }
+
+  // late input older than kept snapshots is reported instead of ignored
+  let (mut session, mut scene) = peers.remove(0);
+  assert!(!session.snapshots.contains_key(&0));
+  session.rollback = Some(0);
+  let result = session.advance(&mut scene, Control::new(), step);
+  assert_eq!(result, Err(RollbackError::MissingSnapshot(0)));
+  assert!(session.is_desynced());
+}
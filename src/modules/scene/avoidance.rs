@@ -0,0 +1,239 @@
+use indexmap::IndexMap;
+use uuid::Uuid;
+
+use crate::{
+  modules::enity::track::EnityTrack,
+  utils::{
+    rect::Rect,
+    scalar::{scalar, Scalar},
+    vector::Vector,
+  },
+};
+
+/// # 碰撞處理模式
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CollisionMode {
+  /// push overlapping enity 2 units away each tick
+  /// 重疊時每 tick 推開 2 單位
+  #[default]
+  PushApart,
+  /// velocity based local avoidance
+  /// 以速度預測避讓, 群體移動更平滑
+  Avoidance(AvoidanceConfig),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvoidanceConfig {
+  /// extra distance to look for neighbours
+  pub range: Scalar,
+  /// seconds to look ahead for collisions
+  pub horizon: Scalar,
+  /// part of overlap resolved per tick `0.0 ~ 1.0`
+  pub separation: Scalar,
+}
+
+impl Default for AvoidanceConfig {
+  fn default() -> Self {
+    AvoidanceConfig {
+      range: scalar(20.),
+      horizon: scalar(1.),
+      separation: scalar(0.5),
+    }
+  }
+}
+
+/// enity state before actions
+pub(super) struct Agent {
+  track: EnityTrack,
+  origin: Vector,
+  radius: Scalar,
+  neighbours: Vec<Uuid>,
+}
+
+/// collect agents and neighbours before actions
+pub(super) fn agents(
+  scene: &super::NormalScene,
+  tracks: &IndexMap<Uuid, EnityTrack>,
+  config: &AvoidanceConfig,
+) -> IndexMap<Uuid, Agent> {
+  let mut agents = IndexMap::with_capacity(tracks.len());
+  for (uuid, track) in tracks.iter() {
+    let collision = track.base().get_collision();
+    if collision == 0 {
+      continue;
+    }
+    let hitbox = track.hitbox(scene.uuid);
+    let radius = hitbox.size.0.max(hitbox.size.1) / scalar(2.);
+    let area = Rect::new(hitbox.position, hitbox.size + config.range * scalar(2.));
+    let neighbours = scene
+      .collision_by_rect(area)
+      .into_iter()
+      .filter(|other| other.uuid() != *uuid && other.base().get_collision() == collision)
+      .map(|other| other.uuid())
+      .collect();
+    agents.insert(
+      *uuid,
+      Agent {
+        track: track.clone(),
+        origin: track.position(scene.uuid).get(),
+        radius,
+        neighbours,
+      },
+    );
+  }
+  agents
+}
+
+/// adjust movement of actions to avoid neighbours
+/// 調整實體移動以避開鄰近實體
+pub(super) fn avoid(
+  scene_uuid: Uuid,
+  agents: &IndexMap<Uuid, Agent>,
+  config: &AvoidanceConfig,
+  delta: usize,
+) {
+  let delta = scalar(delta as f32 / 1000.);
+  if delta <= Scalar::EPSILON {
+    return;
+  }
+
+  // intended velocity of every agent
+  let velocities: IndexMap<Uuid, Vector> = agents
+    .iter()
+    .map(|(uuid, agent)| {
+      let moved = agent.origin.to(agent.track.position(scene_uuid).get());
+      (*uuid, moved / delta)
+    })
+    .collect();
+
+  let mut moves = Vec::with_capacity(agents.len());
+  for (index, (uuid, agent)) in agents.iter().enumerate() {
+    if agent.track.base().is_static() {
+      continue;
+    }
+    let velocity = velocities[uuid];
+    let mut avoidance = Vector::ORIGIN;
+    let mut separation = Vector::ORIGIN;
+
+    for other_uuid in agent.neighbours.iter() {
+      let Some(other) = agents.get(other_uuid) else {
+        continue;
+      };
+      let relative = agent.origin.to(other.origin);
+      let relative_velocity = velocities[other_uuid] - velocity;
+      let radius = agent.radius + other.radius;
+      let distance = relative.distance();
+
+      // overlapping: push away by part of the overlap
+      if distance < radius {
+        let away = if distance > Scalar::EPSILON {
+          -relative / distance
+        } else if index < agents.get_index_of(other_uuid).unwrap_or(0) {
+          Vector::new(-1., 0.)
+        } else {
+          Vector::new(1., 0.)
+        };
+        separation += away * (radius - distance) * config.separation;
+        continue;
+      }
+
+      // closest approach within horizon
+      let speed = relative_velocity.distance_magnitude();
+      if speed <= Scalar::EPSILON {
+        continue;
+      }
+      let time = -relative.dot(relative_velocity) / speed;
+      if time <= scalar(0.) || time >= config.horizon {
+        continue;
+      }
+      let closest = relative + relative_velocity * time;
+      let miss = closest.distance();
+      if miss >= radius {
+        continue;
+      }
+      // head-on: both sidestep to their right
+      let away = if miss > Scalar::EPSILON {
+        -closest / miss
+      } else {
+        velocity.by_length(scalar(1.)).right_normal()
+      };
+      let urgency = scalar(1.) - time / config.horizon;
+      avoidance += away * (radius - miss) * urgency / config.horizon;
+    }
+
+    let speed = agent.track.base().speed().max(velocity.distance());
+    let mut adjusted = velocity + avoidance;
+    if adjusted.distance() > speed {
+      adjusted = adjusted.by_length(speed);
+    }
+    moves.push((agent, adjusted * delta + separation));
+  }
+
+  // velocity follows the adjusted move, used by predicting steering
+  for (agent, moved) in moves {
+    let mut position = agent.track.position(scene_uuid);
+    position.set(agent.origin + moved);
+    position.set_velocity(moved / delta);
+  }
+}
+
+#[test]
+fn test() {
+  use crate::modules::{
+    enity::{base::EnityBase, position::MoveEvent, view::EnityView},
+    scene::NormalScene,
+  };
+
+  let mut scene = NormalScene::new(Vector::new(1000., 1000.));
+  scene.set_collision_mode(CollisionMode::Avoidance(AvoidanceConfig::default()));
+  let mut spawn = |position: Vector, target: Vector| {
+    let base = EnityBase::new("agent".to_string(), vec![], scalar(50.));
+    let view = EnityView::new(
+      vec![],
+      vec![Rect::new(Vector::ORIGIN, Vector::new(10., 10.))],
+    );
+    let track = EnityTrack::new(base, view);
+    let mut enity = track.position(scene.uuid());
+    enity.set(position);
+    enity.set_action(MoveEvent::Moveto(target));
+    drop(enity);
+    scene.insert(&track);
+    track
+  };
+
+  // head-on, they must pass each other
+  let left = spawn(Vector::new(-50., 0.), Vector::new(50., 0.));
+  let right = spawn(Vector::new(50., 0.), Vector::new(-50., 0.));
+  // overlapping and standing still, they must separate
+  let a = spawn(Vector::new(200., 0.), Vector::new(200., 0.));
+  let b = spawn(Vector::new(203., 0.), Vector::new(203., 0.));
+  for track in [&a, &b] {
+    track.position(scene.uuid()).set_action(MoveEvent::Stop);
+  }
+
+  let mut closest = Scalar::MAX;
+  for _ in 0..300 {
+    let before = left.position(scene.uuid()).get();
+    scene.update(16);
+    let position = left.position(scene.uuid());
+    let moved = before.to(position.get());
+    assert!((position.velocity() * scalar(0.016) - moved).distance() < scalar(0.01));
+    drop(position);
+    let distance = left
+      .position(scene.uuid())
+      .get()
+      .to(right.position(scene.uuid()).get())
+      .distance();
+    closest = closest.min(distance);
+  }
+  assert!(closest > scalar(8.));
+  assert!((left.position(scene.uuid()).get() - Vector::new(50., 0.)).distance() < scalar(1.));
+  assert!((right.position(scene.uuid()).get() - Vector::new(-50., 0.)).distance() < scalar(1.));
+
+  let gap = a
+    .position(scene.uuid())
+    .get()
+    .to(b.position(scene.uuid()).get())
+    .distance();
+  assert!(gap >= scalar(9.9));
+}
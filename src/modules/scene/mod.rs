@@ -6,15 +6,22 @@ use indexmap::{
 };
 use uuid::Uuid;
 
-use crate::utils::{rect::Rect, vector::Vector};
+use crate::utils::{geometry::Aabb, rect::Rect, scalar::scalar, vector::Vector, viewbox::ViewBox};
 
-use self::utils::{grid::Grid, simple_grid::SimpleGrid};
+use self::{
+  avoidance::CollisionMode,
+  utils::{grid::Grid, simple_grid::SimpleGrid},
+};
 
 use super::{
-  context::render::{Render, RenderFrame, Texture, ViewPort},
-  enity::track::EnityTrack,
+  context::render::{DrawKey, Render, RenderFrame, Texture, ViewPort},
+  enity::{animation::AnimationEvent, track::EnityTrack},
+  tilemap::Tilemap,
+  tween::{TweenEvent, TweenSequence, Tweener},
 };
 
+pub mod avoidance;
+pub mod snapshot;
 pub mod utils;
 
 pub trait Scene: Render {
@@ -33,6 +40,10 @@ pub struct NormalScene {
   background: Texture,
   viewport: ViewPort,
   grid: SimpleGrid,
+  tilemap: Option<Tilemap>,
+  collision_mode: CollisionMode,
+  tweener: Tweener,
+  animation_events: Vec<AnimationEvent>,
   size: Vector,
   uuid: Uuid,
   ui: UIs,
@@ -47,6 +58,10 @@ impl NormalScene {
       entities: IndexMap::new(),
       uuid: Uuid::new_v4(),
       grid: SimpleGrid::new(),
+      tilemap: None,
+      collision_mode: CollisionMode::default(),
+      tweener: Tweener::new(),
+      animation_events: vec![],
       ui: UIs::new(),
       size,
     }
@@ -56,6 +71,10 @@ impl NormalScene {
     self.uuid
   }
 
+  pub fn size(&self) -> Vector {
+    self.size
+  }
+
   pub fn replace_viewport(&mut self, viewport: ViewPort) -> ViewPort {
     mem::replace(&mut self.viewport, viewport)
   }
@@ -80,6 +99,10 @@ impl NormalScene {
     self.entities.get(uuid)
   }
 
+  pub fn entities(&self) -> &IndexMap<Uuid, EnityTrack> {
+    &self.entities
+  }
+
   pub fn get_ui(&mut self, uuid: &Uuid) -> Option<&EnityTrack> {
     self.ui.get(uuid)
   }
@@ -99,6 +122,40 @@ impl NormalScene {
     &mut self.grid
   }
 
+  pub fn tilemap(&self) -> Option<&Tilemap> {
+    self.tilemap.as_ref()
+  }
+  pub fn tilemap_mut(&mut self) -> Option<&mut Tilemap> {
+    self.tilemap.as_mut()
+  }
+  pub fn set_tilemap(&mut self, tilemap: Option<Tilemap>) -> Option<Tilemap> {
+    mem::replace(&mut self.tilemap, tilemap)
+  }
+
+  pub fn collision_mode(&self) -> CollisionMode {
+    self.collision_mode
+  }
+  pub fn set_collision_mode(&mut self, mode: CollisionMode) -> CollisionMode {
+    mem::replace(&mut self.collision_mode, mode)
+  }
+
+  /// start tween sequence on enity, return its id
+  pub fn tween(&mut self, enity: &EnityTrack, sequence: TweenSequence) -> u64 {
+    self.tweener.insert(enity, sequence)
+  }
+  pub fn cancel_tween(&mut self, id: u64) -> Option<TweenSequence> {
+    self.tweener.cancel(id)
+  }
+  /// completed tweens since last call
+  pub fn tween_events(&mut self) -> Vec<TweenEvent> {
+    self.tweener.drain_events()
+  }
+
+  /// animation events since last call
+  pub fn animation_events(&mut self) -> Vec<AnimationEvent> {
+    mem::take(&mut self.animation_events)
+  }
+
   pub fn background(&self) -> Texture {
     self.background.clone()
   }
@@ -123,21 +180,50 @@ impl NormalScene {
       tracks.insert(uuid, track.clone());
     }
 
-    for track in tracks.values() {
-      calc_collision(self, &track);
-    }
+    let agents = match &self.collision_mode {
+      CollisionMode::PushApart => {
+        for track in tracks.values() {
+          calc_collision(self, &track);
+        }
+        None
+      }
+      CollisionMode::Avoidance(config) => Some(avoidance::agents(self, &tracks, config)),
+    };
 
     for track in tracks.values() {
         // apply action
         let mut position = track.position(self.uuid);
         position.action(self.uuid, track.base().speed(), delta);
     }
+
+    if let (Some(agents), CollisionMode::Avoidance(config)) = (agents, &self.collision_mode) {
+      avoidance::avoid(self.uuid, &agents, config, delta);
+    }
+
+    if let Some(tilemap) = &self.tilemap {
+      for track in tracks.values() {
+        calc_tile_collision(self.uuid, tilemap, track);
+      }
+    }
     mem::swap(&mut self.entities, &mut tracks);
+    self.tweener.update(self.uuid, delta);
+
+    for track in self.entities.values() {
+      let events = track.view_mut().animate(delta);
+      self.animation_events.extend(events.into_iter().map(|(part, clip, kind)| {
+        AnimationEvent {
+          enity: track.uuid(),
+          part,
+          clip,
+          kind,
+        }
+      }));
+    }
 
     fn calc_collision(scene: &mut NormalScene, track: &EnityTrack) {
       // apply collision
       let collision = track.base().get_collision();
-      if collision != 0 {
+      if collision != 0 && !track.base().is_static() {
         let others = scene.grid.collision(scene.uuid, track.clone());
 
         for other in others {
@@ -152,10 +238,27 @@ impl NormalScene {
           let other_pos_vec = other.position(scene.uuid).get();
           
           let goto = other_pos_vec.to(pos_vec);
-          pos.offset(goto / goto.distance() * 2., 0.);
+          pos.offset(goto / goto.distance() * scalar(2.), scalar(0.));
         }
       }
     }
+
+    // 實體與實心瓦片碰撞, 將實體推出瓦片
+    fn calc_tile_collision(scene_uuid: Uuid, tilemap: &Tilemap, track: &EnityTrack) {
+      if track.base().get_collision() == 0 {
+        return;
+      }
+      let hitbox = track.hitbox(scene_uuid);
+      if hitbox.size == Vector::ORIGIN {
+        return;
+      }
+      let offset = tilemap.resolve(hitbox);
+      if offset != Vector::ORIGIN {
+        let mut position = track.position(scene_uuid);
+        let current = position.get();
+        position.set(current + offset);
+      }
+    }
   }
 
   pub fn collision(&self, enity: &EnityTrack) -> Vec<EnityTrack> {
@@ -166,11 +269,29 @@ impl NormalScene {
     self.grid.collision_by_rect(self.uuid, rect)
   }
 
+  /// entities without hitbox whose viewbox overlaps rect
+  pub fn view_by_rect(&self, rect: Rect) -> Vec<EnityTrack> {
+    self.grid.view_by_rect(self.uuid, rect)
+  }
+
   pub fn collision_by_point(&self, point: Vector) -> Vec<EnityTrack> {
     self
       .grid
       .collision_by_rect(self.uuid, Rect::new(point, Vector::ORIGIN))
   }
+
+  /// hitboxes of entities and solid tiles in scene space, for debug overlay
+  pub fn hitboxes(&self) -> Vec<Rect> {
+    let mut hitboxes = vec![];
+    if let Some(tilemap) = &self.tilemap {
+      let (max, min) = Rect::new(Vector::ORIGIN, self.size).maxmin();
+      hitboxes.extend(tilemap.solid_rects(Aabb::new(min, max)));
+    }
+    for track in self.entities.values() {
+      hitboxes.extend(track.hitbox_object(self.uuid));
+    }
+    hitboxes
+  }
 }
 
 impl Render for NormalScene {
@@ -179,16 +300,17 @@ impl Render for NormalScene {
       Rect::new(Vector::ORIGIN, self.size),
       self.background.clone(),
     );
-    frame.push(background);
+    frame.push_keyed(DrawKey::new(self.uuid, "background", 0), background);
 
-    for enity in self.entities.values() {
-      let enity_position = enity.viewbox_object(self.uuid);
-      frame.append(&enity_position);
+    if let Some(tilemap) = &self.tilemap {
+      let (max, min) = self.viewport.maxmin();
+      tilemap.render_visible(frame, Aabb::new(min, max));
     }
 
-    for enity in self.ui.values() {
-      let enity_position = enity.viewbox_object(self.uuid);
-      frame.append(&enity_position);
+    for enity in self.entities.values().chain(self.ui.values()) {
+      for (key, item) in enity.keyed_viewbox_object(self.uuid) {
+        frame.push_keyed(key, item);
+      }
     }
 
     frame.set_viewport(self.viewport)
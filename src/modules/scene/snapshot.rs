@@ -0,0 +1,136 @@
+use indexmap::IndexMap;
+use uuid::Uuid;
+
+use crate::{
+  modules::{
+    context::render::{Texture, ViewPort},
+    enity::{
+      animation::AnimationEvent, base::EnityBase, position::EnityPosition, track::EnityTrack,
+      view::EnityView,
+    },
+    tween::Tweener,
+  },
+  utils::vector::Vector,
+};
+
+use super::NormalScene;
+
+/// # 場景快照
+/// 保存場景內所有實體的狀態, 用於回滾
+/// 進行中的補間和未取出的動畫事件也一併保存
+#[derive(Debug, Clone)]
+pub struct SceneSnapshot {
+  entities: IndexMap<Uuid, EnityState>,
+  tweener: Tweener,
+  animation_events: Vec<AnimationEvent>,
+  background: Texture,
+  viewport: ViewPort,
+  size: Vector,
+}
+
+/// 單個實體在場景內的狀態
+#[derive(Debug, Clone)]
+pub struct EnityState {
+  pub track: EnityTrack,
+  pub base: EnityBase,
+  pub view: EnityView,
+  pub position: EnityPosition,
+}
+
+impl EnityState {
+  pub fn capture(scene_uuid: Uuid, track: &EnityTrack) -> Self {
+    EnityState {
+      track: track.clone(),
+      base: track.base().clone(),
+      view: track.view().clone(),
+      position: track.position(scene_uuid).clone(),
+    }
+  }
+
+  pub fn apply(&self, scene_uuid: Uuid) {
+    *self.track.base_mut() = self.base.clone();
+    *self.track.view_mut() = self.view.clone();
+    *self.track.position(scene_uuid) = self.position.clone();
+  }
+}
+
+impl SceneSnapshot {
+  pub fn entities(&self) -> &IndexMap<Uuid, EnityState> {
+    &self.entities
+  }
+}
+
+impl NormalScene {
+  /// 保存場景快照
+  pub fn snapshot(&self) -> SceneSnapshot {
+    let entities = self
+      .entities
+      .iter()
+      .map(|(uuid, track)| (*uuid, EnityState::capture(self.uuid, track)))
+      .collect();
+
+    SceneSnapshot {
+      entities,
+      tweener: self.tweener.clone(),
+      animation_events: self.animation_events.clone(),
+      background: self.background.clone(),
+      viewport: self.viewport,
+      size: self.size,
+    }
+  }
+
+  /// 還原場景快照
+  /// 快照後新增的實體會被移除, 被移除的實體會被加回
+  pub fn restore(&mut self, snapshot: &SceneSnapshot) {
+    let mut entities = IndexMap::with_capacity(snapshot.entities.len());
+    for (uuid, state) in snapshot.entities.iter() {
+      state.apply(self.uuid);
+      entities.insert(*uuid, state.track.clone());
+    }
+
+    self.entities = entities;
+    self.tweener = snapshot.tweener.clone();
+    self.animation_events = snapshot.animation_events.clone();
+    self.background = snapshot.background.clone();
+    self.viewport = snapshot.viewport;
+    self.size = snapshot.size;
+    self.grid.clear();
+  }
+}
+
+#[test]
+fn test() {
+  use crate::{
+    modules::tween::{easing::Easing, Tween, TweenSequence},
+    utils::{rect::Rect, scalar::scalar},
+  };
+
+  let mut scene = NormalScene::new(Vector::new(100., 100.));
+  let part = Rect::new(Vector::ORIGIN, Vector::new(10., 10.));
+  let view = EnityView::new(vec![(part, Texture::Color("#000000".to_string()))], vec![]);
+  let track = EnityTrack::new(
+    EnityBase::new("tween".to_string(), vec![], scalar(0.)),
+    view,
+  );
+  scene.insert(&track);
+  let sequence =
+    TweenSequence::new().then(Tween::position(Vector::new(100., 0.), 100, Easing::Linear));
+  let id = scene.tween(&track, sequence);
+
+  scene.update(20);
+  let snapshotted = track.position(scene.uuid()).get();
+  let snapshot = scene.snapshot();
+  scene.update(30);
+  let expected = track.position(scene.uuid()).get();
+  scene.update(100);
+  assert_eq!(scene.tween_events().len(), 1);
+
+  // tween resumes from snapshot instead of staying finished
+  scene.restore(&snapshot);
+  assert_eq!(track.position(scene.uuid()).get(), snapshotted);
+  scene.update(30);
+  assert_eq!(track.position(scene.uuid()).get(), expected);
+  scene.update(50);
+  assert_eq!(track.position(scene.uuid()).get(), Vector::new(100., 0.));
+  assert_eq!(scene.tween_events()[0].id, id);
+}
@@ -2,7 +2,7 @@ use std::{fmt::Debug, mem};
 
 use crate::{
   modules::enity::track::EnityTrack,
-  utils::{rect::Rect, vector::Vector, viewbox::ViewBox},
+  utils::{rect::Rect, scalar::{scalar, to_f32}, vector::Vector, viewbox::ViewBox},
 };
 use indexmap::{IndexMap, IndexSet};
 use uuid::Uuid;
@@ -50,13 +50,13 @@ impl Grid {
   }
   pub fn collision(&self, scene_uuid: Uuid, enity: EnityTrack) -> Vec<EnityTrack> {
     let [xs, ys] = Self::detection(enity.viewbox(scene_uuid));
-    let chunk = Rect::new(Vector::new(GRID_SIZE, GRID_SIZE) / 2., Vector::new(GRID_SIZE, GRID_SIZE));
+    let chunk = Rect::new(Vector::new(GRID_SIZE, GRID_SIZE) / scalar(2.), Vector::new(GRID_SIZE, GRID_SIZE));
     let mut collecter = IndexSet::with_capacity(xs.len() * ys.len() * 5);
 
     for x in xs.iter() {
       for y in ys.iter() {
         let mut chunk = chunk.clone();
-        chunk.position *= (Vector::new(*x as f32, *y as f32) + 0.5) * GRID_SIZE;
+        chunk.position *= Vector::new(*x as f32 + 0.5, *y as f32 + 0.5) * scalar(GRID_SIZE);
         collecter.extend(
           self
             .chunks
@@ -90,7 +90,7 @@ impl Grid {
     for x in xs.iter() {
       for y in ys.iter() {
         let mut chunk = chunk.clone();
-        chunk.position *= (Vector::new(*x as f32, *y as f32) + 0.5) * GRID_SIZE;
+        chunk.position *= Vector::new(*x as f32 + 0.5, *y as f32 + 0.5) * scalar(GRID_SIZE);
         collecter.extend(
           self
             .chunks
@@ -113,8 +113,8 @@ impl Grid {
   fn detection(rect: Rect) -> [Vec<isize>; 2] {
     let (max_pos, min_pos) = rect.maxmin();
     let result = [(max_pos.0, min_pos.0), (max_pos.1, min_pos.1)].map(|(max, min)| {
-      let max = (max / GRID_SIZE).floor() as isize;
-      let min = (min / GRID_SIZE).floor() as isize;
+      let max = to_f32(max / scalar(GRID_SIZE)).floor() as isize;
+      let min = to_f32(min / scalar(GRID_SIZE)).floor() as isize;
       (min..=max).collect::<Vec<isize>>()
     });
     result
@@ -204,12 +204,12 @@ impl GridChunk {
     ) -> Vec<EnityTrack> {
       let mut collecter = vec![];
       let mut parent_chunk = parent_chunk.clone();
-      parent_chunk.position -= parent_chunk.size / 2.;
+      parent_chunk.position -= parent_chunk.size / scalar(2.);
       parent_chunk.size = Vector::new(size, size);
       for (i, grid_chunk) in chunks.iter().enumerate() {
         let mut chunk_box = parent_chunk.clone();
         chunk_box.position +=
-          Vector::new((i % width) as f32 + 0.5, (i / width) as f32 + 0.5) * size;
+          Vector::new((i % width) as f32 + 0.5, (i / width) as f32 + 0.5) * scalar(size);
         let collision = chunk_box.collision(&viewbox);
 
         if collision {
@@ -284,10 +284,10 @@ impl GridChunk {
 
     // 無法處理跨區塊
     fn get_chunck_place(point: Vector, width: usize, size: f32) -> (usize, usize) {
-      let place = (point / size).floor();
+      let place = (point / scalar(size)).floor();
       let signs = place.signum().min(Vector::ORIGIN);
       let (x, y) = (place.abs() + signs).unpack();
-      (x as usize % width, y as usize % width)
+      (to_f32(x) as usize % width, to_f32(y) as usize % width)
     }
 
     fn get_points(scene_uuid: Uuid, enity: &EnityTrack) -> [Vector; 4] {
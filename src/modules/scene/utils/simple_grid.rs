@@ -2,7 +2,7 @@ use std::{fmt::Debug, mem};
 
 use crate::{
   modules::{context::control, enity::track::EnityTrack},
-  utils::{rect::Rect, vector::Vector, viewbox::ViewBox},
+  utils::{rect::Rect, scalar::{scalar, to_f32}, vector::Vector, viewbox::ViewBox},
 };
 use indexmap::{IndexMap, IndexSet};
 use uuid::Uuid;
@@ -12,21 +12,27 @@ static GRID_SIZE: f32 = 100.;
 #[derive(Debug, Clone)]
 pub struct SimpleGrid {
   chunks: IndexMap<(isize, isize), Vec<EnityTrack>>,
+  /// entities without hitbox, by viewbox
+  views: IndexMap<(isize, isize), Vec<EnityTrack>>,
 }
 
 impl SimpleGrid {
   pub fn new() -> Self {
     Self {
       chunks: IndexMap::new(),
+      views: IndexMap::new(),
     }
   }
   pub fn insert(&mut self, scene_uuid: Uuid, enity: &EnityTrack) {
-    let [xs, ys] = Self::detection(enity.hitbox(scene_uuid));
+    let (chunks, rect) = match enity.view().hitboxes().is_empty() {
+      true => (&mut self.views, enity.viewbox(scene_uuid)),
+      false => (&mut self.chunks, enity.hitbox(scene_uuid)),
+    };
+    let [xs, ys] = Self::detection(rect);
 
     for x in xs.iter() {
       for y in ys.iter() {
-        self
-          .chunks
+        chunks
           .entry((*x, *y))
           .or_default()
           .push(enity.clone());
@@ -34,13 +40,15 @@ impl SimpleGrid {
     }
   }
   pub fn clear(&mut self) {
-    let mut remove_chunks = vec![];
-    for (&idx, chunk) in self.chunks.iter_mut() {
-      if chunk.is_empty() {remove_chunks.push(idx)};
-      chunk.clear();
-    }
-    for idx in remove_chunks.iter() {
-      self.chunks.swap_remove(idx);
+    for chunks in [&mut self.chunks, &mut self.views] {
+      let mut remove_chunks = vec![];
+      for (&idx, chunk) in chunks.iter_mut() {
+        if chunk.is_empty() {remove_chunks.push(idx)};
+        chunk.clear();
+      }
+      for idx in remove_chunks.iter() {
+        chunks.swap_remove(idx);
+      }
     }
   }
 
@@ -94,11 +102,33 @@ impl SimpleGrid {
     collecter.into_iter().collect()
   }
 
+  /// entities without hitbox whose viewbox overlaps rect
+  pub fn view_by_rect(&self, scene_uuid: Uuid, rect: Rect) -> Vec<EnityTrack> {
+    let [xs, ys] = Self::detection(rect);
+    let mut collecter = IndexSet::new();
+
+    for x in xs.iter() {
+      for y in ys.iter() {
+        let Some(chunk) = self.views.get(&(*x, *y)) else {
+          continue;
+        };
+        collecter.extend(
+          chunk
+            .iter()
+            .filter(|other| ViewBox::collision(&other.viewbox(scene_uuid), &rect))
+            .cloned(),
+        );
+      }
+    }
+
+    collecter.into_iter().collect()
+  }
+
   fn detection(rect: Rect) -> [Vec<isize>; 2] {
     let (max_pos, min_pos) = rect.maxmin();
     let result = [(max_pos.0, min_pos.0), (max_pos.1, min_pos.1)].map(|(max, min)| {
-      let max = (max / GRID_SIZE).floor() as isize;
-      let min = (min / GRID_SIZE).floor() as isize;
+      let max = to_f32(max / scalar(GRID_SIZE)).floor() as isize;
+      let min = to_f32(min / scalar(GRID_SIZE)).floor() as isize;
       (min..=max).collect::<Vec<isize>>()
     });
     result
@@ -0,0 +1,379 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::utils::{
+  geometry::Aabb,
+  rect::Rect,
+  scalar::{scalar, to_f32},
+  vector::Vector,
+};
+
+use super::context::render::{DrawKey, RenderFrame, Texture};
+
+pub mod tiled;
+
+/// index in tileset
+pub type TileId = u32;
+
+///=========================================================================================
+/// Tileset
+///=========================================================================================
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tileset {
+  tiles: Vec<Tile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tile {
+  pub texture: Texture,
+  /// solid for scene collision
+  pub collision: bool,
+}
+
+impl Tile {
+  pub fn new(texture: Texture) -> Self {
+    Tile {
+      texture,
+      collision: false,
+    }
+  }
+  pub fn solid(texture: Texture) -> Self {
+    Tile {
+      texture,
+      collision: true,
+    }
+  }
+}
+
+impl Tileset {
+  pub fn new() -> Self {
+    Self::default()
+  }
+  pub fn push(&mut self, tile: Tile) -> TileId {
+    self.tiles.push(tile);
+    (self.tiles.len() - 1) as TileId
+  }
+  pub fn get(&self, id: TileId) -> Option<&Tile> {
+    self.tiles.get(id as usize)
+  }
+  pub fn get_mut(&mut self, id: TileId) -> Option<&mut Tile> {
+    self.tiles.get_mut(id as usize)
+  }
+  pub fn len(&self) -> usize {
+    self.tiles.len()
+  }
+  pub fn is_empty(&self) -> bool {
+    self.tiles.is_empty()
+  }
+}
+
+///=========================================================================================
+/// TileLayer
+///=========================================================================================
+/// cells are stored row by row, `(0, 0)` is left-bottom
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileLayer {
+  pub name: String,
+  pub visible: bool,
+  /// tiles in this layer join scene collision
+  pub collision: bool,
+  width: usize,
+  height: usize,
+  tiles: Vec<Option<TileId>>,
+}
+
+impl TileLayer {
+  pub fn new(name: String, width: usize, height: usize) -> Self {
+    TileLayer {
+      name,
+      visible: true,
+      collision: true,
+      width,
+      height,
+      tiles: vec![None; width * height],
+    }
+  }
+  pub fn get(&self, x: usize, y: usize) -> Option<TileId> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+    self.tiles[x + y * self.width]
+  }
+  pub fn set(&mut self, x: usize, y: usize, tile: Option<TileId>) -> Option<TileId> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+    std::mem::replace(&mut self.tiles[x + y * self.width], tile)
+  }
+  pub fn fill(&mut self, tile: Option<TileId>) {
+    self.tiles.fill(tile)
+  }
+}
+
+///=========================================================================================
+/// Tilemap
+///=========================================================================================
+/// # 瓦片地圖
+/// 以網格保存多層瓦片, 只繪製可見範圍, 並參與場景碰撞
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tilemap {
+  /// left-bottom of cell `(0, 0)`
+  position: Vector,
+  tile_size: Vector,
+  width: usize,
+  height: usize,
+  tileset: Tileset,
+  layers: Vec<TileLayer>,
+  /// keys draw items apart from other tilemaps
+  #[serde(default = "Uuid::new_v4")]
+  uuid: Uuid,
+}
+
+impl Tilemap {
+  pub fn new(position: Vector, tile_size: Vector, width: usize, height: usize) -> Self {
+    Tilemap {
+      position,
+      tile_size,
+      width,
+      height,
+      tileset: Tileset::new(),
+      layers: vec![],
+      uuid: Uuid::new_v4(),
+    }
+  }
+
+  pub fn uuid(&self) -> Uuid {
+    self.uuid
+  }
+
+  pub fn position(&self) -> Vector {
+    self.position
+  }
+  pub fn set_position(&mut self, position: Vector) {
+    self.position = position
+  }
+  pub fn tile_size(&self) -> Vector {
+    self.tile_size
+  }
+  /// (width, height) in cells
+  pub fn dimension(&self) -> (usize, usize) {
+    (self.width, self.height)
+  }
+  pub fn bounds(&self) -> Aabb {
+    let size = self.tile_size * Vector::new(self.width as f32, self.height as f32);
+    Aabb::new(self.position, self.position + size)
+  }
+
+  pub fn tileset(&self) -> &Tileset {
+    &self.tileset
+  }
+  pub fn tileset_mut(&mut self) -> &mut Tileset {
+    &mut self.tileset
+  }
+
+  //================================================================================
+  // Layer
+  //================================================================================
+  /// add layer on top
+  pub fn add_layer(&mut self, name: &str) -> &mut TileLayer {
+    self
+      .layers
+      .push(TileLayer::new(name.to_string(), self.width, self.height));
+    self.layers.last_mut().unwrap()
+  }
+  pub fn layers(&self) -> &Vec<TileLayer> {
+    &self.layers
+  }
+  pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+    self.layers.iter().find(|layer| layer.name == name)
+  }
+  pub fn layer_mut(&mut self, name: &str) -> Option<&mut TileLayer> {
+    self.layers.iter_mut().find(|layer| layer.name == name)
+  }
+  pub fn remove_layer(&mut self, name: &str) -> Option<TileLayer> {
+    let index = self.layers.iter().position(|layer| layer.name == name)?;
+    Some(self.layers.remove(index))
+  }
+
+  //================================================================================
+  // Cell
+  //================================================================================
+  /// cell containing point
+  pub fn cell_at(&self, point: Vector) -> Option<(usize, usize)> {
+    let (x, y) = self.cell_index(point);
+    if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+      return None;
+    }
+    Some((x as usize, y as usize))
+  }
+  pub fn cell_rect(&self, x: usize, y: usize) -> Rect {
+    let center = Vector::new(x as f32 + 0.5, y as f32 + 0.5) * self.tile_size + self.position;
+    Rect::new(center, self.tile_size)
+  }
+  /// any collision layer has solid tile at cell
+  pub fn is_solid(&self, x: usize, y: usize) -> bool {
+    self.layers.iter().any(|layer| {
+      layer.collision
+        && layer
+          .get(x, y)
+          .and_then(|id| self.tileset.get(id))
+          .is_some_and(|tile| tile.collision)
+    })
+  }
+  pub fn is_solid_at(&self, point: Vector) -> bool {
+    self
+      .cell_at(point)
+      .is_some_and(|(x, y)| self.is_solid(x, y))
+  }
+  /// solid cells overlapping area
+  pub fn solid_rects(&self, area: Aabb) -> Vec<Rect> {
+    let mut rects = vec![];
+    for (x, y) in self.cells_in(area) {
+      if self.is_solid(x, y) {
+        rects.push(self.cell_rect(x, y))
+      }
+    }
+    rects
+  }
+  /// any solid cell overlaps rect
+  pub fn collision(&self, rect: Rect) -> bool {
+    let area = rect.aabb();
+    self.cells_in(area).any(|(x, y)| {
+      self.is_solid(x, y) && crate::utils::hitbox::HitBox::collision(&self.cell_rect(x, y), &rect)
+    })
+  }
+
+  /// cell index, may be out of map
+  fn cell_index(&self, point: Vector) -> (isize, isize) {
+    let local = (point - self.position) / self.tile_size;
+    (
+      to_f32(local.0).floor() as isize,
+      to_f32(local.1).floor() as isize,
+    )
+  }
+
+  /// cells overlapping area (clamped in map)
+  fn cells_in(&self, area: Aabb) -> impl Iterator<Item = (usize, usize)> {
+    let (min_x, min_y) = self.cell_index(area.min);
+    let (max_x, max_y) = self.cell_index(area.max);
+    let clamp = |value: isize, max: usize| value.clamp(0, max as isize) as usize;
+    let (min_x, max_x) = (clamp(min_x, self.width), clamp(max_x + 1, self.width));
+    let (min_y, max_y) = (clamp(min_y, self.height), clamp(max_y + 1, self.height));
+    (min_y..max_y).flat_map(move |y| (min_x..max_x).map(move |x| (x, y)))
+  }
+
+  //================================================================================
+  // Collision resolve
+  //================================================================================
+  /// get offset to push rect out of solid tiles
+  /// 得到將矩形推出實心瓦片的位移
+  pub fn resolve(&self, rect: Rect) -> Vector {
+    let mut offset = Vector::ORIGIN;
+    let mut area = rect.aabb();
+    for tile in self.solid_rects(area) {
+      let tile = tile.aabb();
+      let Some(overlap) = area.intersection(&tile) else {
+        continue;
+      };
+      let size = overlap.size();
+      if size.0 <= scalar(0.) || size.1 <= scalar(0.) {
+        continue;
+      }
+      // push along axis of least penetration
+      let away = tile.center().to(area.center());
+      let push = if size.0 < size.1 {
+        Vector(size.0 * away.0.signum(), scalar(0.))
+      } else {
+        Vector(scalar(0.), size.1 * away.1.signum())
+      };
+      area = Aabb::new(area.min + push, area.max + push);
+      offset += push;
+    }
+    offset
+  }
+
+  //================================================================================
+  // Render
+  //================================================================================
+  /// draw only tiles inside view
+  /// 只繪製可見範圍內的瓦片
+  pub fn render_visible(&self, frame: &mut RenderFrame, view: Aabb) {
+    let cells: Vec<(usize, usize)> = self.cells_in(view).collect();
+    for (index, layer) in self.layers.iter().enumerate() {
+      if !layer.visible {
+        continue;
+      }
+      // layer names may repeat, keyed by tilemap, layer index and cell
+      let part = index.to_string();
+      for (x, y) in cells.iter() {
+        let Some(tile) = layer.get(*x, *y).and_then(|id| self.tileset.get(id)) else {
+          continue;
+        };
+        let key = DrawKey::new(self.uuid, &part, (y * self.width + x) as u32);
+        frame.push_keyed(key, (self.cell_rect(*x, *y), tile.texture.clone()));
+      }
+    }
+  }
+}
+
+#[test]
+fn test() {
+  use std::collections::HashSet;
+
+  use crate::modules::{
+    enity::{base::EnityBase, position::MoveEvent, track::EnityTrack, view::EnityView},
+    scene::NormalScene,
+  };
+
+  let mut tilemap = Tilemap::new(Vector::ORIGIN, Vector::new(10., 10.), 10, 10);
+  let floor = tilemap.tileset_mut().push(Tile::new(Texture::default()));
+  let wall = tilemap
+    .tileset_mut()
+    .push(Tile::solid(Texture::Color("#888888".to_string())));
+  let layer = tilemap.add_layer("ground");
+  layer.fill(Some(floor));
+  for y in 0..10 {
+    layer.set(5, y, Some(wall));
+  }
+
+  assert_eq!(tilemap.cell_at(Vector::new(55., 3.)), Some((5, 0)));
+  assert_eq!(tilemap.cell_at(Vector::new(-1., 3.)), None);
+  assert!(tilemap.is_solid_at(Vector::new(55., 3.)));
+  assert!(!tilemap.is_solid_at(Vector::new(45., 3.)));
+
+  // only visible tiles are drawn
+  let mut frame = RenderFrame::new();
+  tilemap.render_visible(&mut frame, Aabb::new(Vector::ORIGIN, Vector::new(15., 15.)));
+  assert_eq!(frame.get().len(), 4);
+
+  // same layer name and other tilemaps do not share keys
+  let mut copy = Tilemap::new(Vector::ORIGIN, Vector::new(10., 10.), 10, 10);
+  copy.tileset_mut().push(Tile::new(Texture::default()));
+  copy.add_layer("ground").fill(Some(floor));
+  copy.add_layer("ground").fill(Some(floor));
+  let mut frame = RenderFrame::new();
+  let cell = Aabb::new(Vector::ORIGIN, Vector::new(5., 5.));
+  tilemap.render_visible(&mut frame, cell);
+  copy.render_visible(&mut frame, cell);
+  let keys: HashSet<_> = frame.keys().iter().flatten().collect();
+  assert_eq!((frame.get().len(), keys.len()), (3, 3));
+
+  // walking into the wall stops at its edge
+  let mut scene = NormalScene::new(Vector::new(100., 100.));
+  scene.set_tilemap(Some(tilemap));
+  let base = EnityBase::new("player".to_string(), vec![], scalar(100.));
+  let view = EnityView::new(vec![], vec![Rect::new(Vector::ORIGIN, Vector::new(4., 4.))]);
+  let track = EnityTrack::new(base, view);
+  let mut position = track.position(scene.uuid());
+  position.set(Vector::new(20., 35.));
+  position.set_action(MoveEvent::Moving(Vector::new(1., 0.)));
+  drop(position);
+  scene.insert(&track);
+
+  for _ in 0..60 {
+    scene.update(16);
+  }
+  let position = track.position(scene.uuid()).get();
+  assert!((to_f32(position.0) - 48.).abs() < 0.01);
+  assert!((to_f32(position.1) - 35.).abs() < 0.01);
+}
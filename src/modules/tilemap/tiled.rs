@@ -0,0 +1,525 @@
+use std::{fmt, fs, path::Path};
+
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+  modules::{
+    context::render::{Region, Texture},
+    enity::{base::EnityBase, track::EnityTrack, view::EnityView},
+    scene::NormalScene,
+  },
+  utils::{rect::Rect, scalar::scalar, vector::Vector},
+};
+
+use super::{Tile, TileId, Tilemap};
+
+/// flip flags stored in high bits of gid
+const GID_MASK: u32 = 0x0FFF_FFFF;
+
+///=========================================================================================
+/// Error
+///=========================================================================================
+#[derive(Debug)]
+pub enum TiledError {
+  Io(std::io::Error),
+  Json(serde_json::Error),
+  /// feature of the format that is not supported
+  Unsupported(String),
+  /// content that does not match the format
+  Format(String),
+}
+
+impl fmt::Display for TiledError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TiledError::Io(error) => write!(f, "tiled io error: {error}"),
+      TiledError::Json(error) => write!(f, "tiled json error: {error}"),
+      TiledError::Unsupported(what) => write!(f, "tiled unsupported: {what}"),
+      TiledError::Format(what) => write!(f, "tiled format error: {what}"),
+    }
+  }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<std::io::Error> for TiledError {
+  fn from(error: std::io::Error) -> Self {
+    TiledError::Io(error)
+  }
+}
+
+impl From<serde_json::Error> for TiledError {
+  fn from(error: serde_json::Error) -> Self {
+    TiledError::Json(error)
+  }
+}
+
+///=========================================================================================
+/// Format
+///=========================================================================================
+/// # Tiled JSON 地圖
+/// 讀取 Tiled 的 JSON 格式, 建立瓦片圖層和物件實體
+/// Tiled 以左上為原點, y 向下; 場景以中心為原點, y 向上
+#[derive(Debug, Clone, Deserialize)]
+pub struct TiledMap {
+  pub width: usize,
+  pub height: usize,
+  #[serde(rename = "tilewidth")]
+  pub tile_width: f32,
+  #[serde(rename = "tileheight")]
+  pub tile_height: f32,
+  #[serde(rename = "backgroundcolor")]
+  pub background_color: Option<String>,
+  #[serde(default)]
+  pub layers: Vec<TiledLayer>,
+  #[serde(default)]
+  pub tilesets: Vec<TiledTileset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TiledLayer {
+  TileLayer {
+    name: String,
+    #[serde(default = "visible")]
+    visible: bool,
+    #[serde(default)]
+    data: Option<Value>,
+    encoding: Option<String>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+  },
+  ObjectGroup {
+    name: String,
+    #[serde(default = "visible")]
+    visible: bool,
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+  },
+  Group {
+    name: String,
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+  },
+  #[serde(other)]
+  Other,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TiledObject {
+  #[serde(default)]
+  pub name: String,
+  /// `type` before Tiled 1.9, `class` after
+  #[serde(default, alias = "class", rename = "type")]
+  pub class: String,
+  pub x: f32,
+  pub y: f32,
+  #[serde(default)]
+  pub width: f32,
+  #[serde(default)]
+  pub height: f32,
+  /// degrees clockwise
+  #[serde(default)]
+  pub rotation: f32,
+  #[serde(default = "visible")]
+  pub visible: bool,
+  pub gid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TiledTileset {
+  #[serde(rename = "firstgid")]
+  pub first_gid: u32,
+  /// external tileset file
+  pub source: Option<String>,
+  pub image: Option<String>,
+  #[serde(default, rename = "tilecount")]
+  pub tile_count: u32,
+  #[serde(default)]
+  pub columns: u32,
+  #[serde(default, rename = "tilewidth")]
+  pub tile_width: u32,
+  #[serde(default, rename = "tileheight")]
+  pub tile_height: u32,
+  #[serde(default)]
+  pub margin: u32,
+  #[serde(default)]
+  pub spacing: u32,
+  #[serde(default)]
+  pub tiles: Vec<TiledTile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TiledTile {
+  pub id: u32,
+  pub image: Option<String>,
+  #[serde(default)]
+  pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TiledProperty {
+  pub name: String,
+  pub value: Value,
+}
+
+fn visible() -> bool {
+  true
+}
+
+fn property_bool(properties: &[TiledProperty], name: &str) -> Option<bool> {
+  properties
+    .iter()
+    .find(|property| property.name == name)
+    .and_then(|property| property.value.as_bool())
+}
+
+///=========================================================================================
+/// Load
+///=========================================================================================
+impl TiledMap {
+  /// parse map, external tilesets are not resolved
+  pub fn parse(json: &str) -> Result<Self, TiledError> {
+    Ok(serde_json::from_str(json)?)
+  }
+
+  /// load map file, resolve external tilesets and image paths by map directory
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, TiledError> {
+    let path = path.as_ref();
+    let mut map = Self::parse(&fs::read_to_string(path)?)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    for tileset in map.tilesets.iter_mut() {
+      let mut base = base.to_path_buf();
+      if let Some(source) = tileset.source.take() {
+        let source = base.join(source);
+        let mut external: TiledTileset = serde_json::from_str(&fs::read_to_string(&source)?)?;
+        external.first_gid = tileset.first_gid;
+        *tileset = external;
+        base = source.parent().unwrap_or(Path::new("")).to_path_buf();
+      }
+      let resolve = |image: &mut Option<String>| {
+        if let Some(image) = image {
+          *image = base.join(&image).to_string_lossy().into_owned();
+        }
+      };
+      resolve(&mut tileset.image);
+      tileset
+        .tiles
+        .iter_mut()
+        .for_each(|tile| resolve(&mut tile.image));
+    }
+
+    Ok(map)
+  }
+
+  /// map size in pixels
+  pub fn size(&self) -> Vector {
+    Vector::new(
+      self.width as f32 * self.tile_width,
+      self.height as f32 * self.tile_height,
+    )
+  }
+
+  /// tiled pixel position to scene position
+  fn to_scene(&self, x: f32, y: f32) -> Vector {
+    let size = self.size();
+    Vector::new(x, -y) + Vector(-size.0 / scalar(2.), size.1 / scalar(2.))
+  }
+
+  //================================================================================
+  // Build
+  //================================================================================
+  /// build scene with tilemap, objects and background
+  /// 建立場景: 瓦片地圖, 物件實體和背景
+  pub fn build(&self) -> Result<NormalScene, TiledError> {
+    let mut scene = NormalScene::new(self.size());
+    if let Some(color) = &self.background_color {
+      scene.set_background(Texture::Color(css_color(color)));
+    }
+    scene.set_tilemap(Some(self.build_tilemap()?));
+    for track in self.build_entities(scene.uuid()) {
+      scene.insert(&track);
+    }
+    Ok(scene)
+  }
+
+  /// build tilemap, gid `n` becomes tile id `n - 1`
+  pub fn build_tilemap(&self) -> Result<Tilemap, TiledError> {
+    if self.width == 0 || self.height == 0 {
+      return Err(TiledError::Format(format!(
+        "map size {} x {}",
+        self.width, self.height
+      )));
+    }
+    let size = self.size();
+    let mut tilemap = Tilemap::new(
+      Vector(-size.0 / scalar(2.), -size.1 / scalar(2.)),
+      Vector::new(self.tile_width, self.tile_height),
+      self.width,
+      self.height,
+    );
+
+    let max_gid = self
+      .tilesets
+      .iter()
+      .map(|tileset| tileset.first_gid + tileset.tile_count)
+      .max()
+      .unwrap_or(1);
+    for gid in 1..max_gid {
+      tilemap.tileset_mut().push(self.tile(gid));
+    }
+
+    for (name, layer) in self.tile_layers() {
+      let TiledLayer::TileLayer {
+        visible,
+        data,
+        encoding,
+        properties,
+        ..
+      } = layer
+      else {
+        continue;
+      };
+      if encoding
+        .as_deref()
+        .is_some_and(|encoding| encoding != "csv")
+      {
+        return Err(TiledError::Unsupported(format!(
+          "layer data encoding in `{name}`"
+        )));
+      }
+      let Some(Value::Array(data)) = data else {
+        return Err(TiledError::Unsupported(format!(
+          "infinite map layer `{name}`"
+        )));
+      };
+      if data.len() != self.width * self.height {
+        return Err(TiledError::Format(format!(
+          "layer `{name}` has {} tiles, expect {}",
+          data.len(),
+          self.width * self.height
+        )));
+      }
+
+      let layer = tilemap.add_layer(&name);
+      layer.visible = *visible;
+      layer.collision = property_bool(properties, "collision").unwrap_or(true);
+      for (index, gid) in data.iter().enumerate() {
+        let gid = gid.as_u64().unwrap_or(0) as u32 & GID_MASK;
+        if gid == 0 {
+          continue;
+        }
+        let (x, row) = (index % self.width, index / self.width);
+        layer.set(x, self.height - 1 - row, Some((gid - 1) as TileId));
+      }
+    }
+
+    Ok(tilemap)
+  }
+
+  /// create entities from object layers
+  /// 由物件圖層創建實體
+  pub fn build_entities(&self, scene_uuid: Uuid) -> Vec<EnityTrack> {
+    let mut tracks = vec![];
+    for object in self.objects() {
+      let mut groups = vec![];
+      if !object.class.is_empty() {
+        groups.push(object.class.clone());
+      }
+      let base = EnityBase::new(object.name.clone(), groups, scalar(0.));
+
+      let size = Vector::new(object.width, object.height);
+      let rect = Rect::new(Vector::ORIGIN, size);
+      let viewbox = match object.gid {
+        Some(gid) if object.visible => vec![(rect, self.tile(gid & GID_MASK).texture)],
+        _ => vec![],
+      };
+      let track = EnityTrack::new(base, EnityView::new(viewbox, vec![rect]));
+
+      // tiled rotates around top-left, or bottom-left for tile objects
+      let (sin, cos) = object.rotation.to_radians().sin_cos();
+      let (dx, dy) = match object.gid {
+        Some(_) => (object.width / 2., -object.height / 2.),
+        None => (object.width / 2., object.height / 2.),
+      };
+      let center = self.to_scene(
+        object.x + dx * cos - dy * sin,
+        object.y + dx * sin + dy * cos,
+      );
+
+      let mut position = track.position(scene_uuid);
+      position.set(center);
+      position.set_angle(scalar(-object.rotation.to_radians()));
+      drop(position);
+      tracks.push(track);
+    }
+    tracks
+  }
+
+  fn tile(&self, gid: u32) -> Tile {
+    let Some(tileset) = self
+      .tilesets
+      .iter()
+      .filter(|tileset| tileset.first_gid <= gid)
+      .max_by_key(|tileset| tileset.first_gid)
+    else {
+      return Tile::new(Texture::default());
+    };
+    let id = gid - tileset.first_gid;
+    let tile = tileset.tiles.iter().find(|tile| tile.id == id);
+    // image collection tileset has one image per tile, otherwise cut from sheet
+    let texture = match (tile.and_then(|tile| tile.image.clone()), &tileset.image) {
+      (Some(image), _) => Texture::Bitmap(image),
+      (None, Some(image)) if tileset.columns > 0 => {
+        let (column, row) = (id % tileset.columns, id / tileset.columns);
+        Texture::region(
+          image,
+          Region::new(
+            tileset.margin + column * (tileset.tile_width + tileset.spacing),
+            tileset.margin + row * (tileset.tile_height + tileset.spacing),
+            tileset.tile_width,
+            tileset.tile_height,
+          ),
+        )
+      }
+      (None, Some(image)) => Texture::Bitmap(image.clone()),
+      (None, None) => Texture::default(),
+    };
+    let collision = tile
+      .and_then(|tile| property_bool(&tile.properties, "collision"))
+      .unwrap_or(false);
+    Tile { texture, collision }
+  }
+
+  /// tile layers with group path name
+  fn tile_layers(&self) -> Vec<(String, &TiledLayer)> {
+    fn walk<'a>(layers: &'a [TiledLayer], prefix: &str, out: &mut Vec<(String, &'a TiledLayer)>) {
+      for layer in layers {
+        match layer {
+          TiledLayer::TileLayer { name, .. } => out.push((format!("{prefix}{name}"), layer)),
+          TiledLayer::Group { name, layers } => walk(layers, &format!("{prefix}{name}/"), out),
+          _ => {}
+        }
+      }
+    }
+    let mut out = vec![];
+    walk(&self.layers, "", &mut out);
+    out
+  }
+
+  fn objects(&self) -> Vec<&TiledObject> {
+    fn walk<'a>(layers: &'a [TiledLayer], out: &mut Vec<&'a TiledObject>) {
+      for layer in layers {
+        match layer {
+          TiledLayer::ObjectGroup {
+            visible, objects, ..
+          } if *visible => out.extend(objects.iter()),
+          TiledLayer::Group { layers, .. } => walk(layers, out),
+          _ => {}
+        }
+      }
+    }
+    let mut out = vec![];
+    walk(&self.layers, &mut out);
+    out
+  }
+}
+
+/// `#AARRGGBB` (tiled) to `#RRGGBBAA`
+fn css_color(color: &str) -> String {
+  let hex = color.trim_start_matches('#');
+  match hex.len() {
+    8 => format!("#{}{}", &hex[2..], &hex[..2]),
+    _ => format!("#{hex}"),
+  }
+}
+
+impl NormalScene {
+  /// load scene from tiled json map
+  /// 由 Tiled JSON 地圖載入場景
+  pub fn from_tiled(path: impl AsRef<Path>) -> Result<NormalScene, TiledError> {
+    TiledMap::load(path)?.build()
+  }
+}
+
+#[test]
+fn test() {
+  use crate::utils::scalar::to_f32;
+
+  let json = r##"{
+    "width": 4, "height": 2, "tilewidth": 16, "tileheight": 16,
+    "backgroundcolor": "#80112233",
+    "tilesets": [{
+      "firstgid": 1, "image": "tiles.png", "tilecount": 2, "columns": 2,
+      "tilewidth": 16, "tileheight": 16, "margin": 1, "spacing": 2,
+      "tiles": [{ "id": 1, "properties": [{ "name": "collision", "type": "bool", "value": true }] }]
+    }],
+    "layers": [
+      { "type": "tilelayer", "name": "ground", "width": 4, "height": 2, "visible": true,
+        "data": [2, 0, 0, 0, 1, 1, 1, 2147483650] },
+      { "type": "objectgroup", "name": "spawn", "visible": true, "objects": [
+        { "name": "player", "type": "hero", "x": 16, "y": 0, "width": 16, "height": 16, "rotation": 0 },
+        { "name": "crate", "class": "prop", "x": 32, "y": 32, "width": 16, "height": 16, "rotation": 90, "gid": 1 }
+      ] },
+      { "type": "imagelayer", "name": "sky" }
+    ]
+  }"##;
+
+  let map = TiledMap::parse(json).unwrap();
+  let scene = map.build().unwrap();
+  assert_eq!(scene.background(), Texture::Color("#11223380".to_string()));
+
+  let tilemap = scene.tilemap().unwrap();
+  let ground = tilemap.layer("ground").unwrap();
+  // first row in tiled is top row in scene
+  assert_eq!(ground.get(0, 1), Some(1));
+  assert_eq!(ground.get(1, 1), None);
+  // flip flags are ignored
+  assert_eq!(ground.get(3, 0), Some(1));
+  assert!(tilemap.is_solid(0, 1));
+  assert!(!tilemap.is_solid(0, 0));
+  // second tile is cut from sheet after margin and spacing
+  assert_eq!(
+    tilemap.tileset().get(1).unwrap().texture,
+    Texture::region("tiles.png", Region::new(19, 1, 16, 16))
+  );
+
+  let entities: Vec<_> = scene.entities().values().collect();
+  assert_eq!(entities.len(), 2);
+  let player = entities
+    .iter()
+    .find(|track| track.base().name() == "player")
+    .unwrap();
+  assert!(player.base().has_group("hero"));
+  let position = player.position(scene.uuid()).get();
+  assert_eq!((to_f32(position.0), to_f32(position.1)), (-8., 8.));
+
+  // tile object rotates around bottom-left
+  let crate_ = entities
+    .iter()
+    .find(|track| track.base().name() == "crate")
+    .unwrap();
+  assert!(crate_.base().has_group("prop"));
+  let position = crate_.position(scene.uuid()).get();
+  assert!((to_f32(position.0) - 8.).abs() < 0.01);
+  assert!((to_f32(position.1) - -24.).abs() < 0.01);
+
+  // malformed layers are errors instead of panics
+  let layer = |width: usize, data: &str| {
+    let json = format!(
+      r#"{{ "width": {width}, "height": 2, "tilewidth": 16, "tileheight": 16,
+        "layers": [{{ "type": "tilelayer", "name": "ground", "visible": true, "data": [{data}] }}] }}"#
+    );
+    TiledMap::parse(&json).unwrap().build_tilemap()
+  };
+  assert!(layer(2, "1, 0, 0, 1").is_ok());
+  for (width, data) in [(2, "1, 0, 0"), (2, "1, 0, 0, 0, 1"), (0, "")] {
+    assert!(
+      matches!(layer(width, data), Err(TiledError::Format(_))),
+      "{width}: {data}"
+    );
+  }
+}
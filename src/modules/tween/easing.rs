@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::scalar::{scalar, to_f32, Scalar};
+
+const PI: Scalar = scalar(std::f32::consts::PI);
+const BACK: Scalar = scalar(1.70158);
+
+/// # 緩動函數
+/// 只使用 Scalar 運算, 在 `fixed` 下結果一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Easing {
+  #[default]
+  Linear,
+  QuadIn,
+  QuadOut,
+  QuadInOut,
+  CubicIn,
+  CubicOut,
+  CubicInOut,
+  ElasticIn,
+  ElasticOut,
+  ElasticInOut,
+  BounceIn,
+  BounceOut,
+  BounceInOut,
+  BackIn,
+  BackOut,
+  BackInOut,
+}
+
+impl Easing {
+  /// map progress `0.0 ~ 1.0` to eased progress
+  /// 將進度映射為緩動後的進度
+  pub fn apply(self, t: Scalar) -> Scalar {
+    let (zero, one, two) = (scalar(0.), scalar(1.), scalar(2.));
+    let t = t.clamp(zero, one);
+    let half = scalar(0.5);
+    match self {
+      Easing::Linear => t,
+      Easing::QuadIn => t * t,
+      Easing::QuadOut => one - (one - t) * (one - t),
+      Easing::QuadInOut => match t < half {
+        true => two * t * t,
+        false => one - (two - two * t).powi(2) / two,
+      },
+      Easing::CubicIn => t.powi(3),
+      Easing::CubicOut => one - (one - t).powi(3),
+      Easing::CubicInOut => match t < half {
+        true => scalar(4.) * t.powi(3),
+        false => one - (two - two * t).powi(3) / two,
+      },
+      Easing::ElasticIn | Easing::ElasticOut | Easing::ElasticInOut if t <= zero => zero,
+      Easing::ElasticIn | Easing::ElasticOut | Easing::ElasticInOut if t >= one => one,
+      Easing::ElasticIn => {
+        let c4 = two * PI / scalar(3.);
+        -exp2(scalar(10.) * t - scalar(10.)) * ((scalar(10.) * t - scalar(10.75)) * c4).sin()
+      }
+      Easing::ElasticOut => {
+        let c4 = two * PI / scalar(3.);
+        exp2(scalar(-10.) * t) * ((scalar(10.) * t - scalar(0.75)) * c4).sin() + one
+      }
+      Easing::ElasticInOut => {
+        let c5 = two * PI / scalar(4.5);
+        let wave = ((scalar(20.) * t - scalar(11.125)) * c5).sin();
+        match t < half {
+          true => -(exp2(scalar(20.) * t - scalar(10.)) * wave) / two,
+          false => exp2(scalar(-20.) * t + scalar(10.)) * wave / two + one,
+        }
+      }
+      Easing::BounceIn => one - bounce_out(one - t),
+      Easing::BounceOut => bounce_out(t),
+      Easing::BounceInOut => match t < half {
+        true => (one - bounce_out(one - two * t)) / two,
+        false => (one + bounce_out(two * t - one)) / two,
+      },
+      Easing::BackIn => (BACK + one) * t.powi(3) - BACK * t * t,
+      Easing::BackOut => one + (BACK + one) * (t - one).powi(3) + BACK * (t - one).powi(2),
+      Easing::BackInOut => {
+        let c2 = BACK * scalar(1.525);
+        match t < half {
+          true => (two * t).powi(2) * ((c2 + one) * two * t - c2) / two,
+          false => ((two * t - two).powi(2) * ((c2 + one) * (t * two - two) + c2) + two) / two,
+        }
+      }
+    }
+  }
+}
+
+fn bounce_out(t: Scalar) -> Scalar {
+  let n1 = scalar(7.5625);
+  let d1 = scalar(2.75);
+  if t < scalar(1.) / d1 {
+    n1 * t * t
+  } else if t < scalar(2.) / d1 {
+    let t = t - scalar(1.5) / d1;
+    n1 * t * t + scalar(0.75)
+  } else if t < scalar(2.5) / d1 {
+    let t = t - scalar(2.25) / d1;
+    n1 * t * t + scalar(0.9375)
+  } else {
+    let t = t - scalar(2.625) / d1;
+    n1 * t * t + scalar(0.984375)
+  }
+}
+
+/// `2^x` with polynomial fraction, enough for easing
+fn exp2(x: Scalar) -> Scalar {
+  let whole = x.floor();
+  let fraction = x - whole;
+  let polynomial = scalar(1.)
+    + fraction
+      * (scalar(0.6960656) + fraction * (scalar(0.2244943) + fraction * scalar(0.0794402)));
+  scalar(2.).powi(to_f32(whole) as i32) * polynomial
+}
@@ -0,0 +1,426 @@
+use std::mem;
+
+use indexmap::IndexMap;
+use uuid::Uuid;
+
+use crate::utils::{
+  rect::Rect,
+  scalar::{scalar, to_f32, Scalar},
+  vector::Vector,
+};
+
+use self::easing::Easing;
+
+use super::{context::render::Texture, enity::track::EnityTrack};
+
+pub mod easing;
+
+///=========================================================================================
+/// Tween
+///=========================================================================================
+/// animated property and its end value
+#[derive(Debug, Clone, PartialEq)]
+pub enum TweenProperty {
+  Position(Vector),
+  Angle(Scalar),
+  /// rect of view part item
+  PartRect {
+    part: String,
+    index: usize,
+    rect: Rect,
+  },
+  /// color texture of view part item
+  PartColor {
+    part: String,
+    index: usize,
+    color: String,
+  },
+  /// wait, change nothing
+  Delay,
+}
+
+/// start value, captured when tween starts
+#[derive(Debug, Clone, PartialEq)]
+enum TweenValue {
+  Vector(Vector),
+  Scalar(Scalar),
+  Rect(Rect),
+  Color([f32; 4]),
+  None,
+}
+
+/// # 補間
+/// 在一段時間 (毫秒) 內以緩動函數將屬性變化至目標值
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tween {
+  pub property: TweenProperty,
+  /// millisecond
+  pub duration: usize,
+  pub easing: Easing,
+  from: Option<TweenValue>,
+}
+
+impl Tween {
+  pub fn new(property: TweenProperty, duration: usize, easing: Easing) -> Self {
+    Tween {
+      property,
+      duration,
+      easing,
+      from: None,
+    }
+  }
+  pub fn position(to: Vector, duration: usize, easing: Easing) -> Self {
+    Self::new(TweenProperty::Position(to), duration, easing)
+  }
+  pub fn angle(to: Scalar, duration: usize, easing: Easing) -> Self {
+    Self::new(TweenProperty::Angle(to), duration, easing)
+  }
+  pub fn part_rect(part: &str, index: usize, rect: Rect, duration: usize, easing: Easing) -> Self {
+    let part = part.to_string();
+    Self::new(
+      TweenProperty::PartRect { part, index, rect },
+      duration,
+      easing,
+    )
+  }
+  pub fn part_color(
+    part: &str,
+    index: usize,
+    color: &str,
+    duration: usize,
+    easing: Easing,
+  ) -> Self {
+    let (part, color) = (part.to_string(), color.to_string());
+    Self::new(
+      TweenProperty::PartColor { part, index, color },
+      duration,
+      easing,
+    )
+  }
+  pub fn delay(duration: usize) -> Self {
+    Self::new(TweenProperty::Delay, duration, Easing::Linear)
+  }
+
+  fn capture(&self, track: &EnityTrack, scene_uuid: Uuid) -> TweenValue {
+    match &self.property {
+      TweenProperty::Position(_) => TweenValue::Vector(track.position(scene_uuid).get()),
+      TweenProperty::Angle(_) => TweenValue::Scalar(track.position(scene_uuid).get_angle()),
+      TweenProperty::PartRect { part, index, .. } => {
+        let view = track.view();
+        match view.part(part).and_then(|part| part.get(*index)) {
+          Some((rect, _)) => TweenValue::Rect(*rect),
+          None => TweenValue::None,
+        }
+      }
+      TweenProperty::PartColor { part, index, .. } => {
+        let view = track.view();
+        match view.part(part).and_then(|part| part.get(*index)) {
+          Some((_, Texture::Color(color))) => {
+            parse_color(color).map_or(TweenValue::None, TweenValue::Color)
+          }
+          _ => TweenValue::None,
+        }
+      }
+      TweenProperty::Delay => TweenValue::None,
+    }
+  }
+
+  /// apply eased progress
+  fn apply(&self, track: &EnityTrack, scene_uuid: Uuid, progress: Scalar) {
+    let lerp = |from: Scalar, to: Scalar| from + (to - from) * progress;
+    let lerp_vector = |from: Vector, to: Vector| from + (to - from) * progress;
+    match (
+      &self.property,
+      self.from.as_ref().unwrap_or(&TweenValue::None),
+    ) {
+      (TweenProperty::Position(to), TweenValue::Vector(from)) => {
+        track.position(scene_uuid).set(lerp_vector(*from, *to));
+      }
+      (TweenProperty::Angle(to), TweenValue::Scalar(from)) => {
+        track.position(scene_uuid).set_angle(lerp(*from, *to));
+      }
+      (TweenProperty::PartRect { part, index, rect }, TweenValue::Rect(from)) => {
+        let mut view = track.view_mut();
+        if let Some((current, _)) = view.part_mut(part).and_then(|part| part.get_mut(*index)) {
+          *current = Rect::new_with_angle(
+            lerp_vector(from.position, rect.position),
+            lerp_vector(from.size, rect.size),
+            lerp(from.angle, rect.angle),
+          );
+        }
+      }
+      (TweenProperty::PartColor { part, index, color }, from) => {
+        let mut view = track.view_mut();
+        let Some((_, texture)) = view.part_mut(part).and_then(|part| part.get_mut(*index)) else {
+          return;
+        };
+        let (Some(to), TweenValue::Color(from)) = (parse_color(color), from) else {
+          *texture = Texture::Color(color.clone());
+          return;
+        };
+        let progress = to_f32(progress);
+        let mut mixed = [0.; 4];
+        for channel in 0..4 {
+          mixed[channel] = from[channel] + (to[channel] - from[channel]) * progress;
+        }
+        *texture = Texture::Color(format_color(mixed));
+      }
+      _ => {}
+    }
+  }
+}
+
+/// `#rrggbb` or `#rrggbbaa`
+fn parse_color(color: &str) -> Option<[f32; 4]> {
+  let hex = color.strip_prefix('#')?;
+  if hex.len() != 6 && hex.len() != 8 {
+    return None;
+  }
+  let mut channels = [255.; 4];
+  for (index, channel) in channels.iter_mut().enumerate().take(hex.len() / 2) {
+    *channel = u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok()? as f32;
+  }
+  Some(channels)
+}
+
+fn format_color(color: [f32; 4]) -> String {
+  let [r, g, b, a] = color.map(|channel| channel.round().clamp(0., 255.) as u8);
+  match a {
+    255 => format!("#{r:02x}{g:02x}{b:02x}"),
+    _ => format!("#{r:02x}{g:02x}{b:02x}{a:02x}"),
+  }
+}
+
+///=========================================================================================
+/// TweenSequence
+///=========================================================================================
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Repeat {
+  #[default]
+  Once,
+  Times(u32),
+  Forever,
+}
+
+/// # 補間序列
+/// 依序播放補間, 可重複播放和來回播放 (yoyo)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TweenSequence {
+  tweens: Vec<Tween>,
+  repeat: Repeat,
+  yoyo: bool,
+  index: usize,
+  elapsed: usize,
+  reversed: bool,
+  cycle: u32,
+}
+
+impl TweenSequence {
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// play tween after previous
+  pub fn then(mut self, tween: Tween) -> Self {
+    self.tweens.push(tween);
+    self
+  }
+  pub fn delay(self, duration: usize) -> Self {
+    self.then(Tween::delay(duration))
+  }
+  pub fn repeat(mut self, repeat: Repeat) -> Self {
+    self.repeat = repeat;
+    self
+  }
+  /// play backward after forward in every cycle
+  pub fn yoyo(mut self, yoyo: bool) -> Self {
+    self.yoyo = yoyo;
+    self
+  }
+
+  /// advance by millisecond, return `true` when finished
+  /// 推進補間, 完成時回傳 `true`
+  pub fn advance(&mut self, track: &EnityTrack, scene_uuid: Uuid, delta: usize) -> bool {
+    if self.tweens.iter().all(|tween| tween.duration == 0) {
+      for tween in self.tweens.iter_mut() {
+        tween.from = Some(tween.capture(track, scene_uuid));
+        tween.apply(track, scene_uuid, scalar(1.));
+      }
+      return true;
+    }
+
+    let mut left = delta;
+    loop {
+      let reversed = self.reversed;
+      let tween = &mut self.tweens[self.index];
+      if tween.from.is_none() {
+        tween.from = Some(tween.capture(track, scene_uuid));
+      }
+
+      let remain = tween.duration - self.elapsed;
+      if left < remain {
+        self.elapsed += left;
+        let progress = scalar(self.elapsed as f32 / tween.duration as f32);
+        let progress = match reversed {
+          false => tween.easing.apply(progress),
+          true => tween.easing.apply(scalar(1.) - progress),
+        };
+        tween.apply(track, scene_uuid, progress);
+        return false;
+      }
+
+      left -= remain;
+      self.elapsed = 0;
+      tween.apply(track, scene_uuid, scalar(if reversed { 0. } else { 1. }));
+
+      match (reversed, self.yoyo) {
+        (false, _) if self.index + 1 < self.tweens.len() => self.index += 1,
+        (false, true) => self.reversed = true,
+        (true, _) if self.index > 0 => self.index -= 1,
+        _ => {
+          self.cycle += 1;
+          let finished = match self.repeat {
+            Repeat::Once => true,
+            Repeat::Times(times) => self.cycle >= times,
+            Repeat::Forever => false,
+          };
+          if finished {
+            return true;
+          }
+          self.index = 0;
+          self.reversed = false;
+        }
+      }
+    }
+  }
+}
+
+///=========================================================================================
+/// Tweener
+///=========================================================================================
+/// completion of a tween sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TweenEvent {
+  pub id: u64,
+  pub enity: Uuid,
+}
+
+/// 場景內所有進行中的補間
+#[derive(Debug, Clone, Default)]
+pub struct Tweener {
+  next: u64,
+  active: IndexMap<u64, (EnityTrack, TweenSequence)>,
+  events: Vec<TweenEvent>,
+}
+
+impl Tweener {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, track: &EnityTrack, sequence: TweenSequence) -> u64 {
+    self.next += 1;
+    self.active.insert(self.next, (track.clone(), sequence));
+    self.next
+  }
+  pub fn cancel(&mut self, id: u64) -> Option<TweenSequence> {
+    self.active.shift_remove(&id).map(|(_, sequence)| sequence)
+  }
+  pub fn is_active(&self, id: u64) -> bool {
+    self.active.contains_key(&id)
+  }
+
+  pub fn update(&mut self, scene_uuid: Uuid, delta: usize) {
+    let events = &mut self.events;
+    self.active.retain(|id, (track, sequence)| {
+      if track.base().is_destroy() {
+        return false;
+      }
+      let finished = sequence.advance(track, scene_uuid, delta);
+      if finished {
+        events.push(TweenEvent {
+          id: *id,
+          enity: track.uuid(),
+        });
+      }
+      !finished
+    });
+  }
+
+  /// take completion events since last call
+  pub fn drain_events(&mut self) -> Vec<TweenEvent> {
+    mem::take(&mut self.events)
+  }
+}
+
+#[test]
+fn test() {
+  use crate::modules::{
+    enity::{base::EnityBase, view::EnityView},
+    scene::NormalScene,
+  };
+
+  for easing in [
+    Easing::Linear,
+    Easing::QuadInOut,
+    Easing::CubicOut,
+    Easing::ElasticIn,
+    Easing::ElasticOut,
+    Easing::ElasticInOut,
+    Easing::BounceIn,
+    Easing::BounceInOut,
+    Easing::BackIn,
+    Easing::BackInOut,
+  ] {
+    assert!(to_f32(easing.apply(scalar(0.))).abs() < 0.01, "{easing:?}");
+    assert!(
+      (to_f32(easing.apply(scalar(1.))) - 1.).abs() < 0.01,
+      "{easing:?}"
+    );
+  }
+  // back overshoots below zero
+  assert!(Easing::BackIn.apply(scalar(0.2)) < scalar(0.));
+
+  let mut scene = NormalScene::new(Vector::new(100., 100.));
+  let part = Rect::new(Vector::ORIGIN, Vector::new(10., 10.));
+  let view = EnityView::new(vec![(part, Texture::Color("#000000".to_string()))], vec![]);
+  let track = EnityTrack::new(
+    EnityBase::new("tween".to_string(), vec![], scalar(0.)),
+    view,
+  );
+  scene.insert(&track);
+
+  let sequence = TweenSequence::new()
+    .then(Tween::position(Vector::new(100., 0.), 100, Easing::Linear))
+    .delay(50)
+    .then(Tween::part_color("base", 0, "#ff0000", 100, Easing::Linear))
+    .yoyo(true)
+    .repeat(Repeat::Times(2));
+  let id = scene.tween(&track, sequence);
+
+  scene.update(50);
+  assert_eq!(track.position(scene.uuid()).get(), Vector::new(50., 0.));
+  scene.update(100);
+  scene.update(50);
+  assert_eq!(
+    track.view().part("base").unwrap()[0].1,
+    Texture::Color("#800000".to_string())
+  );
+  // forward 250 + backward 250 is one cycle
+  scene.update(300);
+  assert_eq!(track.position(scene.uuid()).get(), Vector::ORIGIN);
+  assert!(scene.tween_events().is_empty());
+
+  scene.update(500);
+  assert_eq!(
+    track.view().part("base").unwrap()[0].1,
+    Texture::Color("#000000".to_string())
+  );
+  assert_eq!(
+    scene.tween_events(),
+    vec![TweenEvent {
+      id,
+      enity: track.uuid()
+    }]
+  );
+  assert!(scene.tween_events().is_empty());
+}
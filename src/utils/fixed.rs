@@ -0,0 +1,381 @@
+use std::{
+  cmp::Ordering,
+  fmt::{self, Debug, Display},
+  iter::Sum,
+  ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign},
+};
+
+use serde::{Deserialize, Serialize};
+
+const FRAC_BITS: u32 = 16;
+const ONE_RAW: i64 = 1 << FRAC_BITS;
+
+/// cordic works in 32 fractional bits
+/// cordic 使用 32 位小數
+const CORDIC_SHIFT: u32 = 32 - FRAC_BITS;
+/// `atan(2^-i)`
+const CORDIC_ATAN: [i128; 32] = [
+  3373259426, 1991351318, 1052175346, 534100635, 268086748, 134174063, 67103403, 33553749,
+  16777131, 8388597, 4194303, 2097152, 1048576, 524288, 262144, 131072, 65536, 32768, 16384, 8192,
+  4096, 2048, 1024, 512, 256, 128, 64, 32, 16, 8, 4, 2,
+];
+/// `prod(1 / sqrt(1 + 2^-2i))`
+const CORDIC_GAIN: i128 = 2608131496;
+const CORDIC_PI: i128 = 13493037705;
+
+/// 48.16 fixed-point number
+/// 48.16 定點數
+///
+/// every operation is done by integer arithmetic,
+/// so the result is bit-identical on every machine and build.
+/// 所有運算皆以整數完成, 在任何機器與編譯下結果都相同
+///
+/// overflow is saturated, division by zero gives `MAX` or `MIN`
+/// 溢位時飽和, 除以零得到 `MAX` 或 `MIN`
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Fixed(i64);
+
+impl Fixed {
+  pub const ZERO: Fixed = Fixed(0);
+  pub const ONE: Fixed = Fixed(ONE_RAW);
+  /// smallest positive value
+  /// 最小正值
+  pub const EPSILON: Fixed = Fixed(1);
+  pub const MAX: Fixed = Fixed(i64::MAX);
+  pub const MIN: Fixed = Fixed(i64::MIN + 1);
+  /// invalid value, only used as a marker
+  /// 無效值, 僅作為標記
+  ///
+  /// unlike f32 NaN it is `i64::MIN`: it equals itself, sorts below every value
+  /// and is not kept by arithmetic, so check it with `is_nan`
+  /// 與 f32 NaN 不同, 它等於自身, 排序小於所有值, 運算後不會保持, 請用 `is_nan` 檢查
+  pub const NAN: Fixed = Fixed(i64::MIN);
+  pub const PI: Fixed = Fixed(205887);
+
+  pub const fn from_raw(raw: i64) -> Fixed {
+    Fixed(raw)
+  }
+  pub const fn raw(self) -> i64 {
+    self.0
+  }
+  /// convert from f32, rounding to nearest
+  /// 從 f32 轉換 (四捨五入)
+  pub const fn from_f32(value: f32) -> Fixed {
+    let scaled = value * ONE_RAW as f32;
+    let rounded = if scaled >= 0. {
+      scaled + 0.5
+    } else {
+      scaled - 0.5
+    };
+    Fixed(rounded as i64)
+  }
+  pub fn to_f32(self) -> f32 {
+    self.0 as f32 / ONE_RAW as f32
+  }
+  pub const fn from_int(value: i64) -> Fixed {
+    Fixed(value.saturating_mul(ONE_RAW))
+  }
+  pub fn is_nan(self) -> bool {
+    self.0 == i64::MIN
+  }
+
+  pub fn abs(self) -> Fixed {
+    Fixed(self.0.saturating_abs())
+  }
+  /// 1 or -1 (zero is 1, same as f32)
+  pub fn signum(self) -> Fixed {
+    if self.0 < 0 {
+      -Fixed::ONE
+    } else {
+      Fixed::ONE
+    }
+  }
+  pub fn floor(self) -> Fixed {
+    Fixed(self.0 & !(ONE_RAW - 1))
+  }
+  pub fn ceil(self) -> Fixed {
+    -(-self).floor()
+  }
+  /// round half away from zero, same as f32
+  pub fn round(self) -> Fixed {
+    let half = Fixed(ONE_RAW / 2);
+    if self.0 < 0 {
+      -(-self + half).floor()
+    } else {
+      (self + half).floor()
+    }
+  }
+  pub fn min(self, other: Fixed) -> Fixed {
+    Ord::min(self, other)
+  }
+  pub fn max(self, other: Fixed) -> Fixed {
+    Ord::max(self, other)
+  }
+  pub fn clamp(self, min: Fixed, max: Fixed) -> Fixed {
+    Ord::clamp(self, min, max)
+  }
+
+  /// negative input gives zero
+  /// 負數得到零
+  pub fn sqrt(self) -> Fixed {
+    if self.0 <= 0 {
+      return Fixed::ZERO;
+    }
+    let raw = ((self.0 as u128) << FRAC_BITS).isqrt();
+    Fixed(raw as i64)
+  }
+  pub fn powi(self, n: i32) -> Fixed {
+    let mut result = Fixed::ONE;
+    for _ in 0..n.unsigned_abs() {
+      result *= self;
+    }
+    if n < 0 {
+      Fixed::ONE / result
+    } else {
+      result
+    }
+  }
+
+  pub fn sin(self) -> Fixed {
+    self.sin_cos().0
+  }
+  pub fn cos(self) -> Fixed {
+    self.sin_cos().1
+  }
+  /// cordic rotation
+  pub fn sin_cos(self) -> (Fixed, Fixed) {
+    // reduce to (-PI, PI]
+    let mut angle = ((self.0 as i128) << CORDIC_SHIFT).rem_euclid(CORDIC_PI * 2);
+    if angle > CORDIC_PI {
+      angle -= CORDIC_PI * 2
+    }
+    // reduce to [-PI/2, PI/2]
+    let mut sign = 1;
+    if angle > CORDIC_PI / 2 {
+      angle -= CORDIC_PI;
+      sign = -1;
+    } else if angle < -CORDIC_PI / 2 {
+      angle += CORDIC_PI;
+      sign = -1;
+    }
+
+    let (mut x, mut y) = (CORDIC_GAIN, 0_i128);
+    for (i, atan) in CORDIC_ATAN.iter().enumerate() {
+      let (dx, dy) = (y >> i, x >> i);
+      if angle >= 0 {
+        (x, y) = (x - dx, y + dy);
+        angle -= atan;
+      } else {
+        (x, y) = (x + dx, y - dy);
+        angle += atan;
+      }
+    }
+
+    (from_cordic(y * sign), from_cordic(x * sign))
+  }
+  /// cordic vectoring, `self` is y
+  pub fn atan2(self, x: Fixed) -> Fixed {
+    let (mut x, mut y) = (
+      (x.0 as i128) << CORDIC_SHIFT,
+      (self.0 as i128) << CORDIC_SHIFT,
+    );
+    if x == 0 && y == 0 {
+      return Fixed::ZERO;
+    }
+
+    let mut angle = 0_i128;
+    if x < 0 {
+      angle = if y >= 0 { CORDIC_PI } else { -CORDIC_PI };
+      (x, y) = (-x, -y);
+    }
+
+    for (i, atan) in CORDIC_ATAN.iter().enumerate() {
+      let (dx, dy) = (y >> i, x >> i);
+      if y > 0 {
+        (x, y) = (x + dx, y - dy);
+        angle += atan;
+      } else {
+        (x, y) = (x - dx, y + dy);
+        angle -= atan;
+      }
+    }
+
+    if angle > CORDIC_PI {
+      angle -= CORDIC_PI * 2
+    }
+    from_cordic(angle)
+  }
+}
+
+fn from_cordic(value: i128) -> Fixed {
+  let half = 1 << (CORDIC_SHIFT - 1);
+  Fixed(((value + half) >> CORDIC_SHIFT) as i64)
+}
+
+fn saturate(value: i128) -> Fixed {
+  Fixed(value.clamp(Fixed::MIN.0 as i128, Fixed::MAX.0 as i128) as i64)
+}
+
+impl Debug for Fixed {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Debug::fmt(&self.to_f32(), f)
+  }
+}
+
+impl Display for Fixed {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Display::fmt(&self.to_f32(), f)
+  }
+}
+
+impl From<f32> for Fixed {
+  fn from(value: f32) -> Self {
+    Fixed::from_f32(value)
+  }
+}
+
+impl From<Fixed> for f32 {
+  fn from(value: Fixed) -> Self {
+    value.to_f32()
+  }
+}
+
+//
+// calc fixed with fixed
+// 用定點數計算定點數
+impl Neg for Fixed {
+  type Output = Fixed;
+  fn neg(self) -> Self::Output {
+    Fixed(self.0.saturating_neg())
+  }
+}
+
+impl Add for Fixed {
+  type Output = Fixed;
+  fn add(self, rhs: Fixed) -> Self::Output {
+    Fixed(self.0.saturating_add(rhs.0))
+  }
+}
+
+impl Sub for Fixed {
+  type Output = Fixed;
+  fn sub(self, rhs: Fixed) -> Self::Output {
+    Fixed(self.0.saturating_sub(rhs.0))
+  }
+}
+
+impl Mul for Fixed {
+  type Output = Fixed;
+  fn mul(self, rhs: Fixed) -> Self::Output {
+    saturate((self.0 as i128 * rhs.0 as i128) >> FRAC_BITS)
+  }
+}
+
+impl Div for Fixed {
+  type Output = Fixed;
+  fn div(self, rhs: Fixed) -> Self::Output {
+    if rhs.0 == 0 {
+      return if self.0 < 0 { Fixed::MIN } else { Fixed::MAX };
+    }
+    saturate(((self.0 as i128) << FRAC_BITS) / rhs.0 as i128)
+  }
+}
+
+impl Rem for Fixed {
+  type Output = Fixed;
+  fn rem(self, rhs: Fixed) -> Self::Output {
+    if rhs.0 == 0 {
+      return Fixed::NAN;
+    }
+    Fixed(self.0 % rhs.0)
+  }
+}
+
+impl Sum for Fixed {
+  fn sum<I: Iterator<Item = Fixed>>(iter: I) -> Self {
+    iter.fold(Fixed::ZERO, |a, b| a + b)
+  }
+}
+
+macro_rules! impl_op_assign {
+  ($($op:ident $method:ident $op_assign:ident $method_assign:ident),+) => {
+    $(
+      impl $op_assign for Fixed {
+        fn $method_assign(&mut self, rhs: Fixed) {
+          *self = $op::$method(*self, rhs)
+        }
+      }
+    )+
+  };
+}
+
+impl_op_assign!(
+  Add add AddAssign add_assign,
+  Sub sub SubAssign sub_assign,
+  Mul mul MulAssign mul_assign,
+  Div div DivAssign div_assign,
+  Rem rem RemAssign rem_assign
+);
+
+// Compare with f32, arithmetic with f32 is left out on purpose so that
+// f32 math does not slip into simulation, convert with `scalar` instead
+// 支持與 f32 比較; 不提供與 f32 的運算, 避免浮點運算混入模擬, 請以 `scalar` 轉換
+impl PartialEq<f32> for Fixed {
+  fn eq(&self, other: &f32) -> bool {
+    *self == Fixed::from_f32(*other)
+  }
+}
+
+impl PartialEq<Fixed> for f32 {
+  fn eq(&self, other: &Fixed) -> bool {
+    Fixed::from_f32(*self) == *other
+  }
+}
+
+impl PartialOrd<f32> for Fixed {
+  fn partial_cmp(&self, other: &f32) -> Option<Ordering> {
+    self.partial_cmp(&Fixed::from_f32(*other))
+  }
+}
+
+impl PartialOrd<Fixed> for f32 {
+  fn partial_cmp(&self, other: &Fixed) -> Option<Ordering> {
+    Fixed::from_f32(*self).partial_cmp(other)
+  }
+}
+
+#[test]
+fn test() {
+  let close = |a: Fixed, b: f32| (a.to_f32() - b).abs() < 1e-3;
+
+  for i in -40..40 {
+    let angle = i as f32 * 0.37;
+    let (sin, cos) = Fixed::from(angle).sin_cos();
+    assert!(close(sin, angle.sin()), "sin({angle})");
+    assert!(close(cos, angle.cos()), "cos({angle})");
+
+    let (y, x) = (angle.sin() * 3., angle.cos() * 2.);
+    assert!(
+      close(Fixed::from(y).atan2(Fixed::from(x)), y.atan2(x)),
+      "atan2({y}, {x})"
+    );
+  }
+
+  assert!(close(Fixed::from(2.).sqrt(), 2_f32.sqrt()));
+  assert!(close(Fixed::from(-2.7).round(), -3.));
+  assert!(close(Fixed::from(-2.2).floor(), -3.));
+  assert!(close(Fixed::from(2.2).ceil(), 3.));
+  assert!(close(
+    Fixed::from(3.) / Fixed::from(4.) * Fixed::from(2.),
+    1.5
+  ));
+  assert_eq!(Fixed::from(1.) / Fixed::from(0.), Fixed::MAX);
+
+  // nan is an ordinary value that sorts first
+  assert!(Fixed::NAN.is_nan() && !Fixed::MIN.is_nan());
+  assert_eq!(Fixed::NAN, Fixed::NAN);
+  assert!(Fixed::NAN < Fixed::MIN);
+  assert!((Fixed::from(1.) % Fixed::ZERO).is_nan());
+  assert!(crate::utils::vector::Vector::UNREACHABLE.is_unreachable());
+  assert!(!crate::utils::vector::Vector::ORIGIN.is_unreachable());
+}
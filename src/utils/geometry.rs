@@ -0,0 +1,441 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+  rect::Rect,
+  scalar::{scalar, Scalar},
+  vector::Vector,
+  viewbox::ViewBox,
+};
+
+//==============================================================================================
+// Aabb
+//==============================================================================================
+
+/// axis-aligned bounding box
+/// 軸對齊包圍盒
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+  pub min: Vector,
+  pub max: Vector,
+}
+
+impl Aabb {
+  /// create new box, corners will be sorted
+  /// 創建新包圍盒, 自動排序角點
+  pub fn new(a: Vector, b: Vector) -> Aabb {
+    Aabb {
+      min: a.min(b),
+      max: a.max(b),
+    }
+  }
+  /// create box by center and size
+  /// 以中心和大小創建包圍盒
+  pub fn from_center(center: Vector, size: Vector) -> Aabb {
+    let half = size.abs() / scalar(2.);
+    Aabb {
+      min: center - half,
+      max: center + half,
+    }
+  }
+  /// create box bounding all points
+  /// 創建包含所有點的包圍盒
+  pub fn from_points(points: &[Vector]) -> Option<Aabb> {
+    let (first, rest) = points.split_first()?;
+    let mut aabb = Aabb::new(*first, *first);
+    for point in rest {
+      aabb = aabb.expand_to(*point);
+    }
+    Some(aabb)
+  }
+  /// create box bounding a viewbox (rotation included)
+  /// 創建包含視框的包圍盒 (包含旋轉)
+  pub fn from_viewbox<T: ViewBox + ?Sized>(viewbox: &T) -> Aabb {
+    let (max, min) = viewbox.maxmin();
+    Aabb { min, max }
+  }
+
+  pub fn center(&self) -> Vector {
+    (self.min + self.max) / scalar(2.)
+  }
+  pub fn size(&self) -> Vector {
+    self.max - self.min
+  }
+  pub fn to_rect(&self) -> Rect {
+    Rect::new(self.center(), self.size())
+  }
+
+  /// whether point is inside, edges included
+  /// 是否包含點 (含邊界)
+  pub fn contains(&self, point: Vector) -> bool {
+    self.min.0 <= point.0 && point.0 <= self.max.0 && self.min.1 <= point.1 && point.1 <= self.max.1
+  }
+  /// whether other box is fully inside
+  /// 是否完全包含另一個包圍盒
+  pub fn contains_aabb(&self, other: &Aabb) -> bool {
+    self.contains(other.min) && self.contains(other.max)
+  }
+  /// whether boxes overlap, touching edges included
+  /// 是否相交 (含邊界)
+  pub fn intersects(&self, other: &Aabb) -> bool {
+    self.min.0 <= other.max.0
+      && other.min.0 <= self.max.0
+      && self.min.1 <= other.max.1
+      && other.min.1 <= self.max.1
+  }
+  /// smallest box containing both
+  /// 聯集
+  pub fn union(&self, other: &Aabb) -> Aabb {
+    Aabb {
+      min: self.min.min(other.min),
+      max: self.max.max(other.max),
+    }
+  }
+  /// overlapping box, `None` if apart
+  /// 交集
+  pub fn intersection(&self, other: &Aabb) -> Option<Aabb> {
+    let min = self.min.max(other.min);
+    let max = self.max.min(other.max);
+    if min.0 > max.0 || min.1 > max.1 {
+      return None;
+    }
+    Some(Aabb { min, max })
+  }
+  /// grow on every side, negative amount shrinks
+  /// 向外擴張 (負數為收縮)
+  pub fn expand(&self, amount: Vector) -> Aabb {
+    Aabb::new(self.min - amount, self.max + amount)
+  }
+  /// grow to contain point
+  /// 擴張至包含點
+  pub fn expand_to(&self, point: Vector) -> Aabb {
+    Aabb {
+      min: self.min.min(point),
+      max: self.max.max(point),
+    }
+  }
+  /// closest point inside box
+  /// 最近點
+  pub fn closest_point(&self, point: Vector) -> Vector {
+    point.max(self.min).min(self.max)
+  }
+  /// distance to point, zero inside
+  /// 到點的距離 (內部為 0)
+  pub fn distance(&self, point: Vector) -> Scalar {
+    self.closest_point(point).to(point).distance()
+  }
+  /// four edges, anticlockwise from min corner
+  /// 四條邊 (左下起逆時針)
+  pub fn segments(&self) -> [Segment; 4] {
+    let (min, max) = (self.min, self.max);
+    let points = [min, Vector(max.0, min.1), max, Vector(min.0, max.1)];
+    [0, 1, 2, 3].map(|i| Segment::new(points[i], points[(i + 1) % 4]))
+  }
+}
+
+impl From<Rect> for Aabb {
+  fn from(rect: Rect) -> Self {
+    Aabb::from_viewbox(&rect)
+  }
+}
+
+impl From<Aabb> for Rect {
+  fn from(aabb: Aabb) -> Self {
+    aabb.to_rect()
+  }
+}
+
+//==============================================================================================
+// Segment
+//==============================================================================================
+
+/// line segment
+/// 線段
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+  pub start: Vector,
+  pub end: Vector,
+}
+
+impl Segment {
+  pub fn new(start: Vector, end: Vector) -> Segment {
+    Segment { start, end }
+  }
+  /// vector from start to end
+  /// 從起點到終點的向量
+  pub fn vector(&self) -> Vector {
+    self.start.to(self.end)
+  }
+  pub fn length(&self) -> Scalar {
+    self.vector().distance()
+  }
+  pub fn midpoint(&self) -> Vector {
+    (self.start + self.end) / scalar(2.)
+  }
+  pub fn aabb(&self) -> Aabb {
+    Aabb::new(self.start, self.end)
+  }
+  /// closest point on segment
+  /// 線段上最近點
+  pub fn closest_point(&self, point: Vector) -> Vector {
+    let vector = self.vector();
+    let length = vector.distance_magnitude();
+    if length < Scalar::EPSILON {
+      return self.start;
+    }
+    let t = (self.start.to(point).dot(vector) / length).clamp(scalar(0.), scalar(1.));
+    self.start + vector * t
+  }
+  /// distance to point
+  /// 到點的距離
+  pub fn distance(&self, point: Vector) -> Scalar {
+    self.closest_point(point).to(point).distance()
+  }
+  /// intersection point of segments
+  /// 線段交點
+  ///
+  /// parallel segments return `None`, collinear overlapping ones too
+  /// 平行的線段回傳 `None`, 共線重疊的線段也是
+  pub fn intersection(&self, other: &Segment) -> Option<Vector> {
+    let (t, u) = line_intersection(self.start, self.vector(), other.start, other.vector())?;
+    if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+      Some(self.start + self.vector() * t)
+    } else {
+      None
+    }
+  }
+  pub fn intersects(&self, other: &Segment) -> bool {
+    self.intersection(other).is_some()
+  }
+}
+
+//==============================================================================================
+// Ray
+//==============================================================================================
+
+/// ray, direction will be normalized
+/// 射線, 方向會被正規化
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Ray {
+  pub origin: Vector,
+  pub direction: Vector,
+}
+
+impl Ray {
+  pub fn new(origin: Vector, direction: Vector) -> Ray {
+    Ray {
+      origin,
+      direction: direction.by_length(scalar(1.)),
+    }
+  }
+  /// ray from origin toward target
+  /// 從起點看向目標
+  pub fn look_at(origin: Vector, target: Vector) -> Ray {
+    Ray::new(origin, origin.to(target))
+  }
+  /// point at `distance` from origin
+  /// 距離起點 `distance` 的點
+  pub fn at(&self, distance: Scalar) -> Vector {
+    self.origin + self.direction * distance
+  }
+  /// distance along ray to segment
+  /// 射線到線段的距離
+  pub fn cast_segment(&self, segment: &Segment) -> Option<Scalar> {
+    let (t, u) = line_intersection(self.origin, self.direction, segment.start, segment.vector())?;
+    if t >= 0. && (0. ..=1.).contains(&u) {
+      Some(t)
+    } else {
+      None
+    }
+  }
+  /// distance along ray to box, zero if origin is inside
+  /// 射線到包圍盒的距離 (起點在內部為 0)
+  pub fn cast_aabb(&self, aabb: &Aabb) -> Option<Scalar> {
+    let mut near = Scalar::MIN;
+    let mut far = Scalar::MAX;
+    for (origin, direction, min, max) in [
+      (self.origin.0, self.direction.0, aabb.min.0, aabb.max.0),
+      (self.origin.1, self.direction.1, aabb.min.1, aabb.max.1),
+    ] {
+      if direction.abs() < Scalar::EPSILON {
+        if origin < min || origin > max {
+          return None;
+        }
+        continue;
+      }
+      let a = (min - origin) / direction;
+      let b = (max - origin) / direction;
+      near = near.max(a.min(b));
+      far = far.min(a.max(b));
+    }
+    if near > far || far < 0. {
+      return None;
+    }
+    Some(near.max(scalar(0.)))
+  }
+  /// distance along ray to rect (rotated too), zero if origin is inside
+  /// 射線到矩形的距離 (支援旋轉, 起點在內部為 0)
+  pub fn cast_rect(&self, rect: &Rect) -> Option<Scalar> {
+    if rect.contains(self.origin) {
+      return Some(scalar(0.));
+    }
+    rect
+      .segments()
+      .iter()
+      .filter_map(|segment| self.cast_segment(segment))
+      .reduce(Scalar::min)
+  }
+  /// distance along ray to circle, zero if origin is inside
+  /// 射線到圓的距離 (起點在內部為 0)
+  pub fn cast_circle(&self, circle: &Circle) -> Option<Scalar> {
+    let to_center = self.origin.to(circle.center);
+    if to_center.distance_magnitude() <= circle.radius.powi(2) {
+      return Some(scalar(0.));
+    }
+    let projection = to_center.dot(self.direction);
+    if projection < 0. {
+      return None;
+    }
+    let closest = to_center.distance_magnitude() - projection.powi(2);
+    let radius = circle.radius.powi(2);
+    if closest > radius {
+      return None;
+    }
+    Some(projection - (radius - closest).sqrt())
+  }
+}
+
+//==============================================================================================
+// Circle
+//==============================================================================================
+
+/// circle
+/// 圓
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Circle {
+  pub center: Vector,
+  pub radius: Scalar,
+}
+
+impl Circle {
+  pub fn new(center: Vector, radius: Scalar) -> Circle {
+    Circle {
+      center,
+      radius: radius.abs(),
+    }
+  }
+  pub fn aabb(&self) -> Aabb {
+    Aabb::from_center(self.center, Vector(self.radius, self.radius) * scalar(2.))
+  }
+  pub fn contains(&self, point: Vector) -> bool {
+    self.center.to(point).distance_magnitude() <= self.radius.powi(2)
+  }
+  pub fn intersects(&self, other: &Circle) -> bool {
+    self.center.to(other.center).distance_magnitude() <= (self.radius + other.radius).powi(2)
+  }
+  pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+    self.contains(aabb.closest_point(self.center))
+  }
+  pub fn intersects_segment(&self, segment: &Segment) -> bool {
+    self.contains(segment.closest_point(self.center))
+  }
+  /// closest point on or inside circle
+  /// 圓上 (或內部) 最近點
+  pub fn closest_point(&self, point: Vector) -> Vector {
+    if self.contains(point) {
+      return point;
+    }
+    self.center + self.center.to(point).by_length(self.radius)
+  }
+  /// distance to point, zero inside
+  /// 到點的距離 (內部為 0)
+  pub fn distance(&self, point: Vector) -> Scalar {
+    (self.center.to(point).distance() - self.radius).max(scalar(0.))
+  }
+}
+
+/// solve `a + av * t == b + bv * u`
+fn line_intersection(a: Vector, av: Vector, b: Vector, bv: Vector) -> Option<(Scalar, Scalar)> {
+  let denominator = av.cross(bv);
+  if denominator.abs() < Scalar::EPSILON {
+    return None;
+  }
+  let ab = a.to(b);
+  Some((ab.cross(bv) / denominator, ab.cross(av) / denominator))
+}
+
+#[test]
+fn test() {
+  let close = |a: Scalar, b: f32| (a - scalar(b)).abs() < scalar(1e-3);
+
+  // aabb
+  let a = Aabb::new(Vector::new(2., 2.), Vector::new(0., 0.));
+  let b = Aabb::from_center(Vector::new(2., 2.), Vector::new(2., 2.));
+  assert_eq!(a.min, Vector::ORIGIN);
+  assert_eq!(a.union(&b), Aabb::new(Vector::ORIGIN, Vector::new(3., 3.)));
+  assert_eq!(
+    a.intersection(&b),
+    Some(Aabb::new(Vector::new(1., 1.), Vector::new(2., 2.)))
+  );
+  let far = Aabb::new(Vector::new(5., 5.), Vector::new(6., 6.));
+  assert_eq!(a.intersection(&far), None);
+  assert!(!a.intersects(&far));
+  assert!(a.contains(Vector::new(2., 0.)));
+  assert!(!a.contains(Vector::new(2.5, 0.)));
+  assert!(a.union(&b).contains_aabb(&b));
+  assert!(!a.contains_aabb(&b));
+
+  // segment
+  let horizontal = Segment::new(Vector::new(0., 1.), Vector::new(4., 1.));
+  let vertical = Segment::new(Vector::new(2., 0.), Vector::new(2., 4.));
+  assert_eq!(
+    horizontal.intersection(&vertical),
+    Some(Vector::new(2., 1.))
+  );
+  let short = Segment::new(Vector::new(2., 2.), Vector::new(2., 4.));
+  assert_eq!(horizontal.intersection(&short), None);
+  let parallel = Segment::new(Vector::new(0., 2.), Vector::new(4., 2.));
+  assert_eq!(horizontal.intersection(&parallel), None);
+  let collinear = Segment::new(Vector::new(2., 1.), Vector::new(6., 1.));
+  assert_eq!(horizontal.intersection(&collinear), None);
+  assert_eq!(
+    horizontal.closest_point(Vector::new(-3., 5.)),
+    Vector::new(0., 1.)
+  );
+
+  // ray
+  let ray = Ray::new(Vector::new(-5., 1.), Vector::new(3., 0.));
+  assert!(close(ray.cast_aabb(&a).unwrap(), 5.));
+  assert!(close(ray.cast_segment(&vertical).unwrap(), 7.));
+  assert_eq!(ray.cast_aabb(&far), None);
+  let inside = Ray::new(Vector::new(1., 1.), Vector::new(0., 1.));
+  assert!(close(inside.cast_aabb(&a).unwrap(), 0.));
+  let backward = Ray::new(Vector::new(5., 1.), Vector::new(1., 0.));
+  assert_eq!(backward.cast_aabb(&a), None);
+  let rect = Rect::new(Vector::new(1., 1.), Vector::new(2., 2.));
+  assert!(close(ray.cast_rect(&rect).unwrap(), 5.));
+  let circle = Circle::new(Vector::new(5., 1.), scalar(2.));
+  assert!(close(ray.cast_circle(&circle).unwrap(), 8.));
+  assert_eq!(
+    backward.cast_circle(&Circle::new(Vector::ORIGIN, scalar(1.))),
+    None
+  );
+  assert!(close(
+    Ray::new(Vector::new(5., 0.), Vector::new(0., 1.))
+      .cast_circle(&circle)
+      .unwrap(),
+    0.
+  ));
+
+  // circle
+  assert_eq!(
+    circle.closest_point(Vector::new(5., 9.)),
+    Vector::new(5., 3.)
+  );
+  assert_eq!(
+    circle.closest_point(Vector::new(6., 1.)),
+    Vector::new(6., 1.)
+  );
+  assert!(close(circle.distance(Vector::new(10., 1.)), 3.));
+  assert!(circle.intersects_aabb(&Aabb::new(Vector::new(6., 2.), Vector::new(8., 8.))));
+  assert!(!circle.intersects(&Circle::new(Vector::ORIGIN, scalar(1.))));
+}
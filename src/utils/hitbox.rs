@@ -1,9 +1,9 @@
-use crate::utils::vector::Vector;
+use crate::utils::{scalar::Scalar, vector::Vector};
 
 use super::viewbox::ViewBox;
 
 pub trait HitBox: ViewBox {
-  fn angle(&self) -> f32;
+  fn angle(&self) -> Scalar;
   fn size(&self) -> Vector;
   fn position(&self) -> Vector;
 
@@ -15,14 +15,14 @@ pub trait HitBox: ViewBox {
   }
 
   fn collision(&self, other: &impl HitBox) -> bool {
-    if HitBox::angle(self).abs() < f32::EPSILON && HitBox::angle(other).abs() < f32::EPSILON {
+    if HitBox::angle(self).abs() < Scalar::EPSILON && HitBox::angle(other).abs() < Scalar::EPSILON {
       return ViewBox::collision(self, other);
     }
 
     let mut a = vec![];
     for nomral in self.nomral_vector() {
-      let mut max = f32::MIN;
-      let mut min = f32::MAX;
+      let mut max = Scalar::MIN;
+      let mut min = Scalar::MAX;
       for point in self.points() {
         let orthoprojection = nomral.orthoprojection_length(point);
         if orthoprojection > max {
@@ -32,8 +32,8 @@ pub trait HitBox: ViewBox {
           min = orthoprojection
         }
       }
-      let mut other_max = f32::MIN;
-      let mut other_min = f32::MAX;
+      let mut other_max = Scalar::MIN;
+      let mut other_min = Scalar::MAX;
       for point in other.points() {
         let orthoprojection = nomral.orthoprojection_length(point);
         if orthoprojection > other_max {
@@ -6,6 +6,10 @@ pub mod hitbox;
 pub mod rchash;
 pub mod rect;
 pub mod bar;
+pub mod transform;
+pub mod geometry;
+pub mod fixed;
+pub mod scalar;
 
 #[macro_use]
 pub mod event;
\ No newline at end of file
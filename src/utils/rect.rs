@@ -2,13 +2,19 @@ use std::ops::Add;
 
 use serde::{Deserialize, Serialize};
 
-use super::{hitbox::HitBox, vector::Vector, viewbox::ViewBox};
+use super::{
+  geometry::{Aabb, Segment},
+  hitbox::HitBox,
+  scalar::{scalar, Scalar},
+  vector::Vector,
+  viewbox::ViewBox,
+};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
   pub position: Vector,
   pub size: Vector,
-  pub angle: f32,
+  pub angle: Scalar,
 }
 
 impl Rect {
@@ -16,17 +22,36 @@ impl Rect {
     Rect {
       size,
       position,
-      angle: 0.0,
+      angle: scalar(0.),
     }
   }
-  pub fn new_with_angle(position: Vector, size: Vector, angle: f32) -> Rect {
+  pub fn new_with_angle(position: Vector, size: Vector, angle: Scalar) -> Rect {
     let mut rect = Self::new(position,size);
     rect.set_angle(angle);
     rect
   }
-  pub fn set_angle(&mut self, angle: f32) {
+  pub fn set_angle(&mut self, angle: Scalar) {
     self.angle = angle
   }
+  /// axis aligned bounding box
+  /// 軸對齊包圍盒
+  pub fn aabb(&self) -> Aabb {
+    Aabb::from(*self)
+  }
+  /// whether point is inside, rotation supported
+  /// 是否包含點 (支援旋轉)
+  pub fn contains(&self, point: Vector) -> bool {
+    let [a, b, c, d] = self.points();
+    let inside = |start: Vector, end: Vector| start.to(end).cross(start.to(point));
+    let sides = [inside(a, b), inside(b, c), inside(c, d), inside(d, a)];
+    sides.iter().all(|side| *side <= 0.) || sides.iter().all(|side| *side >= 0.)
+  }
+  /// four edges
+  /// 四條邊
+  pub fn segments(&self) -> [Segment; 4] {
+    let points = self.points();
+    [0, 1, 2, 3].map(|i| Segment::new(points[i], points[(i + 1) % 4]))
+  }
 }
 
 impl Add<Vector> for Rect {
@@ -39,7 +64,7 @@ impl Add<Vector> for Rect {
 }
 
 impl ViewBox for Rect {
-  fn angle(&self) -> f32 {
+  fn angle(&self) -> Scalar {
     self.angle
   }
 
@@ -53,7 +78,7 @@ impl ViewBox for Rect {
 }
 
 impl HitBox for Rect {
-  fn angle(&self) -> f32 {
+  fn angle(&self) -> Scalar {
     self.angle
   }
 
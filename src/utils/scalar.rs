@@ -0,0 +1,86 @@
+//! simulation number type
+//! 模擬使用的數字類型
+//!
+//! `f32` by default, `Fixed` with the `fixed` feature for deterministic simulation.
+//! 預設為 `f32`, 啟用 `fixed` 功能後使用定點數以確保模擬結果一致
+
+#[cfg(not(feature = "fixed"))]
+pub type Scalar = f32;
+
+#[cfg(feature = "fixed")]
+pub type Scalar = super::fixed::Fixed;
+
+/// convert f32 to scalar (usable in const)
+/// 將 f32 轉換為模擬數字
+#[cfg(not(feature = "fixed"))]
+pub const fn scalar(value: f32) -> Scalar {
+  value
+}
+
+/// convert f32 to scalar (usable in const)
+/// 將 f32 轉換為模擬數字
+#[cfg(feature = "fixed")]
+pub const fn scalar(value: f32) -> Scalar {
+  super::fixed::Fixed::from_f32(value)
+}
+
+/// convert scalar to f32
+/// 將模擬數字轉換為 f32
+#[cfg(not(feature = "fixed"))]
+pub fn to_f32(value: Scalar) -> f32 {
+  value
+}
+
+/// convert scalar to f32
+/// 將模擬數字轉換為 f32
+#[cfg(feature = "fixed")]
+pub fn to_f32(value: Scalar) -> f32 {
+  value.to_f32()
+}
+
+#[cfg(feature = "fixed")]
+#[test]
+fn test() {
+  use crate::modules::{
+    enity::{base::EnityBase, position::MoveEvent, track::EnityTrack, view::EnityView},
+    scene::NormalScene,
+  };
+
+  use super::{rect::Rect, vector::Vector};
+
+  // entities crowd to the center and push each other apart
+  let run = || {
+    let mut scene = NormalScene::new(Vector::new(400., 400.));
+    let mut tracks = vec![];
+    for index in 0..16 {
+      let hitbox = Rect::new(Vector::ORIGIN, Vector::new(10., 10.));
+      let base = EnityBase::new(format!("{index}"), vec![], scalar(40. + index as f32));
+      let track = EnityTrack::new(base, EnityView::new(vec![], vec![hitbox]));
+      let (sin, cos) = scalar(index as f32 * 0.7).sin_cos();
+      let mut position = track.position(scene.uuid());
+      position.set(Vector(cos * scalar(100.), sin * scalar(100.)) + Vector::new(200., 200.));
+      position.set_action(MoveEvent::Moveto(Vector::new(200., 200.)));
+      drop(position);
+      scene.insert(&track);
+      tracks.push(track);
+    }
+    for _ in 0..200 {
+      scene.update(16);
+    }
+    tracks
+      .iter()
+      .map(|track| {
+        let (x, y) = track.position(scene.uuid()).get().unpack();
+        (x.raw(), y.raw())
+      })
+      .collect::<Vec<_>>()
+  };
+
+  let first = run();
+  assert_eq!(first, run());
+  // collisions kept them apart
+  let mut unique = first.clone();
+  unique.sort();
+  unique.dedup();
+  assert_eq!(unique.len(), first.len());
+}
@@ -0,0 +1,241 @@
+use std::ops::Mul;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+  rect::Rect,
+  scalar::{scalar, Scalar},
+  vector::Vector,
+};
+
+/// largest cosine between matrix axes treated as no shear
+const SHEAR_TOLERANCE: Scalar = scalar(1e-3);
+
+/// 2D transform (translation, rotation, scale, pivot)
+/// 二維變換 (平移, 旋轉, 縮放, 樞軸)
+///
+/// apply order: `translation + pivot + rotate(scale * (point - pivot))`
+/// 套用順序: 先以樞軸縮放, 再以樞軸旋轉, 最後平移
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Transform2D {
+  pub translation: Vector,
+  pub rotation: Scalar,
+  pub scale: Vector,
+  pub pivot: Vector,
+}
+
+impl Transform2D {
+  /// identity transform
+  /// 單位變換
+  pub const IDENTITY: Transform2D = Transform2D {
+    translation: Vector::ORIGIN,
+    rotation: scalar(0.),
+    scale: Vector(scalar(1.), scalar(1.)),
+    pivot: Vector::ORIGIN,
+  };
+  /// create new transform by translation and rotation
+  /// 以平移和旋轉創建變換
+  pub fn new(translation: Vector, rotation: Scalar) -> Transform2D {
+    Transform2D {
+      translation,
+      rotation,
+      ..Self::IDENTITY
+    }
+  }
+  /// create translation only transform
+  /// 創建平移變換
+  pub fn from_translation(translation: Vector) -> Transform2D {
+    Self::new(translation, scalar(0.))
+  }
+  /// create rotation only transform
+  /// 創建旋轉變換
+  pub fn from_rotation(rotation: Scalar) -> Transform2D {
+    Self::new(Vector::ORIGIN, rotation)
+  }
+  /// create scale only transform
+  /// 創建縮放變換
+  pub fn from_scale(scale: Vector) -> Transform2D {
+    Transform2D {
+      scale,
+      ..Self::IDENTITY
+    }
+  }
+  /// set pivot
+  /// 設置樞軸
+  pub fn with_pivot(mut self, pivot: Vector) -> Transform2D {
+    self.pivot = pivot;
+    self
+  }
+  /// set scale
+  /// 設置縮放
+  pub fn with_scale(mut self, scale: Vector) -> Transform2D {
+    self.scale = scale;
+    self
+  }
+
+  /// apply transform to vector
+  /// 對向量套用變換
+  pub fn apply(&self, vector: Vector) -> Vector {
+    let local = (vector - self.pivot) * self.scale;
+    self.translation + self.pivot + local.rotate(self.rotation)
+  }
+  /// apply rotation and scale only (for directions)
+  /// 只套用旋轉和縮放 (用於方向)
+  pub fn apply_vector(&self, vector: Vector) -> Vector {
+    (vector * self.scale).rotate(self.rotation)
+  }
+  /// apply transform to rect
+  /// 對矩形套用變換
+  pub fn apply_rect(&self, rect: Rect) -> Rect {
+    Rect {
+      position: self.apply(rect.position),
+      size: rect.size * self.scale.abs(),
+      angle: rect.angle + self.rotation,
+    }
+  }
+
+  /// get composed transform, apply `other` first then `self`
+  /// 得到合成變換, 先套用 `other` 再套用 `self`
+  /// return `None` if result has shear (see `from_matrix`)
+  pub fn compose(&self, other: &Transform2D) -> Option<Transform2D> {
+    let [a, b, c, d, tx, ty] = self.matrix();
+    let [oa, ob, oc, od, otx, oty] = other.matrix();
+    Self::from_matrix([
+      a * oa + c * ob,
+      b * oa + d * ob,
+      a * oc + c * od,
+      b * oc + d * od,
+      a * otx + c * oty + tx,
+      b * otx + d * oty + ty,
+    ])
+  }
+  /// get inverse transform
+  /// 得到逆變換
+  /// return `None` if scale has zero, or inverse has shear (see `from_matrix`)
+  pub fn inverse(&self) -> Option<Transform2D> {
+    let [a, b, c, d, tx, ty] = self.matrix();
+    let det = a * d - b * c;
+    if det.abs() < Scalar::EPSILON {
+      return None;
+    }
+    let (ia, ib, ic, id) = (d / det, -b / det, -c / det, a / det);
+    Self::from_matrix([ia, ib, ic, id, -(ia * tx + ic * ty), -(ib * tx + id * ty)])
+  }
+
+  /// get column-major affine matrix `[a, b, c, d, tx, ty]`
+  /// 得到仿射矩陣
+  pub fn matrix(&self) -> [Scalar; 6] {
+    let (sin, cos) = self.rotation.sin_cos();
+    let (sx, sy) = self.scale.unpack();
+    let (a, b, c, d) = (cos * sx, sin * sx, -sin * sy, cos * sy);
+    let (px, py) = self.pivot.unpack();
+    let (x, y) = self.translation.unpack();
+    [
+      a,
+      b,
+      c,
+      d,
+      x + px - (a * px + c * py),
+      y + py - (b * px + d * py),
+    ]
+  }
+  /// create transform from affine matrix (pivot at origin)
+  /// 從仿射矩陣創建變換 (樞軸為原點)
+  ///
+  /// return `None` if matrix has shear, which rotation and scale can not express,
+  /// e.g. non-uniform scale after a rotation that is not a multiple of π/2
+  /// 矩陣含有切變時回傳 `None`, 例如旋轉後再做非等比縮放
+  pub fn from_matrix(matrix: [Scalar; 6]) -> Option<Transform2D> {
+    let [a, b, c, d, tx, ty] = matrix;
+    // columns of rotation × scale are orthogonal
+    let (x_axis, y_axis) = (Vector(a, b).distance(), Vector(c, d).distance());
+    if (a * c + b * d).abs() > x_axis * y_axis * SHEAR_TOLERANCE {
+      return None;
+    }
+    let sx = Vector(a, b).distance();
+    let rotation = b.atan2(a);
+    let sy = if sx < Scalar::EPSILON {
+      Vector(c, d).distance()
+    } else {
+      (a * d - b * c) / sx
+    };
+    Some(Transform2D {
+      translation: Vector(tx, ty),
+      rotation,
+      scale: Vector(sx, sy),
+      pivot: Vector::ORIGIN,
+    })
+  }
+}
+
+impl Default for Transform2D {
+  fn default() -> Self {
+    Self::IDENTITY
+  }
+}
+
+impl Mul<Vector> for Transform2D {
+  type Output = Vector;
+  fn mul(self, rhs: Vector) -> Self::Output {
+    self.apply(rhs)
+  }
+}
+
+impl Mul<Rect> for Transform2D {
+  type Output = Rect;
+  fn mul(self, rhs: Rect) -> Self::Output {
+    self.apply_rect(rhs)
+  }
+}
+
+#[test]
+fn test() {
+  let close = |a: Vector, b: Vector| a.to(b).distance() < 1e-3;
+
+  let transform = Transform2D::new(Vector::new(10., 5.), scalar(std::f32::consts::FRAC_PI_2))
+    .with_scale(Vector::new(2., 2.))
+    .with_pivot(Vector::new(1., 0.));
+  let point = Vector::new(2., 0.);
+  assert!(close(transform.apply(point), Vector::new(11., 7.)));
+
+  let inverse = transform.inverse().unwrap();
+  assert!(close(inverse.apply(transform.apply(point)), point));
+
+  let parent = Transform2D::new(Vector::new(3., 0.), scalar(0.5));
+  let composed = parent.compose(&transform).unwrap();
+  assert!(close(composed * point, parent * (transform * point)));
+
+  // non-uniform scale with rotation
+  let skewed = Transform2D::new(Vector::ORIGIN, scalar(0.5)).with_scale(Vector::new(2., 1.));
+  let point = Vector::new(1., 1.);
+  assert!(skewed.inverse().is_none());
+  assert!(skewed.compose(&skewed).is_none());
+  // a quarter turn keeps axes, so inverse exists
+  let quarter = Transform2D::new(Vector::new(1., 2.), scalar(std::f32::consts::FRAC_PI_2))
+    .with_scale(Vector::new(2., 1.));
+  let inverse = quarter.inverse().unwrap();
+  assert!(close(inverse.apply(quarter.apply(point)), point));
+  let twice = quarter.compose(&quarter).unwrap();
+  assert!(close(twice * point, quarter * (quarter * point)));
+  // parent with uniform scale keeps child axes orthogonal
+  let uniform = Transform2D::new(Vector::ORIGIN, scalar(0.5)).with_scale(Vector::new(2., 2.));
+  let composed = uniform.compose(&skewed).unwrap();
+  assert!(close(composed * point, uniform * (skewed * point)));
+
+  // rotation keeps length
+  let quarter_turn = scalar(std::f32::consts::FRAC_PI_2);
+  assert!(close(
+    Vector::new(2., 0.).rotate(quarter_turn),
+    Vector::new(0., 2.)
+  ));
+  // part rect of an entity, offset is not halved and angle is relative
+  let entity = Transform2D::new(Vector::new(10., 0.), scalar(std::f32::consts::FRAC_PI_2));
+  let part = entity.apply_rect(Rect::new_with_angle(
+    Vector::new(4., 0.),
+    Vector::new(2., 1.),
+    scalar(0.25),
+  ));
+  assert!(close(part.position, Vector::new(10., 4.)));
+  assert!((part.angle - scalar(std::f32::consts::FRAC_PI_2 + 0.25)).abs() < scalar(1e-3));
+  assert_eq!(part.size, Vector::new(2., 1.));
+}
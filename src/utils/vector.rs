@@ -5,44 +5,54 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
-use super::viewbox::ViewBox;
+use super::{
+  scalar::{scalar, Scalar},
+  viewbox::ViewBox,
+};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub struct Vector(pub f32, pub f32);
+pub struct Vector(pub Scalar, pub Scalar);
 
 impl Vector {
   /// zero vector
   /// 零向量
-  pub const ORIGIN: Vector = Vector(0., 0.);
+  pub const ORIGIN: Vector = Vector(scalar(0.), scalar(0.));
   /// invalid vector
   /// 無效向量
-  pub const UNREACHABLE: Vector = Vector(f32::NAN, f32::NAN);
-  /// create new vector
-  /// 創建新向量
+  /// compare with `is_unreachable`, `==` fails for f32 NaN
+  /// 請用 `is_unreachable` 判斷, f32 NaN 無法以 `==` 比較
+  pub const UNREACHABLE: Vector = Vector(Scalar::NAN, Scalar::NAN);
+  /// whether vector is `UNREACHABLE` (any part is NaN)
+  /// 是否為無效向量
+  pub fn is_unreachable(self) -> bool {
+    self.0.is_nan() || self.1.is_nan()
+  }
+  /// create new vector from f32
+  /// 從 f32 創建新向量
   pub fn new(x: f32, y: f32) -> Vector {
-    Vector(x, y)
+    Vector(scalar(x), scalar(y))
   }
   /// get vector distance
   /// 得到向量距離
-  pub fn distance(self) -> f32 {
+  pub fn distance(self) -> Scalar {
     self.distance_magnitude().sqrt()
   }
   /// get vector distance magnitude
   /// 得到向量距離^2
-  pub fn distance_magnitude(self) -> f32 {
+  pub fn distance_magnitude(self) -> Scalar {
     self.0.powi(2) + self.1.powi(2)
   }
   /// get vector radian
   /// 得到向量弧度
-  pub fn radian(self) -> f32 {
+  pub fn radian(self) -> Scalar {
     let (x, y) = self.unpack();
     y.atan2(x)
   }
   /// scale vector by length
   /// 按長度縮放矢量
-  pub fn by_length(self, length: f32) -> Vector {
+  pub fn by_length(self, length: Scalar) -> Vector {
     let distance = self.distance();
-    if distance < f32::EPSILON {
+    if distance < Scalar::EPSILON {
       return Vector::ORIGIN;
     }
     let ratio = length / distance;
@@ -55,21 +65,15 @@ impl Vector {
   }
   /// unpack vector
   /// 打包向量
-  pub fn unpack(self) -> (f32, f32) {
+  pub fn unpack(self) -> (Scalar, Scalar) {
     (self.0, self.1)
   }
-  /// anticlockwise rotation
-  /// 逆時針旋轉
-  pub fn rotate(self, radian: f32) -> Vector {
-    let (x, y) = (self / 2.).unpack();
-
-    let sin = radian.sin();
-    let cos = radian.cos();
-
-    let rx = x * cos - y * sin;
-    let ry = x * sin + y * cos;
-
-    Vector::new(rx, ry)
+  /// anticlockwise rotation, length is kept
+  /// 逆時針旋轉, 長度不變
+  pub fn rotate(self, radian: Scalar) -> Vector {
+    let (x, y) = self.unpack();
+    let (sin, cos) = radian.sin_cos();
+    Vector(x * cos - y * sin, x * sin + y * cos)
   }
   /// get left normal vector
   /// 得到左法向量
@@ -94,7 +98,7 @@ impl Vector {
   }
   /// get vector dot product
   /// 得到向量點積
-  pub fn dot(self, target: Vector) -> f32 {
+  pub fn dot(self, target: Vector) -> Scalar {
     let concat = target * self;
     concat.0 + concat.1
   }
@@ -104,7 +108,7 @@ impl Vector {
   /// value > 0, Anticlockwise rotation  
   /// value < 0, Clockwise rotation  
   /// value = 0, Parallel  
-  pub fn cross(self, target: Vector) -> f32 {
+  pub fn cross(self, target: Vector) -> Scalar {
     return self.0 * target.1 - self.1 * target.0;
   }
   /// get orthoprojection of target on self
@@ -114,7 +118,7 @@ impl Vector {
   }
   /// get orthoprojection length of target on self
   /// 得到目標對自身的正射投影長度
-  pub fn orthoprojection_length(self, target: Vector) -> f32 {
+  pub fn orthoprojection_length(self, target: Vector) -> Scalar {
     self.dot(target) / self.distance()
   }
 
@@ -254,80 +258,81 @@ impl PartialOrd for Vector {
 }
 
 //
-// Support f32
-// 支持 f32
+// Support Scalar
+// 支持 Scalar
 
-impl Add<f32> for Vector {
+impl Add<Scalar> for Vector {
   type Output = Vector;
-  fn add(self, rhs: f32) -> Self::Output {
+  fn add(self, rhs: Scalar) -> Self::Output {
     Vector(self.0 + rhs, self.1 + rhs)
   }
 }
 
-impl AddAssign<f32> for Vector {
-  fn add_assign(&mut self, rhs: f32) {
+impl AddAssign<Scalar> for Vector {
+  fn add_assign(&mut self, rhs: Scalar) {
     *self = *self + rhs
   }
 }
 
-impl Sub<f32> for Vector {
+impl Sub<Scalar> for Vector {
   type Output = Vector;
-  fn sub(self, rhs: f32) -> Self::Output {
+  fn sub(self, rhs: Scalar) -> Self::Output {
     Vector(self.0 - rhs, self.1 - rhs)
   }
 }
 
-impl SubAssign<f32> for Vector {
-  fn sub_assign(&mut self, rhs: f32) {
+impl SubAssign<Scalar> for Vector {
+  fn sub_assign(&mut self, rhs: Scalar) {
     *self = *self - rhs
   }
 }
 
-impl Mul<f32> for Vector {
+impl Mul<Scalar> for Vector {
   type Output = Vector;
-  fn mul(self, rhs: f32) -> Self::Output {
+  fn mul(self, rhs: Scalar) -> Self::Output {
     Vector(self.0 * rhs, self.1 * rhs)
   }
 }
 
-impl MulAssign<f32> for Vector {
-  fn mul_assign(&mut self, rhs: f32) {
+impl MulAssign<Scalar> for Vector {
+  fn mul_assign(&mut self, rhs: Scalar) {
     *self = *self * rhs
   }
 }
 
-impl Div<f32> for Vector {
+impl Div<Scalar> for Vector {
   type Output = Vector;
-  fn div(self, rhs: f32) -> Self::Output {
+  fn div(self, rhs: Scalar) -> Self::Output {
     Vector(self.0 / rhs, self.1 / rhs)
   }
 }
 
-impl DivAssign<f32> for Vector {
-  fn div_assign(&mut self, rhs: f32) {
+impl DivAssign<Scalar> for Vector {
+  fn div_assign(&mut self, rhs: Scalar) {
     *self = *self / rhs
   }
 }
 
-impl Rem<f32> for Vector {
+impl Rem<Scalar> for Vector {
   type Output = Vector;
-  fn rem(self, rhs: f32) -> Self::Output {
+  fn rem(self, rhs: Scalar) -> Self::Output {
     Vector(self.0 % rhs, self.1 % rhs)
   }
 }
 
-impl RemAssign<f32> for Vector {
-  fn rem_assign(&mut self, rhs: f32) {
+impl RemAssign<Scalar> for Vector {
+  fn rem_assign(&mut self, rhs: Scalar) {
     *self = *self % rhs
   }
 }
 
+
 //
 //
 //
 impl ViewBox for Vector {
-  fn angle(&self) -> f32 {
-    0.0
+  fn angle(&self) -> Scalar {
+    scalar(0.)
   }
 
   fn size(&self) -> Vector {
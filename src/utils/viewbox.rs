@@ -1,7 +1,7 @@
-use crate::utils::vector::Vector;
+use crate::utils::{scalar::{scalar, Scalar}, vector::Vector};
 
 pub trait ViewBox {
-  fn angle(&self) -> f32;
+  fn angle(&self) -> Scalar;
   fn size(&self) -> Vector;
   fn position(&self) -> Vector;
 
@@ -17,7 +17,7 @@ pub trait ViewBox {
       Vector::new(1., -1.),
       Vector::new(-1., -1.),
     ]
-    .map(|path| position + (size * path).rotate(angle));
+    .map(|path| position + (size * path * scalar(0.5)).rotate(angle));
 
     points
   }